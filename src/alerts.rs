@@ -0,0 +1,414 @@
+//! Per-person delivery of life safety alerts over each person's own channels (Slack direct
+//! message, email or webhook), honoring quiet hours and tracking acknowledgements.
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::json;
+use std::env;
+use std::sync::atomic::{AtomicI64, Ordering};
+use tokio::time::Duration as TokioDuration;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::db::alerts::AlertDelivery;
+use crate::db::core::Database;
+use crate::db::places::Person;
+use crate::email::send_email;
+use crate::geo::Impact;
+use crate::slack::send_slack_notice;
+use crate::threat::Urgency;
+use crate::TARGET_WEB_REQUEST;
+
+const ALERT_ACK_BASE_URL_ENV: &str = "ALERT_ACK_BASE_URL";
+
+/// Attempts per delivery before giving up.
+const MAX_ATTEMPTS: i64 = 5;
+
+/// How often workers look for alerts that are due.
+const DELIVERY_CHECK_INTERVAL_SECS: i64 = 30;
+
+/// Alerts still being sent after this long were claimed by a worker that died, and are
+/// sent again. Every channel gives up well before.
+const CLAIM_TIMEOUT_MINUTES: i64 = 10;
+
+/// How a person receives alerts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelKind {
+    /// Slack direct message; the address is a Slack user ID
+    Slack,
+    Email,
+    /// JSON POST to a URL
+    Webhook,
+}
+
+impl ChannelKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelKind::Slack => "slack",
+            ChannelKind::Email => "email",
+            ChannelKind::Webhook => "webhook",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "slack" => Some(ChannelKind::Slack),
+            "email" => Some(ChannelKind::Email),
+            "webhook" => Some(ChannelKind::Webhook),
+            _ => None,
+        }
+    }
+}
+
+/// A daily local time window, e.g. 22:00-07:00, in which only immediate alerts are sent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Parses "HH:MM-HH:MM".
+    pub fn parse(value: &str) -> Option<Self> {
+        let (start, end) = value.split_once('-')?;
+        let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?;
+        let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?;
+        (start != end).then_some(QuietHours { start, end })
+    }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Reads the UTC offset from a time zone description such as "CET (UTC+1)",
+/// "UTC-05:30" or "GMT+2".
+pub fn utc_offset(timezone: &str) -> Option<FixedOffset> {
+    static OFFSET_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?:UTC|GMT)\s*([+-])\s*(\d{1,2})(?::?(\d{2}))?").unwrap());
+    let captures = OFFSET_REGEX.captures(timezone)?;
+    let hours: i32 = captures[2].parse().ok()?;
+    let minutes: i32 = captures
+        .get(3)
+        .map_or(Some(0), |m| m.as_str().parse().ok())?;
+    let seconds = (hours * 60 + minutes) * 60;
+    if &captures[1] == "-" {
+        FixedOffset::west_opt(seconds)
+    } else {
+        FixedOffset::east_opt(seconds)
+    }
+}
+
+/// When an alert may be delivered to a person: now, unless it falls in their quiet hours
+/// and isn't immediate, in which case when their quiet hours end. Quiet hours are read in
+/// the person's time zone, or UTC if it has no recognizable offset.
+pub fn deliver_after(
+    now: DateTime<Utc>,
+    quiet_hours: Option<&str>,
+    timezone: Option<&str>,
+    urgency: Urgency,
+) -> DateTime<Utc> {
+    let Some(quiet_hours) = quiet_hours.and_then(QuietHours::parse) else {
+        return now;
+    };
+    if urgency == Urgency::Immediate {
+        return now;
+    }
+    let offset = timezone
+        .and_then(utc_offset)
+        .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+    let local = now.with_timezone(&offset);
+    if !quiet_hours.contains(local.time()) {
+        return now;
+    }
+    let mut end = local.date_naive().and_time(quiet_hours.end);
+    if end <= local.naive_local() {
+        end += Duration::days(1);
+    }
+    (end - offset).and_utc()
+}
+
+/// The acknowledgement link for an alert, if `ALERT_ACK_BASE_URL` is set.
+pub fn ack_url(ack_token: &str) -> Option<String> {
+    env::var(ALERT_ACK_BASE_URL_ENV)
+        .ok()
+        .map(|base| format!("{}/alerts/ack/{}", base.trim_end_matches('/'), ack_token))
+}
+
+/// A life safety alert to deliver to the people it affects.
+#[derive(Debug, Clone)]
+pub struct PersonAlert<'a> {
+    pub article_id: Option<i64>,
+    pub incident_id: Option<i64>,
+    pub urgency: Urgency,
+    pub subject: &'a str,
+    pub summary: &'a str,
+    pub url: Option<&'a str>,
+    /// What to tell people who are directly affected, e.g. advice to shelter
+    pub direct_details: &'a str,
+}
+
+impl PersonAlert<'_> {
+    fn body(&self, impact: Option<Impact>) -> String {
+        let mut body = self.summary.trim().to_string();
+        match impact {
+            Some(Impact::Direct) => {
+                body.push_str("\n\nYou are in the affected area.");
+                if !self.direct_details.trim().is_empty() {
+                    body.push_str("\n\n");
+                    body.push_str(self.direct_details.trim());
+                }
+            }
+            Some(Impact::Near) => body.push_str("\n\nYou are near the affected area."),
+            None => {}
+        }
+        if let Some(url) = self.url {
+            body.push_str("\n\n");
+            body.push_str(url);
+        }
+        body
+    }
+}
+
+/// Queues an alert for each affected person on each of their channels, returning how many
+/// deliveries were queued. `impact` is `None` for notices such as all-clears that don't
+/// depend on where someone is.
+pub async fn queue_person_alerts(
+    db: &Database,
+    alert: &PersonAlert<'_>,
+    recipients: &[(&Person, Option<Impact>)],
+) -> usize {
+    let now = Utc::now();
+    let mut queued = 0;
+    for (person, impact) in recipients {
+        let channels = match db.fetch_person_channels(person.id).await {
+            Ok(channels) => channels,
+            Err(e) => {
+                error!(target: TARGET_WEB_REQUEST, "Failed to fetch channels for person {}: {:?}", person.id, e);
+                continue;
+            }
+        };
+        if channels.is_empty() {
+            debug!(target: TARGET_WEB_REQUEST, "Person {} has no alert channels", person.id);
+            continue;
+        }
+
+        let deliver_after = deliver_after(
+            now,
+            person.quiet_hours.as_deref(),
+            person.timezone.as_deref(),
+            alert.urgency,
+        );
+        // Acknowledging on any channel acknowledges the alert on all of them
+        let ack_token = Uuid::new_v4().simple().to_string();
+        let body = alert.body(*impact);
+        for (i, channel) in channels.into_iter().enumerate() {
+            let delivery = AlertDelivery {
+                id: 0,
+                person_id: person.id,
+                article_id: alert.article_id,
+                incident_id: alert.incident_id,
+                kind: channel.kind,
+                address: channel.address,
+                urgency: alert.urgency.as_str().to_string(),
+                subject: alert.subject.to_string(),
+                body: body.clone(),
+                deliver_after,
+                attempts: 0,
+                ack_token: format!("{}-{}", ack_token, i),
+            };
+            match db.add_alert_delivery(&delivery).await {
+                Ok(_) => queued += 1,
+                Err(e) => {
+                    error!(target: TARGET_WEB_REQUEST, "Failed to queue alert for person {}: {:?}", person.id, e)
+                }
+            }
+        }
+        if deliver_after > now {
+            info!(target: TARGET_WEB_REQUEST, "Holding alert for person {} until their quiet hours end at {}", person.id, deliver_after);
+        }
+    }
+    queued
+}
+
+/// Queues an all-clear for everyone who was alerted about an incident.
+pub async fn queue_all_clear(db: &Database, incident_id: i64, subject: &str, reason: &str) {
+    let recipients = match db.fetch_incident_recipients(incident_id).await {
+        Ok(recipients) => recipients,
+        Err(e) => {
+            error!(target: TARGET_WEB_REQUEST, "Failed to fetch recipients of incident {}: {:?}", incident_id, e);
+            return;
+        }
+    };
+    if recipients.is_empty() {
+        return;
+    }
+    let people = match db.fetch_people().await {
+        Ok(people) => people,
+        Err(e) => {
+            error!(target: TARGET_WEB_REQUEST, "Failed to fetch people: {:?}", e);
+            return;
+        }
+    };
+    let recipients: Vec<(&Person, Option<Impact>)> = people
+        .iter()
+        .filter(|person| recipients.contains(&person.id))
+        .map(|person| (person, None))
+        .collect();
+    let alert = PersonAlert {
+        article_id: None,
+        incident_id: Some(incident_id),
+        urgency: Urgency::Routine,
+        subject,
+        summary: reason,
+        url: None,
+        direct_details: "",
+    };
+    queue_person_alerts(db, &alert, &recipients).await;
+}
+
+/// Sends alerts that are due, retrying failures with exponential backoff. Checks at most
+/// every 30 seconds however often it is called, unless `force` is set.
+pub async fn deliver_due_alerts(db: &Database, slack_token: &str, force: bool) {
+    static LAST_CHECK: AtomicI64 = AtomicI64::new(0);
+    let now = Utc::now().timestamp();
+    let last_check = LAST_CHECK.load(Ordering::Relaxed);
+    if !force
+        && (now - last_check < DELIVERY_CHECK_INTERVAL_SECS
+            || LAST_CHECK
+                .compare_exchange(last_check, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err())
+    {
+        return;
+    }
+
+    let before = Utc::now() - Duration::minutes(CLAIM_TIMEOUT_MINUTES);
+    match db.release_stale_alert_claims(before).await {
+        Ok(0) => {}
+        Ok(released) => {
+            warn!(target: TARGET_WEB_REQUEST, "Retrying {} alerts left unsent by a stopped worker", released)
+        }
+        Err(e) => {
+            error!(target: TARGET_WEB_REQUEST, "Failed to release stale alert claims: {:?}", e)
+        }
+    }
+
+    let deliveries = match db.fetch_due_alert_deliveries(100).await {
+        Ok(deliveries) => deliveries,
+        Err(e) => {
+            error!(target: TARGET_WEB_REQUEST, "Failed to fetch due alerts: {:?}", e);
+            return;
+        }
+    };
+
+    for delivery in deliveries {
+        match db.claim_alert_delivery(delivery.id).await {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                error!(target: TARGET_WEB_REQUEST, "Failed to claim alert {}: {:?}", delivery.id, e);
+                continue;
+            }
+        }
+
+        let result = send_alert(&delivery, slack_token).await;
+        let recorded = match &result {
+            Ok(()) => {
+                info!(target: TARGET_WEB_REQUEST, "Delivered {} alert {} to person {}", delivery.kind.as_str(), delivery.id, delivery.person_id);
+                db.mark_alert_delivered(delivery.id).await
+            }
+            Err(e) => {
+                let attempts = delivery.attempts + 1;
+                let retry_at = (attempts < MAX_ATTEMPTS)
+                    .then(|| Utc::now() + Duration::minutes(1 << attempts.min(10)));
+                warn!(target: TARGET_WEB_REQUEST, "Failed to deliver {} alert {} (attempt {}): {}", delivery.kind.as_str(), delivery.id, attempts, e);
+                db.mark_alert_failed(delivery.id, e, retry_at).await
+            }
+        };
+        if let Err(e) = recorded {
+            error!(target: TARGET_WEB_REQUEST, "Failed to record delivery of alert {}: {:?}", delivery.id, e);
+        }
+    }
+}
+
+async fn send_alert(delivery: &AlertDelivery, slack_token: &str) -> Result<(), String> {
+    let ack_url = ack_url(&delivery.ack_token);
+    match delivery.kind {
+        ChannelKind::Slack => {
+            let mut text = format!("*{}*\n{}", delivery.subject, delivery.body);
+            if let Some(ack_url) = &ack_url {
+                text.push_str(&format!("\n\n<{}|Acknowledge>", ack_url));
+            }
+            if send_slack_notice(&text, slack_token, &delivery.address).await {
+                Ok(())
+            } else {
+                Err("Slack did not accept the message".to_string())
+            }
+        }
+        ChannelKind::Email => {
+            let mut body = delivery.body.clone();
+            if let Some(ack_url) = &ack_url {
+                body.push_str(&format!("\n\nPlease acknowledge: {}", ack_url));
+            }
+            send_email(&delivery.address, &delivery.subject, &body).await
+        }
+        ChannelKind::Webhook => {
+            let payload = json!({
+                "person_id": delivery.person_id,
+                "article_id": delivery.article_id,
+                "incident_id": delivery.incident_id,
+                "urgency": delivery.urgency,
+                "subject": delivery.subject,
+                "body": delivery.body,
+                "ack_url": ack_url,
+            });
+            let response = reqwest::Client::new()
+                .post(&delivery.address)
+                .json(&payload)
+                .timeout(TokioDuration::from_secs(10))
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if response.status().is_success() {
+                Ok(())
+            } else {
+                Err(format!("Webhook returned {}", response.status()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_hours_defer_non_immediate_alerts() {
+        assert_eq!(utc_offset("CET (UTC+1)"), FixedOffset::east_opt(3600));
+        assert_eq!(utc_offset("IST (UTC+05:30)"), FixedOffset::east_opt(19800));
+        assert_eq!(utc_offset("EST (GMT-5)"), FixedOffset::west_opt(18000));
+        assert_eq!(utc_offset("Pacific"), None);
+
+        // 23:30 UTC is 00:30 in CET, inside 22:00-07:00, which ends at 06:00 UTC
+        let now = DateTime::parse_from_rfc3339("2025-03-10T23:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let quiet = Some("22:00-07:00");
+        let timezone = Some("CET (UTC+1)");
+        assert_eq!(
+            deliver_after(now, quiet, timezone, Urgency::Elevated).to_rfc3339(),
+            "2025-03-11T06:00:00+00:00"
+        );
+        assert_eq!(deliver_after(now, quiet, timezone, Urgency::Immediate), now);
+        assert_eq!(
+            deliver_after(now, Some("09:00-17:00"), timezone, Urgency::Routine),
+            now
+        );
+        assert_eq!(deliver_after(now, None, timezone, Urgency::Routine), now);
+    }
+}
@@ -0,0 +1,669 @@
+//! Admin API and dashboard, served by the app API under `/admin`.
+//!
+//! Every route requires `ADMIN_TOKEN`, as a bearer token or as the password of HTTP Basic
+//! authentication so browsers can open the dashboard. Without `ADMIN_TOKEN` the routes
+//! don't exist.
+
+use axum::extract::{ConnectInfo, Query};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use base64::Engine;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use tracing::{info, warn};
+
+use super::api::get_client_ip;
+use crate::db::admin::{
+    AliasReviews, ClusterMerge, FeedStatus, PipelineActivity, QueueDepths, RecentFailure,
+};
+use crate::db::core::Database;
+use crate::util::escape_html;
+use crate::workers::status::{self, WorkerStatus};
+
+const ADMIN_TOKEN_ENV: &str = "ADMIN_TOKEN";
+
+const DEFAULT_LIST_LIMIT: i64 = 50;
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Everything on the dashboard. Sections that failed to load are left out and their
+/// errors listed, e.g. cluster merges on a database without the merge history table.
+#[derive(Serialize)]
+pub struct AdminOverview {
+    generated_at: String,
+    queues: Option<QueueDepths>,
+    workers: Option<PipelineActivity>,
+    /// The last reported state of each decision and analysis worker
+    live_workers: Vec<WorkerStatus>,
+    failures: Option<Vec<RecentFailure>>,
+    alias_reviews: Option<AliasReviews>,
+    cluster_merges: Option<Vec<ClusterMerge>>,
+    feeds: Option<Vec<FeedStatus>>,
+    errors: Vec<String>,
+}
+
+/// A worker's last reported state, and whether it is stuck.
+#[derive(Serialize)]
+pub struct WorkerHealth {
+    #[serde(flatten)]
+    pub status: WorkerStatus,
+    pub stuck: bool,
+}
+
+/// The state of every worker and queue, behind the public health check.
+#[derive(Serialize)]
+pub struct HealthReport {
+    /// `ok`, or `degraded` when a worker is stuck or the queues can't be read
+    pub status: &'static str,
+    pub stuck_after_seconds: i64,
+    pub queues: Option<QueueDepths>,
+    pub workers: Vec<WorkerHealth>,
+}
+
+impl HealthReport {
+    /// 503 when degraded, so the health check can back a load balancer or uptime check.
+    pub fn status_code(&self) -> StatusCode {
+        if self.status == "ok" {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    }
+}
+
+/// Query parameters of the admin lists.
+#[derive(Deserialize)]
+struct ListQuery {
+    limit: Option<i64>,
+}
+
+impl ListQuery {
+    fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_LIST_LIMIT)
+            .clamp(1, MAX_LIST_LIMIT)
+    }
+}
+
+/// Why an admin request was refused.
+enum AdminError {
+    Unauthorized,
+    Database(sqlx::Error),
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        match self {
+            AdminError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Basic realm=\"Argus admin\"")],
+            )
+                .into_response(),
+            AdminError::Database(e) => {
+                warn!("Admin request failed: {:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+impl From<sqlx::Error> for AdminError {
+    fn from(e: sqlx::Error) -> Self {
+        AdminError::Database(e)
+    }
+}
+
+/// The admin routes, or none if `ADMIN_TOKEN` isn't set.
+pub fn router() -> Router {
+    if admin_token().is_none() {
+        info!("{} is not set, the admin API is disabled", ADMIN_TOKEN_ENV);
+        return Router::new();
+    }
+    Router::new()
+        .route("/admin", get(dashboard))
+        .route("/admin/api/overview", get(overview))
+        .route("/admin/api/health", get(health))
+        .route("/admin/api/queues", get(queues))
+        .route("/admin/api/workers", get(workers))
+        .route("/admin/api/failures", get(failures))
+        .route("/admin/api/aliases", get(alias_reviews))
+        .route("/admin/api/merges", get(cluster_merges))
+        .route("/admin/api/feeds", get(feeds))
+}
+
+fn admin_token() -> Option<String> {
+    env::var(ADMIN_TOKEN_ENV)
+        .ok()
+        .filter(|token| !token.is_empty())
+}
+
+/// Checks a bearer token, or the password of HTTP Basic authentication, against the
+/// admin token in constant time.
+fn is_authorized(headers: &HeaderMap, admin_token: &str) -> bool {
+    let Some(value) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return false;
+    };
+    let presented = if let Some(token) = value.strip_prefix("Bearer ") {
+        token.trim().to_string()
+    } else if let Some(credentials) = value.strip_prefix("Basic ") {
+        let Some(decoded) = base64::engine::general_purpose::STANDARD
+            .decode(credentials.trim())
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+        else {
+            return false;
+        };
+        match decoded.split_once(':') {
+            Some((_, password)) => password.to_string(),
+            None => return false,
+        }
+    } else {
+        return false;
+    };
+
+    presented.len() == admin_token.len()
+        && presented
+            .bytes()
+            .zip(admin_token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn authorize(addr: &SocketAddr, headers: &HeaderMap, route: &str) -> Result<(), AdminError> {
+    let client_ip = get_client_ip(headers, addr);
+    info!("app::admin {} request from IP {}", route, client_ip);
+    match admin_token() {
+        Some(token) if is_authorized(headers, &token) => Ok(()),
+        _ => {
+            warn!("Unauthorized admin request from IP {}", client_ip);
+            Err(AdminError::Unauthorized)
+        }
+    }
+}
+
+/// Keeps a section of the dashboard, or notes why it couldn't be loaded.
+fn section<T>(errors: &mut Vec<String>, name: &str, result: Result<T, sqlx::Error>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!("Failed to load admin section {}: {:?}", name, e);
+            errors.push(format!("{}: {}", name, e));
+            None
+        }
+    }
+}
+
+/// Loads every section of the dashboard.
+pub async fn load_overview(db: &Database) -> AdminOverview {
+    let mut errors = Vec::new();
+    AdminOverview {
+        generated_at: Utc::now().to_rfc3339(),
+        queues: section(&mut errors, "queues", db.queue_depths().await),
+        workers: section(&mut errors, "workers", db.pipeline_activity().await),
+        live_workers: status::snapshot(),
+        failures: section(
+            &mut errors,
+            "failures",
+            db.recent_failures(DEFAULT_LIST_LIMIT).await,
+        ),
+        alias_reviews: section(
+            &mut errors,
+            "alias reviews",
+            db.alias_reviews(DEFAULT_LIST_LIMIT).await,
+        ),
+        cluster_merges: section(
+            &mut errors,
+            "cluster merges",
+            db.recent_cluster_merges(DEFAULT_LIST_LIMIT).await,
+        ),
+        feeds: section(&mut errors, "feeds", db.feed_health().await),
+        errors,
+    }
+}
+
+/// Checks every decision and analysis worker for progress and counts the queues, warning
+/// about stuck workers.
+pub async fn health_report(db: &Database) -> HealthReport {
+    let queues = db
+        .queue_depths()
+        .await
+        .inspect_err(|e| warn!("app::admin health_report failed to count queues: {:?}", e))
+        .ok();
+
+    let now = Utc::now();
+    let stuck_after_seconds = status::stuck_threshold();
+    let workers: Vec<WorkerHealth> = status::snapshot()
+        .into_iter()
+        .map(|status| WorkerHealth {
+            stuck: status.is_stuck(now, stuck_after_seconds),
+            status,
+        })
+        .collect();
+
+    let degraded = queues.is_none() || workers.iter().any(|worker| worker.stuck);
+    for worker in workers.iter().filter(|worker| worker.stuck) {
+        warn!(
+            "app::admin health_report: {} {} stuck since {} on {:?}",
+            worker.status.name,
+            worker.status.id,
+            worker.status.last_activity,
+            worker.status.current_article
+        );
+    }
+
+    HealthReport {
+        status: if degraded { "degraded" } else { "ok" },
+        stuck_after_seconds,
+        queues,
+        workers,
+    }
+}
+
+/// Serves the dashboard, which reloads itself every 30 seconds.
+async fn dashboard(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Html<String>, AdminError> {
+    authorize(&addr, &headers, "dashboard")?;
+    let db = Database::instance().await;
+    Ok(Html(render_dashboard(&load_overview(db).await)))
+}
+
+/// Returns every section of the dashboard as JSON.
+async fn overview(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<AdminOverview>, AdminError> {
+    authorize(&addr, &headers, "overview")?;
+    let db = Database::instance().await;
+    Ok(Json(load_overview(db).await))
+}
+
+/// Returns the state of every worker and queue, with the public health check's status code.
+async fn health(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<HealthReport>), AdminError> {
+    authorize(&addr, &headers, "health")?;
+    let db = Database::instance().await;
+    let report = health_report(db).await;
+    Ok((report.status_code(), Json(report)))
+}
+
+/// Returns the number of entries waiting in each queue.
+async fn queues(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<QueueDepths>, AdminError> {
+    authorize(&addr, &headers, "queues")?;
+    let db = Database::instance().await;
+    Ok(Json(db.queue_depths().await?))
+}
+
+/// Returns what the workers got done in the last hour.
+async fn workers(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<PipelineActivity>, AdminError> {
+    authorize(&addr, &headers, "workers")?;
+    let db = Database::instance().await;
+    Ok(Json(db.pipeline_activity().await?))
+}
+
+/// Returns the latest notifications and personal alerts that failed for good.
+async fn failures(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(params): Query<ListQuery>,
+) -> Result<Json<Vec<RecentFailure>>, AdminError> {
+    authorize(&addr, &headers, "failures")?;
+    let db = Database::instance().await;
+    Ok(Json(db.recent_failures(params.limit()).await?))
+}
+
+/// Returns the alias review backlog.
+async fn alias_reviews(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(params): Query<ListQuery>,
+) -> Result<Json<AliasReviews>, AdminError> {
+    authorize(&addr, &headers, "aliases")?;
+    let db = Database::instance().await;
+    Ok(Json(db.alias_reviews(params.limit()).await?))
+}
+
+/// Returns the latest cluster merges.
+async fn cluster_merges(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(params): Query<ListQuery>,
+) -> Result<Json<Vec<ClusterMerge>>, AdminError> {
+    authorize(&addr, &headers, "merges")?;
+    let db = Database::instance().await;
+    Ok(Json(db.recent_cluster_merges(params.limit()).await?))
+}
+
+/// Returns the latest fetch outcome of every feed, failing feeds first.
+async fn feeds(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<FeedStatus>>, AdminError> {
+    authorize(&addr, &headers, "feeds")?;
+    let db = Database::instance().await;
+    Ok(Json(db.feed_health().await?))
+}
+
+/// Appends an HTML table; cells are escaped.
+fn table(html: &mut String, headings: &[&str], rows: Vec<Vec<String>>) {
+    if rows.is_empty() {
+        html.push_str("<p class=\"empty\">Nothing here.</p>\n");
+        return;
+    }
+    html.push_str("<table>\n<tr>");
+    for heading in headings {
+        let _ = write!(html, "<th>{}</th>", escape_html(heading));
+    }
+    html.push_str("</tr>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            let _ = write!(html, "<td>{}</td>", escape_html(&cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+}
+
+fn or_never(time: &Option<String>) -> String {
+    time.clone().unwrap_or_else(|| "never".to_string())
+}
+
+/// Renders the dashboard as a self-contained HTML page.
+pub fn render_dashboard(overview: &AdminOverview) -> String {
+    let mut html = String::from(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta http-equiv="refresh" content="30">
+<title>Argus admin</title>
+<style>
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.4em; } h2 { font-size: 1.1em; margin-top: 2em; }
+table { border-collapse: collapse; font-size: 0.9em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f3f3f3; }
+.empty, .generated { color: #777; }
+.errors { color: #a00; }
+</style>
+</head>
+<body>
+<h1>Argus admin</h1>
+"#,
+    );
+    let _ = writeln!(
+        html,
+        "<p class=\"generated\">Generated at {}</p>",
+        escape_html(&overview.generated_at)
+    );
+
+    if !overview.errors.is_empty() {
+        html.push_str("<ul class=\"errors\">\n");
+        for error in &overview.errors {
+            let _ = writeln!(html, "<li>{}</li>", escape_html(error));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    if let Some(queues) = &overview.queues {
+        html.push_str("<h2>Queues</h2>\n");
+        table(
+            &mut html,
+            &["Queue", "Entries"],
+            vec![
+                vec!["RSS".to_string(), queues.rss_queue.to_string()],
+                vec![
+                    "Matched topics".to_string(),
+                    queues.matched_topics_queue.to_string(),
+                ],
+                vec![
+                    "Life safety".to_string(),
+                    queues.life_safety_queue.to_string(),
+                ],
+                vec![
+                    "Notification batches".to_string(),
+                    queues.notification_batches.to_string(),
+                ],
+                vec![
+                    "Pending alerts".to_string(),
+                    queues.pending_alert_deliveries.to_string(),
+                ],
+            ],
+        );
+    }
+
+    if let Some(workers) = &overview.workers {
+        html.push_str("<h2>Workers</h2>\n");
+        table(
+            &mut html,
+            &["Activity", "Value"],
+            vec![
+                vec![
+                    "Articles in the last hour".to_string(),
+                    workers.articles_last_hour.to_string(),
+                ],
+                vec![
+                    "Analyses in the last hour".to_string(),
+                    workers.analyses_last_hour.to_string(),
+                ],
+                vec![
+                    "Last article".to_string(),
+                    or_never(&workers.last_article_at),
+                ],
+                vec![
+                    "Last analysis".to_string(),
+                    or_never(&workers.last_analysis_at),
+                ],
+            ],
+        );
+    }
+
+    if !overview.live_workers.is_empty() {
+        let now = Utc::now();
+        let stuck_after = status::stuck_threshold();
+        html.push_str("<h2>Worker status</h2>\n");
+        table(
+            &mut html,
+            &["Worker", "Mode", "Model", "Article", "Last activity"],
+            overview
+                .live_workers
+                .iter()
+                .map(|worker| {
+                    let stuck = if worker.is_stuck(now, stuck_after) {
+                        " (stuck)"
+                    } else {
+                        ""
+                    };
+                    vec![
+                        format!("{} {}", worker.name, worker.id),
+                        format!("{:?}", worker.mode),
+                        worker.model.clone(),
+                        worker.current_article.clone().unwrap_or_default(),
+                        format!("{}{}", worker.last_activity.to_rfc3339(), stuck),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    if let Some(feeds) = &overview.feeds {
+        html.push_str("<h2>Feeds</h2>\n");
+        table(
+            &mut html,
+            &[
+                "Feed",
+                "Last fetched",
+                "Last success",
+                "Failures in a row",
+                "New articles",
+                "Last error",
+            ],
+            feeds
+                .iter()
+                .map(|feed| {
+                    vec![
+                        feed.url.clone(),
+                        or_never(&feed.last_fetched_at),
+                        or_never(&feed.last_success_at),
+                        feed.consecutive_failures.to_string(),
+                        feed.last_new_articles.to_string(),
+                        feed.last_error.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    if let Some(failures) = &overview.failures {
+        html.push_str("<h2>Recent failures</h2>\n");
+        table(
+            &mut html,
+            &["At", "Kind", "Target", "Attempts", "Error"],
+            failures
+                .iter()
+                .map(|failure| {
+                    vec![
+                        failure.at.clone(),
+                        failure.kind.clone(),
+                        failure.target.clone(),
+                        failure.attempts.to_string(),
+                        failure.error.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    if let Some(reviews) = &overview.alias_reviews {
+        let _ = writeln!(
+            html,
+            "<h2>Alias reviews</h2>\n<p>{} pending, {} open review batches</p>",
+            reviews.pending, reviews.open_batches
+        );
+        table(
+            &mut html,
+            &[
+                "ID",
+                "Type",
+                "Canonical name",
+                "Alias",
+                "Source",
+                "Confidence",
+            ],
+            reviews
+                .aliases
+                .iter()
+                .map(|alias| {
+                    vec![
+                        alias.id.to_string(),
+                        alias.entity_type.clone(),
+                        alias.canonical_name.clone(),
+                        alias.alias_text.clone(),
+                        alias.source.clone(),
+                        format!("{:.2}", alias.confidence),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    if let Some(merges) = &overview.cluster_merges {
+        html.push_str("<h2>Cluster merges</h2>\n");
+        table(
+            &mut html,
+            &["Date", "Cluster", "Merged into", "Reason"],
+            merges
+                .iter()
+                .map(|merge| {
+                    vec![
+                        merge.merge_date.clone(),
+                        merge.original_cluster_id.to_string(),
+                        merge.merged_into_cluster_id.to_string(),
+                        merge.merge_reason.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(authorization: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(authorization).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_is_authorized() {
+        assert!(is_authorized(&headers("Bearer s3cret"), "s3cret"));
+        assert!(!is_authorized(&headers("Bearer s3cre"), "s3cret"));
+        assert!(!is_authorized(&HeaderMap::new(), "s3cret"));
+
+        // Any user name, with the token as password
+        let basic = base64::engine::general_purpose::STANDARD.encode("admin:s3cret");
+        assert!(is_authorized(
+            &headers(&format!("Basic {}", basic)),
+            "s3cret"
+        ));
+        let basic = base64::engine::general_purpose::STANDARD.encode("s3cret");
+        assert!(!is_authorized(
+            &headers(&format!("Basic {}", basic)),
+            "s3cret"
+        ));
+    }
+
+    #[test]
+    fn test_render_dashboard_escapes() {
+        let overview = AdminOverview {
+            generated_at: "now".to_string(),
+            queues: None,
+            workers: None,
+            live_workers: Vec::new(),
+            failures: Some(vec![RecentFailure {
+                kind: "notification".to_string(),
+                target: "slack: https://example.com/?a=1&b=2".to_string(),
+                error: Some("<script>alert(1)</script>".to_string()),
+                attempts: 3,
+                at: "2025-06-01T00:00:00Z".to_string(),
+            }]),
+            alias_reviews: None,
+            cluster_merges: None,
+            feeds: Some(Vec::new()),
+            errors: vec!["cluster merges: no such table".to_string()],
+        };
+        let html = render_dashboard(&overview);
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(html.contains("?a=1&amp;b=2"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("no such table"));
+        assert!(html.contains("Nothing here."));
+    }
+}
@@ -1,24 +1,36 @@
 use anyhow::Result;
-use axum::extract::{ConnectInfo, Json};
-use axum::http::{HeaderMap, StatusCode};
-use axum::{routing::post, Router};
+use axum::extract::{ConnectInfo, Json, Path, Query};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use axum::{
+    routing::{get, post},
+    Router,
+};
 use axum_extra::extract::TypedHeader;
 use axum_extra::headers::{authorization::Bearer, Authorization};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
-use once_cell::sync::Lazy;
-use ring::rand::{SecureRandom, SystemRandom};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use futures::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
 use std::net::{IpAddr, SocketAddr};
-use std::{
-    collections::{HashMap, HashSet},
-    sync::Mutex,
-};
 use tokio::net::TcpListener;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
+use crate::app::push::{self, Platform, WebPushKeys};
+use crate::app::{admin, auth};
+use crate::db::admin::QueueDepths;
+use crate::db::article_query::{ArticleFilter, ArticleRecord};
+use crate::db::cluster::ClusterPreference;
 use crate::db::core::Database;
+use crate::db::events::EventFilter;
 use crate::entity::matching::calculate_entity_similarity;
-use crate::vector::search::get_article_entities;
+use crate::events::{self, EventKind};
+use crate::metrics;
+use crate::search::{self, SearchOptions, SearchResult, SemanticResults};
+use crate::slack;
+use crate::topics;
+use crate::vector::search::{get_article_entities, SemanticFilter};
 use crate::SubscriptionsResponse;
 
 /// Request for syncing clusters
@@ -52,9 +64,17 @@ struct ClusterData {
     article_count: i32,
     importance_score: f64,
     has_timeline: bool,
+    /// Whether the device follows the cluster's story updates
+    followed: bool,
     articles: Vec<ArticleBrief>,
 }
 
+/// Response for the cluster preferences endpoint
+#[derive(Serialize)]
+struct ClusterPreferencesResponse {
+    preferences: Vec<ClusterPreference>,
+}
+
 /// Brief article data for cluster listings
 #[derive(Serialize)]
 pub struct ArticleBrief {
@@ -72,23 +92,35 @@ struct ClusterMergeInfo {
     merged_into_id: i64,
 }
 
-/// Represents the response for an authentication request, containing a JWT token.
+/// Represents the response for an authentication request, containing a JWT token and a
+/// refresh token to get the next one with.
 #[derive(Serialize)]
 struct AuthResponse {
     token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+    /// Seconds until `token` expires
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_in: Option<i64>,
 }
 
-/// Represents the claims stored in a JWT token.
-#[derive(Serialize, Deserialize)]
-struct Claims {
-    sub: String, // Subject (e.g., device ID)
-    exp: usize,  // Expiration time (as a timestamp)
+/// Represents the request payload for authentication, containing a device ID: an APNs
+/// device token, an FCM registration token or a web push endpoint.
+#[derive(Deserialize)]
+struct AuthRequest {
+    device_id: String,
+    /// `ios` (the default), `android` or `web`
+    platform: Option<String>,
+    /// The subscription keys of a web push endpoint
+    keys: Option<WebPushKeys>,
 }
 
-/// Represents the request payload for authentication, containing a device ID.
+/// Represents the request payload for refreshing a JWT token. The refresh token must have
+/// been issued to the same device.
 #[derive(Deserialize)]
-struct AuthRequest {
+struct RefreshRequest {
     device_id: String,
+    refresh_token: String,
 }
 
 /// Represents the request payload for topic subscription and unsubscription.
@@ -142,39 +174,79 @@ struct ArticleMatchAnalysisResponse {
     reason_for_failure: Option<String>,
 }
 
-/// Static private key used for encoding and decoding JWT tokens.
-static PRIVATE_KEY: Lazy<Mutex<Vec<u8>>> = Lazy::new(|| {
-    let rng = SystemRandom::new();
-    let mut key_bytes = vec![0u8; 32]; // 256-bit key for HMAC
-    rng.fill(&mut key_bytes)
-        .expect("Failed to generate secure random bytes");
-    Mutex::new(key_bytes)
-});
-
-/// Static encoding key for generating JWT tokens.
-static ENCODING_KEY: Lazy<EncodingKey> = Lazy::new(|| {
-    let key = PRIVATE_KEY.lock().unwrap();
-    EncodingKey::from_secret(&key)
-});
-
-/// Static decoding key for validating JWT tokens.
-static DECODING_KEY: Lazy<DecodingKey> = Lazy::new(|| {
-    let key = PRIVATE_KEY.lock().unwrap();
-    DecodingKey::from_secret(&key)
-});
-
-/// Static set of valid topics parsed from an environment variable.
-static VALID_TOPICS: Lazy<HashSet<String>> = Lazy::new(|| {
-    let mut topics = std::env::var("TOPICS")
-        .unwrap_or_default()
-        .lines()
-        .filter_map(|line| line.split(':').next().map(str::trim).map(String::from))
-        .collect::<HashSet<String>>();
-    topics.insert("Alert: Direct".to_string());
-    topics.insert("Alert: Near".to_string());
-    topics.insert("Test".to_string());
-    topics
-});
+/// Query parameters for listing articles. Dates are RFC 3339 or YYYY-MM-DD; `until` is
+/// inclusive of a bare date.
+#[derive(Deserialize)]
+struct ArticleListQuery {
+    topic: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    min_quality: Option<i64>,
+    domain: Option<String>,
+    cluster_id: Option<i64>,
+    /// `next_cursor` of the previous page
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+/// Query parameters of the event stream. Lists are comma separated.
+#[derive(Deserialize)]
+struct EventStreamQuery {
+    /// Event types: analysis, life_safety_alert or cluster_update
+    types: Option<String>,
+    topics: Option<String>,
+    /// Resume after this event; the `Last-Event-ID` header takes precedence
+    last_event_id: Option<i64>,
+}
+
+/// A page of articles, newest first.
+#[derive(Serialize)]
+struct ArticleListResponse {
+    articles: Vec<ArticleRecord>,
+    /// Pass as `cursor` to get the next page; absent on the last page
+    next_cursor: Option<String>,
+}
+
+/// Query parameters for a keyword search. Setting `semantic_weight` makes it a hybrid
+/// search.
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+    topic: Option<String>,
+    limit: Option<usize>,
+    semantic_weight: Option<f64>,
+    /// Pass `q` to FTS5 as is
+    #[serde(default)]
+    raw: bool,
+}
+
+/// Search results, best first.
+#[derive(Serialize)]
+struct SearchResponse {
+    results: Vec<SearchResult>,
+}
+
+/// Request for a semantic search. Dates are RFC 3339 or YYYY-MM-DD, on the publication
+/// date; `until` is inclusive of a bare date.
+#[derive(Deserialize)]
+struct SemanticSearchRequest {
+    query: String,
+    since: Option<String>,
+    until: Option<String>,
+    #[serde(default)]
+    categories: Vec<String>,
+    min_quality: Option<i8>,
+    /// Minimum similarity to the query (0.0 to 1.0)
+    min_score: Option<f32>,
+    limit: Option<usize>,
+}
+
+const SLACK_SIGNING_SECRET_ENV: &str = "SLACK_SIGNING_SECRET";
+
+const DEFAULT_ARTICLE_PAGE_SIZE: i64 = 50;
+const MAX_ARTICLE_PAGE_SIZE: i64 = 200;
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+const MAX_SEARCH_LIMIT: usize = 100;
 
 /// Analyze the matching between two specific articles to understand why they
 /// match or don't match. This is a diagnostic endpoint for tuning the matching algorithm.
@@ -186,9 +258,7 @@ async fn analyze_article_match(
 ) -> Result<Json<ArticleMatchAnalysisResponse>, StatusCode> {
     // Validate the JWT token
     let token = auth_header.token();
-    if decode::<Claims>(token, &DECODING_KEY, &Validation::new(Algorithm::HS256)).is_err() {
-        return Err(StatusCode::UNAUTHORIZED);
-    }
+    auth::authorize(token).await?;
 
     info!(
         "Analyzing match between articles {} and {}",
@@ -396,9 +466,7 @@ async fn sync_clusters(
 ) -> Result<Json<SyncClustersResponse>, StatusCode> {
     // Validate the JWT token
     let token = auth_header.token();
-    if decode::<Claims>(token, &DECODING_KEY, &Validation::new(Algorithm::HS256)).is_err() {
-        return Err(StatusCode::UNAUTHORIZED);
-    }
+    let claims = auth::authorize(token).await?;
 
     let client_ip = get_client_ip(&headers, &addr);
     info!("app::api sync_clusters request from IP {}", client_ip);
@@ -411,6 +479,21 @@ async fn sync_clusters(
         known_clusters.insert(info.id, info.version);
     }
 
+    // Silenced clusters are left out; followed ones are flagged, and the versions the
+    // client has are recorded so story updates aren't pushed for them
+    let preferences: HashMap<i64, ClusterPreference> =
+        crate::db::cluster::get_cluster_preferences(db, &claims.sub)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .into_iter()
+            .map(|preference| (preference.cluster_id, preference))
+            .collect();
+    if let Err(e) =
+        crate::db::cluster::record_seen_cluster_versions(db, &claims.sub, &known_clusters).await
+    {
+        warn!("Failed to record seen cluster versions: {:?}", e);
+    }
+
     // Get all active clusters
     let active_clusters = crate::db::cluster::get_active_clusters(&db)
         .await
@@ -423,6 +506,11 @@ async fn sync_clusters(
         let id = cluster.id;
         let version = cluster.summary_version;
 
+        let preference = preferences.get(&id);
+        if preference.is_some_and(|preference| preference.silenced) {
+            continue;
+        }
+
         let known_version = known_clusters.get(&id).copied().unwrap_or(-1);
 
         // If client doesn't know this cluster or has an outdated version
@@ -440,6 +528,7 @@ async fn sync_clusters(
                 article_count: cluster.article_count,
                 importance_score: cluster.importance_score,
                 has_timeline: cluster.has_timeline,
+                followed: preference.is_some_and(|preference| preference.followed),
                 articles,
             };
 
@@ -488,15 +577,35 @@ async fn sync_clusters(
 
 /// Main application loop, setting up and running the Axum-based API server.
 pub async fn app_api_loop() -> Result<()> {
+    auth::init_keys();
+    let db = Database::instance().await;
+    if let Err(e) = db.prune_auth_tokens(auth::ACCESS_TOKEN_SECONDS).await {
+        warn!("Failed to prune expired auth tokens: {:?}", e);
+    }
+
     let app = Router::new()
         .route("/status", post(status_check))
+        .route("/status/health", get(health_check))
+        .route("/metrics", get(metrics_export))
         .route("/authenticate", post(authenticate))
+        .route("/authenticate/refresh", post(refresh_token))
+        .route("/authenticate/revoke", post(revoke_token))
         .route("/subscriptions", post(get_subscriptions))
         .route("/subscribe", post(subscribe_to_topic))
         .route("/unsubscribe", post(unsubscribe_from_topic))
+        .route("/articles", get(list_articles))
+        .route("/articles/{id}", get(get_article))
         .route("/articles/sync", post(sync_seen_articles))
+        .route("/events", get(stream_events))
+        .route("/search", get(search_articles))
+        .route("/search/semantic", post(semantic_search))
         .route("/articles/analyze-match", post(analyze_article_match))
-        .route("/clusters/sync", post(sync_clusters));
+        .route("/clusters/sync", post(sync_clusters))
+        .route("/clusters/preferences", get(get_cluster_preferences))
+        .route("/clusters/{id}/{action}", post(set_cluster_preference))
+        .route("/alerts/ack/{token}", get(acknowledge_alert))
+        .route("/slack/interactions", post(slack_interaction))
+        .merge(admin::router());
 
     let port: u16 = std::env::var("PORT")
         .ok()
@@ -521,7 +630,7 @@ pub async fn app_api_loop() -> Result<()> {
 }
 
 // Helper to extract proxied client address.
-fn get_client_ip(headers: &HeaderMap, socket_addr: &SocketAddr) -> String {
+pub(crate) fn get_client_ip(headers: &HeaderMap, socket_addr: &SocketAddr) -> String {
     let ip_from_headers = headers
         .get("CF-Connecting-IP")
         .and_then(|hv| hv.to_str().ok())
@@ -566,23 +675,129 @@ async fn authenticate(
         warn!("Failed to log IP address: {:?}", e);
     }
 
-    // Basic validation for iOS device token
-    if payload.device_id.len() != 64 || !payload.device_id.chars().all(|c| c.is_ascii_hexdigit()) {
-        tracing::error!("Invalid iOS device token format: {}", payload.device_id);
-        return Json(AuthResponse {
-            token: "Invalid device token".to_string(),
-        });
+    // Basic validation for the device token of the platform
+    let platform = match payload.platform.as_deref() {
+        None => Ok(Platform::Ios),
+        Some(name) => Platform::from_name(name).ok_or("unknown platform"),
+    };
+    let platform = match platform.and_then(|platform| {
+        push::validate_device(platform, &payload.device_id, payload.keys.as_ref()).map(|_| platform)
+    }) {
+        Ok(platform) => platform,
+        Err(reason) => {
+            tracing::error!(
+                "Invalid device token format: {} ({})",
+                payload.device_id,
+                reason
+            );
+            return Json(AuthResponse {
+                token: "Invalid device token".to_string(),
+                refresh_token: None,
+                expires_in: None,
+            });
+        }
+    };
+    let push_keys = payload
+        .keys
+        .as_ref()
+        .filter(|_| platform == Platform::Web)
+        .and_then(|keys| serde_json::to_string(keys).ok());
+    if let Err(e) = db
+        .register_device(&payload.device_id, platform.as_str(), push_keys.as_deref())
+        .await
+    {
+        warn!("Failed to register device: {:?}", e);
     }
 
-    let claims = Claims {
-        sub: payload.device_id.clone(),
-        exp: (chrono::Utc::now() + chrono::Duration::hours(1)).timestamp() as usize,
+    let token = auth::issue_access_token(&payload.device_id).expect("Failed to encode JWT");
+    let refresh_token = match auth::issue_refresh_token(db, &payload.device_id).await {
+        Ok(refresh_token) => Some(refresh_token),
+        Err(e) => {
+            warn!("Failed to issue refresh token: {:?}", e);
+            None
+        }
     };
 
-    let token = encode(&Header::new(Algorithm::HS256), &claims, &ENCODING_KEY)
-        .expect("Failed to encode JWT");
+    Json(AuthResponse {
+        token,
+        refresh_token,
+        expires_in: Some(auth::ACCESS_TOKEN_SECONDS),
+    })
+}
+
+/// Exchanges a refresh token for a new JWT token and a new refresh token. Each refresh
+/// token works once, and only for the device it was issued to.
+async fn refresh_token(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<RefreshRequest>,
+) -> Result<Json<AuthResponse>, StatusCode> {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!(
+        "Refreshing token for device_id: {} from IP: {}",
+        payload.device_id, client_ip,
+    );
+
+    let db = Database::instance().await;
+    let token_hash = auth::hash_refresh_token(&payload.refresh_token);
+    match db.take_refresh_token(&token_hash, &payload.device_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            warn!(
+                "Invalid or expired refresh token for device_id: {}",
+                payload.device_id
+            );
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        Err(e) => {
+            warn!("Failed to look up refresh token: {:?}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
 
-    Json(AuthResponse { token })
+    let token = auth::issue_access_token(&payload.device_id).map_err(|e| {
+        warn!("Failed to encode JWT: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let refresh_token = auth::issue_refresh_token(db, &payload.device_id)
+        .await
+        .map_err(|e| {
+            warn!("Failed to issue refresh token: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(AuthResponse {
+        token,
+        refresh_token: Some(refresh_token),
+        expires_in: Some(auth::ACCESS_TOKEN_SECONDS),
+    }))
+}
+
+/// Signs a device out: revokes the JWT token it presents and every refresh token issued
+/// to it.
+async fn revoke_token(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+) -> Result<StatusCode, StatusCode> {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!("app::api revoke_token request from IP {}", client_ip);
+    let claims = auth::authorize(auth_header.token()).await?;
+
+    let db = Database::instance().await;
+    let revoked = async {
+        db.revoke_token(&claims.jti, &claims.sub, claims.exp as i64)
+            .await?;
+        db.revoke_refresh_tokens(&claims.sub).await?;
+        db.prune_auth_tokens(auth::ACCESS_TOKEN_SECONDS).await
+    };
+    match revoked.await {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => {
+            warn!("Failed to revoke token of {}: {:?}", claims.sub, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
 }
 
 /// Subscribes a device to a topic after validating the JWT and topic validity.
@@ -599,15 +814,13 @@ async fn subscribe_to_topic(
     );
 
     let token = auth_header.token();
-    let claims = decode::<Claims>(token, &DECODING_KEY, &Validation::new(Algorithm::HS256))
-        .map_err(|e| {
-            warn!(
-                "app::api subscribe_to_topic JWT validation failed: {:#?}",
-                e
-            );
-            StatusCode::UNAUTHORIZED
-        })?;
-    let device_id = claims.claims.sub;
+    let claims = auth::authorize(token).await.inspect_err(|e| {
+        warn!(
+            "app::api subscribe_to_topic JWT validation failed: {:#?}",
+            e
+        );
+    })?;
+    let device_id = claims.sub;
     info!(
         "app::api subscribe_to_topic validated JWT for device_id: {}",
         device_id
@@ -620,7 +833,7 @@ async fn subscribe_to_topic(
     }
 
     // Validate the provided topic
-    if !VALID_TOPICS.contains(&payload.topic) {
+    if !topics::is_valid_subscription_topic(&payload.topic) {
         warn!(
             "app::api subscribe_to_topic invalid topic: {}",
             payload.topic
@@ -668,16 +881,14 @@ async fn unsubscribe_from_topic(
     );
     let token = auth_header.token();
 
-    let claims = decode::<Claims>(token, &DECODING_KEY, &Validation::new(Algorithm::HS256))
-        .map_err(|e| {
-            warn!(
-                "app::api unsubscribe_from_topic JWT validation failed: {:#?}",
-                e
-            );
-            StatusCode::UNAUTHORIZED
-        })?;
+    let claims = auth::authorize(token).await.inspect_err(|e| {
+        warn!(
+            "app::api unsubscribe_from_topic JWT validation failed: {:#?}",
+            e
+        );
+    })?;
 
-    let device_id = claims.claims.sub;
+    let device_id = claims.sub;
     info!(
         "app::api unsubscribe_from_topic validated JWT for device_id: {}",
         device_id
@@ -690,7 +901,7 @@ async fn unsubscribe_from_topic(
     }
 
     // Validate the provided topic
-    if !VALID_TOPICS.contains(&payload.topic) {
+    if !topics::is_valid_subscription_topic(&payload.topic) {
         warn!(
             "app::api unsubscribe_from_topic invalid topic: {}",
             payload.topic
@@ -730,7 +941,7 @@ async fn status_check(
     info!("app::api status_check request from IP {} ", client_ip,);
     if let Some(TypedHeader(auth_header)) = auth_header {
         let token = auth_header.token();
-        if decode::<Claims>(token, &DECODING_KEY, &Validation::new(Algorithm::HS256)).is_ok() {
+        if auth::authorize(token).await.is_ok() {
             info!("Valid JWT provided for status check");
             return Ok("OK");
         } else {
@@ -743,6 +954,145 @@ async fn status_check(
     Ok("OK")
 }
 
+/// Response for the public health check. Only counts are public; the state of each
+/// worker and queue is at `/admin/api/health`.
+#[derive(Serialize)]
+struct HealthResponse {
+    /// `ok`, or `degraded` when a worker is stuck or the queues can't be read
+    status: &'static str,
+    workers: usize,
+    stuck_workers: usize,
+    /// Entries waiting in all queues, if they could be counted
+    queued: Option<i64>,
+}
+
+/// Reports whether every decision and analysis worker is making progress, and how much
+/// work is queued. Responds with 503 when degraded, so it can back a load balancer or
+/// uptime check.
+async fn health_check(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<HealthResponse>) {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!("app::api health_check request from IP {}", client_ip);
+
+    let db = Database::instance().await;
+    let report = admin::health_report(db).await;
+    let response = HealthResponse {
+        status: report.status,
+        workers: report.workers.len(),
+        stuck_workers: report.workers.iter().filter(|worker| worker.stuck).count(),
+        queued: report.queues.as_ref().map(QueueDepths::total),
+    };
+    (report.status_code(), Json(response))
+}
+
+/// Exports pipeline metrics in the Prometheus text format.
+async fn metrics_export(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    let client_ip = get_client_ip(&headers, &addr);
+    debug!("app::api metrics_export request from IP {}", client_ip);
+
+    let db = Database::instance().await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(db).await,
+    )
+}
+
+/// Records that a person acknowledged a life safety alert. Reached from the link in the
+/// alert itself, so it needs no JWT; the token identifies the alert.
+async fn acknowledge_alert(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(token): Path<String>,
+) -> Result<&'static str, StatusCode> {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!("app::api acknowledge_alert request from IP {}", client_ip);
+    let db = Database::instance().await;
+    match db.acknowledge_alert(&token).await {
+        Ok(true) => Ok("Thanks, your alert has been acknowledged."),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            warn!("app::api acknowledge_alert failed: {:#?}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Records a vote from the feedback buttons on a Slack message. Slack signs each request
+/// with the app's signing secret (`SLACK_SIGNING_SECRET`), so it needs no JWT.
+async fn slack_interaction(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: String,
+) -> StatusCode {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!("app::api slack_interaction request from IP {}", client_ip);
+
+    let Ok(signing_secret) = std::env::var(SLACK_SIGNING_SECRET_ENV) else {
+        warn!(
+            "app::api slack_interaction {} is not set",
+            SLACK_SIGNING_SECRET_ENV
+        );
+        return StatusCode::SERVICE_UNAVAILABLE;
+    };
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("")
+    };
+    if !slack::verify_slack_signature(
+        &signing_secret,
+        header("X-Slack-Request-Timestamp"),
+        &body,
+        header("X-Slack-Signature"),
+        chrono::Utc::now().timestamp(),
+    ) {
+        warn!(
+            "app::api slack_interaction invalid signature from IP {}",
+            client_ip
+        );
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    // Other interactions, such as link clicks, need no response
+    let Some(feedback) = slack::parse_feedback_interaction(&body) else {
+        return StatusCode::OK;
+    };
+    info!(
+        "app::api slack_interaction {} feedback on article {} from {}",
+        feedback.kind.as_str(),
+        feedback.article_id,
+        feedback.user_id
+    );
+
+    let db = Database::instance().await;
+    for topic in &feedback.topics {
+        if let Err(e) = db
+            .record_article_feedback(feedback.article_id, topic, feedback.kind, &feedback.user_id)
+            .await
+        {
+            warn!(
+                "app::api slack_interaction failed to record feedback: {:#?}",
+                e
+            );
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    }
+
+    // Slack expects an answer within three seconds, so thank the reader separately
+    if let Some(response_url) = feedback.response_url {
+        tokio::spawn(async move {
+            slack::acknowledge_feedback(&response_url, feedback.kind).await;
+        });
+    }
+    StatusCode::OK
+}
+
 /// Handles syncing seen articles and returning unseen articles.
 async fn sync_seen_articles(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -755,10 +1105,9 @@ async fn sync_seen_articles(
     let token = auth_header.token();
 
     // Validate JWT and extract claims
-    let claims = decode::<Claims>(token, &DECODING_KEY, &Validation::new(Algorithm::HS256))
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let claims = auth::authorize(token).await?;
 
-    let device_id = claims.claims.sub;
+    let device_id = claims.sub;
     info!("Syncing seen articles for device_id: {}", device_id);
 
     let db = Database::instance().await;
@@ -783,8 +1132,310 @@ async fn sync_seen_articles(
     Ok(Json(SyncSeenArticlesResponse { unseen_articles }))
 }
 
+/// Searches analyzed articles by keyword, ranked by BM25, optionally merged with semantic
+/// similarity from Qdrant.
+async fn search_articles(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+    Query(params): Query<SearchQuery>,
+) -> Result<Json<SearchResponse>, StatusCode> {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!("app::api search_articles request from IP {}", client_ip);
+    auth::authorize(auth_header.token()).await?;
+
+    if params.q.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let options = SearchOptions {
+        topic: params.topic,
+        limit: params
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT),
+        semantic_weight: params.semantic_weight,
+        raw: params.raw,
+    };
+    let db = Database::instance().await;
+    match search::search(db, &params.q, &options).await {
+        Ok(results) => Ok(Json(SearchResponse { results })),
+        Err(e) => {
+            warn!("app::api search_articles failed: {:#?}", e);
+            // Queries without words and malformed raw queries are the client's fault
+            match e.downcast_ref::<sqlx::Error>() {
+                Some(sqlx::Error::Database(err)) if err.message().contains("fts5") => {
+                    Err(StatusCode::BAD_REQUEST)
+                }
+                Some(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+                None => Err(StatusCode::BAD_REQUEST),
+            }
+        }
+    }
+}
+
+/// Finds the articles closest in meaning to a free-text query, grouped by story cluster,
+/// with the score breakdown of each match.
+async fn semantic_search(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+    Json(payload): Json<SemanticSearchRequest>,
+) -> Result<Json<SemanticResults>, StatusCode> {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!("app::api semantic_search request from IP {}", client_ip);
+    auth::authorize(auth_header.token()).await?;
+
+    if payload.query.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let date_bound = |value: Option<&str>, end_of_day| match value {
+        Some(value) => parse_date_bound(value, end_of_day)
+            .map(Some)
+            .ok_or(StatusCode::BAD_REQUEST),
+        None => Ok(None),
+    };
+    let filter = SemanticFilter {
+        since: date_bound(payload.since.as_deref(), false)?,
+        until: date_bound(payload.until.as_deref(), true)?,
+        categories: payload.categories,
+        min_quality: payload.min_quality,
+    };
+    let limit = payload
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+
+    let db = Database::instance().await;
+    match search::semantic_search(db, &payload.query, &filter, limit, payload.min_score).await {
+        Ok(results) => Ok(Json(results)),
+        Err(e) => {
+            warn!("app::api semantic_search failed: {:#?}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Follows, unfollows, silences or unsilences a cluster for the device. Followed clusters
+/// get a push when their summary is updated; silenced clusters get no pushes at all and
+/// are left out of cluster syncs. Silencing also unfollows.
+async fn set_cluster_preference(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+    Path((cluster_id, action)): Path<(i64, String)>,
+) -> Result<StatusCode, StatusCode> {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!(
+        "app::api set_cluster_preference request from IP {}: {} cluster {}",
+        client_ip, action, cluster_id
+    );
+    let claims = auth::authorize(auth_header.token()).await?;
+
+    let (followed, silenced) = match action.as_str() {
+        "follow" => (Some(true), Some(false)),
+        "unfollow" => (Some(false), None),
+        "silence" => (Some(false), Some(true)),
+        "unsilence" => (None, Some(false)),
+        _ => return Err(StatusCode::NOT_FOUND),
+    };
+
+    let db = Database::instance().await;
+    match crate::db::cluster::does_cluster_exist(db, cluster_id).await {
+        Ok(true) => {}
+        Ok(false) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            warn!("Failed to look up cluster {}: {:?}", cluster_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+    crate::db::cluster::set_cluster_preference(db, &claims.sub, cluster_id, followed, silenced)
+        .await
+        .map_err(|e| {
+            warn!("Failed to set cluster preference: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(StatusCode::OK)
+}
+
+/// Lists the clusters the device follows or silenced.
+async fn get_cluster_preferences(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+) -> Result<Json<ClusterPreferencesResponse>, StatusCode> {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!(
+        "app::api get_cluster_preferences request from IP {}",
+        client_ip
+    );
+    let claims = auth::authorize(auth_header.token()).await?;
+
+    let db = Database::instance().await;
+    let preferences = crate::db::cluster::get_cluster_preferences(db, &claims.sub)
+        .await
+        .map_err(|e| {
+            warn!("Failed to fetch cluster preferences: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(ClusterPreferencesResponse { preferences }))
+}
+
+/// Streams analyses, life safety alerts and cluster updates as server-sent events, each
+/// named by its type and carrying its ID so the client can resume after a disconnect.
+/// Without a last event ID only new events are sent.
+async fn stream_events(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+    Query(params): Query<EventStreamQuery>,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>, StatusCode> {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!("app::api stream_events request from IP {}", client_ip);
+    let claims = auth::authorize(auth_header.token()).await?;
+
+    let split = |value: Option<String>| -> Vec<String> {
+        value
+            .iter()
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect()
+    };
+    let filter = EventFilter {
+        kinds: split(params.types),
+        topics: split(params.topics),
+    };
+    if filter
+        .kinds
+        .iter()
+        .any(|kind| EventKind::from_name(kind).is_none())
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let last_event_id = match headers.get("Last-Event-ID") {
+        Some(value) => Some(
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+                .ok_or(StatusCode::BAD_REQUEST)?,
+        ),
+        None => params.last_event_id,
+    };
+
+    let db = Database::instance().await;
+    let after_id = match last_event_id {
+        Some(id) => id,
+        None => db.latest_event_id().await.map_err(|e| {
+            warn!("app::api stream_events failed: {:#?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?,
+    };
+    info!(
+        "app::api streaming events after {} to device {}: {:?}",
+        after_id, claims.sub, filter
+    );
+
+    let stream = events::stream(db, after_id, filter).map(|event| {
+        Ok(SseEvent::default()
+            .id(event.id.to_string())
+            .event(event.kind.as_str())
+            .data(serde_json::to_string(&event).unwrap_or_default()))
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
 // [Nothing here - remove this duplicate function]
 
+/// Parses a date filter. A bare date means the start of that day, or with `end_of_day`
+/// the start of the next, so that `until` includes it.
+fn parse_date_bound(value: &str, end_of_day: bool) -> Option<DateTime<Utc>> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Some(date_time.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    let date = if end_of_day { date.succ_opt()? } else { date };
+    Some(date.and_time(NaiveTime::MIN).and_utc())
+}
+
+/// Returns an analyzed article, with its full analysis, straight from the database.
+async fn get_article(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+    Path(id): Path<i64>,
+) -> Result<Json<ArticleRecord>, StatusCode> {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!("app::api get_article request from IP {}", client_ip);
+    auth::authorize(auth_header.token()).await?;
+
+    let db = Database::instance().await;
+    match db.fetch_article(id).await {
+        Ok(Some(article)) => Ok(Json(article)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            warn!("app::api get_article failed: {:#?}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Lists analyzed articles, newest first, filtered by topic, date range, quality, source
+/// domain and cluster. Pages are linked by cursor.
+async fn list_articles(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    TypedHeader(auth_header): TypedHeader<Authorization<Bearer>>,
+    Query(params): Query<ArticleListQuery>,
+) -> Result<Json<ArticleListResponse>, StatusCode> {
+    let client_ip = get_client_ip(&headers, &addr);
+    info!("app::api list_articles request from IP {}", client_ip);
+    auth::authorize(auth_header.token()).await?;
+
+    let date_bound = |value: Option<&str>, end_of_day| match value {
+        Some(value) => parse_date_bound(value, end_of_day)
+            .map(Some)
+            .ok_or(StatusCode::BAD_REQUEST),
+        None => Ok(None),
+    };
+    let before_id = match params.cursor.as_deref() {
+        Some(cursor) => Some(cursor.parse().map_err(|_| StatusCode::BAD_REQUEST)?),
+        None => None,
+    };
+    let filter = ArticleFilter {
+        topic: params.topic,
+        since: date_bound(params.since.as_deref(), false)?,
+        until: date_bound(params.until.as_deref(), true)?,
+        min_quality: params.min_quality,
+        domain: params.domain,
+        cluster_id: params.cluster_id,
+        before_id,
+    };
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_ARTICLE_PAGE_SIZE)
+        .clamp(1, MAX_ARTICLE_PAGE_SIZE);
+
+    // Fetch one extra article to know whether there is another page
+    let db = Database::instance().await;
+    let mut articles = db.list_articles(&filter, limit + 1).await.map_err(|e| {
+        warn!("app::api list_articles failed: {:#?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let next_cursor = if articles.len() as i64 > limit {
+        articles.truncate(limit as usize);
+        articles.last().map(|article| article.id.to_string())
+    } else {
+        None
+    };
+
+    Ok(Json(ArticleListResponse {
+        articles,
+        next_cursor,
+    }))
+}
+
 async fn get_subscriptions(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
@@ -794,12 +1445,10 @@ async fn get_subscriptions(
     info!("app::api get_subscriptions request from IP {}", client_ip);
 
     let token = auth_header.token();
-    let claims = decode::<Claims>(token, &DECODING_KEY, &Validation::new(Algorithm::HS256))
-        .map_err(|e| {
-            warn!("app::api get_subscriptions JWT validation failed: {:#?}", e);
-            StatusCode::UNAUTHORIZED
-        })?;
-    let device_id = claims.claims.sub;
+    let claims = auth::authorize(token).await.inspect_err(|e| {
+        warn!("app::api get_subscriptions JWT validation failed: {:#?}", e);
+    })?;
+    let device_id = claims.sub;
 
     info!(
         "app::api get_subscriptions validated JWT for device_id: {}",
@@ -827,3 +1476,27 @@ async fn get_subscriptions(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date_bound() {
+        let start = parse_date_bound("2025-03-01", false).unwrap();
+        assert_eq!(start.to_rfc3339(), "2025-03-01T00:00:00+00:00");
+        // `until` includes the whole day
+        let end = parse_date_bound("2025-03-01", true).unwrap();
+        assert_eq!(end.to_rfc3339(), "2025-03-02T00:00:00+00:00");
+        let end = parse_date_bound("2024-12-31", true).unwrap();
+        assert_eq!(end.to_rfc3339(), "2025-01-01T00:00:00+00:00");
+
+        // Full timestamps are taken as they are, in UTC
+        let time = parse_date_bound("2025-03-01T12:30:00+02:00", true).unwrap();
+        assert_eq!(time.to_rfc3339(), "2025-03-01T10:30:00+00:00");
+
+        assert_eq!(parse_date_bound("2025-02-30", false), None);
+        assert_eq!(parse_date_bound("yesterday", false), None);
+        assert_eq!(parse_date_bound("", true), None);
+    }
+}
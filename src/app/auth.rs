@@ -0,0 +1,284 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header};
+use jsonwebtoken::{errors::ErrorKind, Validation};
+use once_cell::sync::Lazy;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::{env, fs};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db::core::Database;
+
+const JWT_KEYS_PATH_ENV: &str = "JWT_KEYS_PATH";
+const JWT_SIGNING_KEYS_ENV: &str = "JWT_SIGNING_KEYS";
+
+/// How long access tokens are valid.
+pub const ACCESS_TOKEN_SECONDS: i64 = 60 * 60;
+/// How long refresh tokens are valid. Each can be used once.
+pub const REFRESH_TOKEN_DAYS: i64 = 30;
+/// HMAC secrets shorter than this are rejected.
+const MIN_SECRET_BYTES: usize = 32;
+
+/// Represents the claims stored in a JWT token.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String, // Subject (e.g., device ID)
+    pub exp: usize,  // Expiration time (as a timestamp)
+    pub iat: usize,  // Issued at (as a timestamp)
+    pub jti: String, // Token ID, for revocation
+}
+
+/// The JWT signing keys file: the ID of the key new tokens are signed with, and every
+/// key tokens are accepted from, by ID.
+#[derive(Debug, Deserialize)]
+struct KeysFile {
+    active: String,
+    keys: HashMap<String, String>,
+}
+
+/// HMAC keys by key ID. New tokens are signed with the active key and carry its ID in
+/// the `kid` header; tokens signed with any of the keys are accepted, so a new key can
+/// be made active while tokens signed with the old one are still in use.
+pub struct KeyRing {
+    active: String,
+    keys: HashMap<String, Vec<u8>>,
+}
+
+impl KeyRing {
+    fn new(active: String, keys: HashMap<String, Vec<u8>>) -> Result<Self, String> {
+        if !keys.contains_key(&active) {
+            return Err(format!("Active key '{}' is not among the keys", active));
+        }
+        if let Some((kid, _)) = keys.iter().find(|(_, key)| key.len() < MIN_SECRET_BYTES) {
+            return Err(format!(
+                "Key '{}' is shorter than {} bytes",
+                kid, MIN_SECRET_BYTES
+            ));
+        }
+        Ok(KeyRing { active, keys })
+    }
+
+    /// Parses a keys file like `{"active": "2025-06", "keys": {"2025-06": "...", ...}}`.
+    pub fn parse_json(json_data: &str) -> Result<Self, String> {
+        let file: KeysFile = serde_json::from_str(json_data)
+            .map_err(|err| format!("Failed to parse the keys file: {}", err))?;
+        let keys = file
+            .keys
+            .into_iter()
+            .map(|(kid, secret)| (kid, secret.into_bytes()))
+            .collect();
+        KeyRing::new(file.active, keys)
+    }
+
+    /// Parses `kid:secret` pairs separated by commas; the first key is the active one.
+    pub fn parse_env(value: &str) -> Result<Self, String> {
+        let mut active = None;
+        let mut keys = HashMap::new();
+        for pair in value
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            let (kid, secret) = pair
+                .split_once(':')
+                .ok_or_else(|| format!("Expected kid:secret, got '{}'", pair))?;
+            let kid = kid.trim().to_string();
+            active.get_or_insert_with(|| kid.clone());
+            keys.insert(kid, secret.trim().as_bytes().to_vec());
+        }
+        let active = active.ok_or_else(|| "No keys given".to_string())?;
+        KeyRing::new(active, keys)
+    }
+
+    /// A random key that only lives as long as the process.
+    fn ephemeral() -> Self {
+        let mut key = vec![0u8; 32]; // 256-bit key for HMAC
+        SystemRandom::new()
+            .fill(&mut key)
+            .expect("Failed to generate secure random bytes");
+        KeyRing {
+            active: "ephemeral".to_string(),
+            keys: HashMap::from([("ephemeral".to_string(), key)]),
+        }
+    }
+
+    /// Loads the keys from the file at `JWT_KEYS_PATH` or from `JWT_SIGNING_KEYS`. Without
+    /// either, tokens are signed with a random key and are invalid after a restart.
+    fn load() -> Self {
+        let loaded = if let Ok(path) = env::var(JWT_KEYS_PATH_ENV) {
+            fs::read_to_string(&path)
+                .map_err(|err| format!("Failed to read {}: {}", path, err))
+                .and_then(|json_data| KeyRing::parse_json(&json_data))
+        } else if let Ok(value) = env::var(JWT_SIGNING_KEYS_ENV) {
+            KeyRing::parse_env(&value)
+        } else {
+            warn!(
+                "Neither {} nor {} is set; app tokens will be invalid after a restart",
+                JWT_KEYS_PATH_ENV, JWT_SIGNING_KEYS_ENV
+            );
+            return KeyRing::ephemeral();
+        };
+        match loaded {
+            Ok(ring) => {
+                info!(
+                    "Loaded {} JWT signing keys, signing with '{}'",
+                    ring.keys.len(),
+                    ring.active
+                );
+                ring
+            }
+            Err(err) => panic!("Failed to load JWT signing keys: {}", err),
+        }
+    }
+
+    /// Signs claims with the active key.
+    pub fn encode(&self, claims: &Claims) -> jsonwebtoken::errors::Result<String> {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(self.active.clone());
+        encode(
+            &header,
+            claims,
+            &EncodingKey::from_secret(&self.keys[&self.active]),
+        )
+    }
+
+    /// Validates a token signed with any of the keys. Tokens without a key ID are
+    /// checked against the active key.
+    pub fn decode(&self, token: &str) -> jsonwebtoken::errors::Result<Claims> {
+        let kid = decode_header(token)?
+            .kid
+            .unwrap_or_else(|| self.active.clone());
+        let key = self
+            .keys
+            .get(&kid)
+            .ok_or(jsonwebtoken::errors::Error::from(ErrorKind::InvalidToken))?;
+        decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(key),
+            &Validation::new(Algorithm::HS256),
+        )
+        .map(|data| data.claims)
+    }
+}
+
+static KEYS: Lazy<KeyRing> = Lazy::new(KeyRing::load);
+
+/// Loads the signing keys, so a bad configuration stops the API at startup rather than
+/// at the first request.
+pub fn init_keys() {
+    Lazy::force(&KEYS);
+}
+
+/// Issues an access token for a device.
+pub fn issue_access_token(device_id: &str) -> jsonwebtoken::errors::Result<String> {
+    let now = Utc::now().timestamp();
+    let claims = Claims {
+        sub: device_id.to_string(),
+        exp: (now + ACCESS_TOKEN_SECONDS) as usize,
+        iat: now as usize,
+        jti: Uuid::new_v4().to_string(),
+    };
+    KEYS.encode(&claims)
+}
+
+/// Issues a refresh token for a device and stores its hash. It can be exchanged once,
+/// by the same device, for a new access token and refresh token.
+pub async fn issue_refresh_token(db: &Database, device_id: &str) -> Result<String, sqlx::Error> {
+    let token = random_token();
+    let expires_at = (Utc::now() + chrono::Duration::days(REFRESH_TOKEN_DAYS)).timestamp();
+    db.store_refresh_token(&hash_refresh_token(&token), device_id, expires_at)
+        .await?;
+    Ok(token)
+}
+
+/// A random 256-bit value as 64 hex characters, for refresh tokens and signing keys.
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("Failed to generate secure random bytes");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Hex SHA-256 of a refresh token, as stored in the database.
+pub fn hash_refresh_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Validates an access token and checks it against the revocation list.
+pub async fn authorize(token: &str) -> Result<Claims, StatusCode> {
+    let claims = KEYS.decode(token).map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let db = Database::instance().await;
+    match db
+        .is_token_revoked(&claims.jti, &claims.sub, claims.iat as i64)
+        .await
+    {
+        Ok(false) => Ok(claims),
+        Ok(true) => Err(StatusCode::UNAUTHORIZED),
+        Err(e) => {
+            error!("Failed to check the token revocation list: {:?}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims() -> Claims {
+        let now = Utc::now().timestamp();
+        Claims {
+            sub: "device".to_string(),
+            exp: (now + 60) as usize,
+            iat: now as usize,
+            jti: "token".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old = KeyRing::parse_env("2025-01:0123456789abcdef0123456789abcdef").unwrap();
+        let rotated = KeyRing::parse_json(
+            r#"{"active": "2025-06", "keys": {
+                "2025-06": "fedcba9876543210fedcba9876543210",
+                "2025-01": "0123456789abcdef0123456789abcdef"
+            }}"#,
+        )
+        .unwrap();
+
+        // Tokens signed with the old key are still accepted after the rotation
+        let token = old.encode(&claims()).unwrap();
+        assert_eq!(rotated.decode(&token).unwrap(), claims());
+
+        // New tokens are signed with the new key, which the old ring doesn't know
+        let token = rotated.encode(&claims()).unwrap();
+        assert_eq!(
+            decode_header(&token).unwrap().kid.as_deref(),
+            Some("2025-06")
+        );
+        assert!(old.decode(&token).is_err());
+
+        // Once the old key is dropped, its tokens are rejected
+        let retired = KeyRing::parse_env("2025-06:fedcba9876543210fedcba9876543210").unwrap();
+        assert!(retired.decode(&old.encode(&claims()).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert!(KeyRing::parse_env("").is_err());
+        assert!(KeyRing::parse_env("no-secret").is_err());
+        assert!(KeyRing::parse_env("short:secret").is_err());
+        assert!(KeyRing::parse_json(
+            r#"{"active": "missing", "keys": {"2025-06": "fedcba9876543210fedcba9876543210"}}"#
+        )
+        .is_err());
+    }
+}
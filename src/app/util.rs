@@ -2,156 +2,196 @@ use aws_sdk_s3::config::BehaviorVersion;
 use aws_sdk_s3::config::{Credentials, Region};
 use aws_sdk_s3::primitives::ByteStream;
 use aws_sdk_s3::{Client, Config};
-use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
-use reqwest::StatusCode;
-use serde::Serialize;
 use serde_json::{json, Value};
+use std::collections::HashSet;
 use std::env;
-use std::fs;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::time::Duration;
 use tracing::{error, info};
-use url::Url;
 use uuid::Uuid;
 
+use crate::app::push::{Interruption, PushMessage, Pusher};
+use crate::db::cluster;
 use crate::db::core::Database;
 use crate::metrics::SystemInfo;
+use crate::throttle::{self, Candidate, Recipient, Verdict};
+use crate::topics;
 
-#[derive(Serialize)]
-struct Claims {
-    iss: String,
-    iat: u64,
-}
+/// Maximum length of the summary in a story update notification.
+const STORY_UPDATE_BODY_CHARS: usize = 178;
 
-/// Send iOS app a push notification.
+/// Uploads an analysis to R2 and pushes it to the apps, returning its R2 URL. A failed
+/// push is logged; the upload is what decides the result.
 ///
 /// # Arguments
 /// * `json` - A json object with details about the analyzed article.
-pub async fn send_to_app(json: &Value) -> Option<String> {
-    // Upload the JSON to R2
+/// * `cluster_id` - The story cluster the article belongs to, if any.
+pub async fn send_to_app(json: &Value, cluster_id: Option<i64>) -> Option<String> {
     let json_url = upload_to_r2(json).await?;
-    let title = json
-        .get("tiny_title")
-        .and_then(|v| v.as_str())
-        .unwrap_or("No title available.");
-    let body = json
-        .get("tiny_summary")
-        .and_then(|v| v.as_str())
-        .unwrap_or("No summary available.");
-    let sources_quality = json
-        .get("sources_quality")
-        .and_then(|v| v.as_str())
-        .unwrap_or("2");
-    let argument_quality = json
-        .get("argument_quality")
-        .and_then(|v| v.as_str())
-        .unwrap_or("2");
-    let source_type = json
-        .get("source_type")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let pub_date = json
-        .get("pub_date")
-        .and_then(|v| v.as_str())
-        .unwrap_or("Unknown date");
-    // Extract base URL of the article
-    let domain = json
-        .get("url")
-        .and_then(|v| v.as_str())
-        .and_then(|url| {
-            Url::parse(url)
-                .ok()
-                .and_then(|parsed_url| parsed_url.host_str().map(|host| host.to_string()))
-        })
-        .unwrap_or_else(|| "unknown".to_string());
-    // Load required environment variables
-    let team_id = env::var("APP_TEAM_ID").ok()?;
-    let key_id = env::var("APP_KEY_ID").ok()?;
-    let private_key_path = env::var("APP_PRIVATE_KEY_PATH").ok()?;
-    let private_key = fs::read_to_string(&private_key_path).ok()?;
-    // Generate JWT token
-    let iat = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
-    let claims = Claims {
-        iss: team_id.clone(),
-        iat,
-    };
-    let mut header = Header::new(Algorithm::ES256);
-    header.kid = Some(key_id.clone());
-    let encoding_key = EncodingKey::from_ec_pem(private_key.as_bytes()).ok()?;
-    let jwt_token = encode(&header, &claims, &encoding_key).ok()?;
-    // Send notification
-    let client = reqwest::Client::builder()
-        .http2_prior_knowledge()
-        .build()
-        .ok()?;
-    // Fetch subscribed devices with high priority only
-    let db = Database::instance().await;
-    let topic = json.get("topic").and_then(|v| v.as_str()).unwrap_or("none");
-    let device_tokens_with_priorities = db.fetch_devices_for_topic(topic).await.ok()?;
-
-    for (device_token, _) in device_tokens_with_priorities {
-        // All notifications are high priority now
-        let payload = serde_json::json!({
-            "aps": {
-                "alert": {
-                    "title": title,
-                    "body": body
-                },
-                "sound": "default",
-                "content-available": 1
-            },
-            "data": {
-                "json_url": json_url,
-                "topic": json.get("topic").and_then(|v| v.as_str()).unwrap_or("none"),
-                "article_title": json.get("title").and_then(|v| v.as_str()).unwrap_or("none"),
-                "domain": domain,
-                "sources_quality": sources_quality,
-                "argument_quality": argument_quality,
-                "source_type": source_type,
-                "pub_date": pub_date
-            }
-        });
-
-        let apns_url = format!("https://api.push.apple.com/3/device/{}", device_token);
-        match client
-            .post(&apns_url)
-            .header("apns-topic", "com.andrews.Argus.Argus")
-            .header("apns-priority", "10") // Always high priority (10)
-            .header("authorization", format!("bearer {}", jwt_token))
-            .header("Content-Type", "application/json")
-            .body(payload.to_string())
-            .timeout(Duration::from_secs(10))
-            .send()
+    if let Some(pusher) = Pusher::shared() {
+        let db = Database::instance().await;
+        if let Err(e) = push_to_app(db, pusher, json, &json_url, cluster_id).await {
+            error!("Failed to push {} to the apps: {}", json_url, e);
+        }
+    }
+    Some(json_url)
+}
+
+/// Send the apps a push notification about an analysis uploaded to `json_url`, through
+/// each device's push service.
+///
+/// Each device is subject to the per-device rate limit: devices already notified about
+/// the article's story cluster are skipped, and devices over their limit get the article
+/// later in a batch. Devices that silenced the cluster are skipped. Life safety alerts
+/// are always pushed.
+///
+/// # Arguments
+/// * `db` - Database instance
+/// * `pusher` - The push services to send through
+/// * `json` - A json object with details about the analyzed article.
+/// * `json_url` - Where the analysis was uploaded to.
+/// * `cluster_id` - The story cluster the article belongs to, if any.
+pub async fn push_to_app(
+    db: &Database,
+    pusher: &Pusher,
+    json: &Value,
+    json_url: &str,
+    cluster_id: Option<i64>,
+) -> Result<(), String> {
+    let message = PushMessage::article(json, json_url);
+    let article_url = json.get("url").and_then(|v| v.as_str()).unwrap_or("");
+    // Notify subscribers of every matched topic, but each device only once, under the
+    // first topic it subscribed to
+    let mut device_tokens: Vec<(String, String)> = Vec::new();
+    for topic in topics::topics_in_response(json) {
+        if !topics::get(&topic).is_none_or(|t| t.push_eligible) {
+            info!(
+                "Topic {} is not push eligible, skipping notifications.",
+                topic
+            );
+            continue;
+        }
+        let devices = db
+            .fetch_devices_for_topic(&topic)
             .await
-        {
-            Ok(response) if response.status().is_success() => {
-                info!("Notification sent successfully.");
+            .map_err(|e| format!("Failed to fetch devices for {}: {}", topic, e))?;
+        for (device_token, _) in devices {
+            if !device_tokens
+                .iter()
+                .any(|(token, _)| *token == device_token)
+            {
+                device_tokens.push((device_token, topic.clone()));
             }
-            Ok(response) => {
-                let status = response.status();
-                let response_text = response.text().await.unwrap_or_default();
+        }
+    }
+
+    let is_alert = json.get("threat").is_some();
+    let silenced = match cluster_id {
+        Some(cluster_id) if !is_alert => cluster::get_devices_silencing_cluster(db, cluster_id)
+            .await
+            .unwrap_or_else(|e| {
                 error!(
-                    "Failed to send notification: Status = {}, Response = {}",
-                    status, response_text
+                    "Failed to fetch devices silencing cluster {}: {}",
+                    cluster_id, e
                 );
-                if status == StatusCode::GONE || status == StatusCode::NOT_FOUND {
-                    info!(
-                        "Device token {} is unregistered. Unsubscribing from all topics.",
-                        device_token
-                    );
-                    if let Err(e) = db.remove_device_token(&device_token).await {
-                        error!("Failed to unsubscribe device {}: {}", device_token, e);
-                    } else {
-                        info!("Device {} unsubscribed from all topics.", device_token);
-                    }
-                }
-            }
-            Err(e) => {
-                error!("Failed to send POST request to APNs: {}", e);
-            }
+                HashSet::new()
+            }),
+        _ => HashSet::new(),
+    };
+
+    for (device_token, topic) in device_tokens {
+        if silenced.contains(&device_token) {
+            continue;
+        }
+        let recipient = Recipient::Device(&device_token);
+        let candidate = Candidate {
+            topic: &topic,
+            cluster_id,
+            article_url,
+            title: &message.title,
+        };
+        if !is_alert && throttle::admit(db, &recipient, &candidate).await != Verdict::Send {
+            continue;
+        }
+
+        if pusher.push(db, &device_token, &message).await && !is_alert {
+            throttle::record_sent(db, &recipient, &candidate).await;
+        }
+    }
+    Ok(())
+}
+
+/// Pushes a "story update" to the devices following a cluster whose summary was just
+/// rewritten, unless they silenced it or have already seen this version. Story updates
+/// count towards each device's rate limit, and a device gets at most one about a cluster
+/// a day.
+///
+/// # Arguments
+/// * `db` - Database instance
+/// * `cluster_id` - The story cluster
+/// * `version` - The cluster's new summary version
+/// * `summary` - The new summary
+pub async fn send_story_update(db: &Database, cluster_id: i64, version: i32, summary: &str) {
+    let followers = match cluster::get_cluster_followers(db, cluster_id, version).await {
+        Ok(followers) => followers,
+        Err(e) => {
+            error!("Failed to fetch followers of cluster {}: {}", cluster_id, e);
+            return;
+        }
+    };
+    if followers.is_empty() {
+        return;
+    }
+    let Some(pusher) = Pusher::shared() else {
+        return;
+    };
+
+    // The first paragraph of the summary, shortened to fit a notification
+    let mut body = summary
+        .lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or("The story has new developments.")
+        .to_string();
+    if let Some((index, _)) = body.char_indices().nth(STORY_UPDATE_BODY_CHARS) {
+        body.truncate(index);
+        body.push('…');
+    }
+
+    let data = json!({
+        "type": "story_update",
+        "cluster_id": cluster_id,
+        "version": version
+    });
+    let message = PushMessage {
+        title: "Story update".to_string(),
+        body,
+        data: data.as_object().cloned().unwrap_or_default(),
+        interruption: Interruption::Active,
+        high_priority: false,
+        sound: true,
+        content_available: true,
+    };
+    info!(
+        "Sending story update for cluster {} version {} to {} followers",
+        cluster_id,
+        version,
+        followers.len()
+    );
+    let dedup_key = throttle::story_update_key(cluster_id);
+    for device_token in followers {
+        let recipient = Recipient::Device(&device_token);
+        let candidate = Candidate {
+            topic: throttle::STORY_UPDATE_TOPIC,
+            cluster_id: None,
+            article_url: &dedup_key,
+            title: &message.body,
+        };
+        if throttle::admit(db, &recipient, &candidate).await != Verdict::Send {
+            continue;
+        }
+        if pusher.push(db, &device_token, &message).await {
+            throttle::record_sent(db, &recipient, &candidate).await;
         }
     }
-    Some(json_url)
 }
 
 pub async fn upload_to_r2(json: &Value) -> Option<String> {
@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use argus::db::Database;
+use argus::feedback::{FeedbackKind, LabelledArticle};
+use clap::{Parser, Subcommand};
+use prettytable::{row, Table};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use tokio::main;
+
+#[derive(Parser)]
+#[command(author, version, about = "Export reader feedback as labelled data", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Write one JSON object per labelled article and topic, for the prompt regression set
+    Export {
+        /// Only export feedback on this topic
+        #[arg(short, long)]
+        topic: Option<String>,
+
+        /// File to write to instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Summarize feedback per topic, with the match confidence of good and bad matches
+    Summary {
+        /// Only summarize this topic
+        #[arg(short, long)]
+        topic: Option<String>,
+    },
+}
+
+#[main]
+async fn main() -> Result<()> {
+    // Initialize tracing
+    argus::logging::configure_logging();
+
+    let cli = Cli::parse();
+
+    // Get database connection
+    let database_url = std::env::var("DATABASE_PATH").unwrap_or_else(|_| "argus.db".to_string());
+    let db = Database::new(&database_url)
+        .await
+        .context("Failed to connect to database")?;
+
+    match cli.command {
+        Commands::Export { topic, output } => {
+            let labelled = db.fetch_labelled_articles(topic.as_deref()).await?;
+            let mut writer: Box<dyn Write> = match &output {
+                Some(path) => Box::new(BufWriter::new(
+                    File::create(path).with_context(|| format!("Failed to create {}", path))?,
+                )),
+                None => Box::new(io::stdout().lock()),
+            };
+            for article in &labelled {
+                writeln!(writer, "{}", serde_json::to_string(article)?)?;
+            }
+            writer.flush()?;
+            if let Some(path) = output {
+                println!("Exported {} labelled articles to {}", labelled.len(), path);
+            }
+        }
+
+        Commands::Summary { topic } => {
+            let labelled = db.fetch_labelled_articles(topic.as_deref()).await?;
+            if labelled.is_empty() {
+                println!("No feedback has been recorded");
+                return Ok(());
+            }
+
+            let mut by_topic: BTreeMap<&str, Vec<&LabelledArticle>> = BTreeMap::new();
+            for article in &labelled {
+                by_topic.entry(&article.topic).or_default().push(article);
+            }
+
+            let mut table = Table::new();
+            table.add_row(row![
+                "Topic",
+                "Articles",
+                "Not relevant",
+                "Wrong topic",
+                "Great",
+                "Avg confidence (great)",
+                "Max confidence (not relevant)"
+            ]);
+            for (topic, articles) in by_topic {
+                let count = |kind: FeedbackKind| {
+                    articles
+                        .iter()
+                        .filter(|article| article.label == kind)
+                        .count()
+                };
+                let confidences = |positive: bool| {
+                    articles
+                        .iter()
+                        .filter(|article| article.label.is_positive() == positive)
+                        .filter_map(|article| article.confidence)
+                        .collect::<Vec<f64>>()
+                };
+                let good = confidences(true);
+                let bad = confidences(false);
+                let average = if good.is_empty() {
+                    "-".to_string()
+                } else {
+                    format!("{:.3}", good.iter().sum::<f64>() / good.len() as f64)
+                };
+                let max_bad = bad
+                    .iter()
+                    .copied()
+                    .reduce(f64::max)
+                    .map_or("-".to_string(), |max| format!("{:.3}", max));
+                table.add_row(row![
+                    topic,
+                    articles.len(),
+                    count(FeedbackKind::NotRelevant),
+                    count(FeedbackKind::WrongTopic),
+                    count(FeedbackKind::Great),
+                    average,
+                    max_bad
+                ]);
+            }
+            table.printstd();
+        }
+    }
+
+    Ok(())
+}
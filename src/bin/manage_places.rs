@@ -0,0 +1,329 @@
+use anyhow::{anyhow, Context, Result};
+use argus::alerts::{ChannelKind, QuietHours};
+use argus::db::Database;
+use argus::places;
+use clap::{Parser, Subcommand};
+use tokio::main;
+use tracing::info;
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// Where a person lives.
+#[derive(clap::Args)]
+struct LocationArgs {
+    /// Continent, e.g. "Europe"
+    #[arg(long)]
+    continent: String,
+
+    /// Country, e.g. "Italy"
+    #[arg(long)]
+    country: String,
+
+    /// Region within the country, e.g. "Tuscany"
+    #[arg(long)]
+    region: String,
+
+    /// City, e.g. "Florence"
+    #[arg(long)]
+    city: String,
+
+    /// Optional latitude of the city
+    #[arg(long, allow_hyphen_values = true)]
+    lat: Option<f64>,
+
+    /// Optional longitude of the city
+    #[arg(long, allow_hyphen_values = true)]
+    lon: Option<f64>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// List everyone in the places registry
+    List,
+
+    /// Add a person
+    Add {
+        /// First name
+        #[arg(short, long)]
+        first: String,
+
+        /// Last name
+        #[arg(short, long)]
+        last: String,
+
+        #[command(flatten)]
+        location: LocationArgs,
+
+        /// Time zone, e.g. "CET (UTC+1)"
+        #[arg(short, long)]
+        timezone: Option<String>,
+
+        /// Slack user ID or handle
+        #[arg(short, long)]
+        slack_id: Option<String>,
+    },
+
+    /// Move a person to another location
+    Move {
+        /// ID of the person, as shown by `list`
+        #[arg(short, long)]
+        id: i64,
+
+        #[command(flatten)]
+        location: LocationArgs,
+    },
+
+    /// Set a person's home coordinates, used instead of their city's when matching threats
+    SetHome {
+        /// ID of the person, as shown by `list`
+        #[arg(short, long)]
+        id: i64,
+
+        /// Latitude of the person's home
+        #[arg(long, allow_hyphen_values = true, required_unless_present = "clear")]
+        lat: Option<f64>,
+
+        /// Longitude of the person's home
+        #[arg(long, allow_hyphen_values = true, required_unless_present = "clear")]
+        lon: Option<f64>,
+
+        /// Clear the home coordinates instead
+        #[arg(long, conflicts_with_all = ["lat", "lon"])]
+        clear: bool,
+    },
+
+    /// Add a channel a person receives life safety alerts on
+    AddChannel {
+        /// ID of the person, as shown by `list`
+        #[arg(short, long)]
+        id: i64,
+
+        /// slack, email or webhook
+        #[arg(short, long)]
+        kind: String,
+
+        /// Slack user ID, email address or webhook URL; defaults to the person's Slack ID
+        /// for slack channels
+        #[arg(short, long)]
+        address: Option<String>,
+    },
+
+    /// Remove an alert channel
+    RemoveChannel {
+        /// ID of the channel, as shown by `list`
+        #[arg(short, long)]
+        channel_id: i64,
+    },
+
+    /// Set the local hours in which a person only receives immediate alerts
+    QuietHours {
+        /// ID of the person, as shown by `list`
+        #[arg(short, long)]
+        id: i64,
+
+        /// Quiet hours in the person's time zone, e.g. "22:00-07:00"
+        #[arg(long, required_unless_present = "clear")]
+        hours: Option<String>,
+
+        /// Clear the quiet hours instead
+        #[arg(long, conflicts_with = "hours")]
+        clear: bool,
+    },
+
+    /// Remove a person
+    Remove {
+        /// ID of the person, as shown by `list`
+        #[arg(short, long)]
+        id: i64,
+    },
+
+    /// Import people from a places.json file
+    Import {
+        /// Path to places.json
+        #[arg(short, long)]
+        path: String,
+    },
+}
+
+#[main]
+async fn main() -> Result<()> {
+    // Initialize tracing
+    argus::logging::configure_logging();
+
+    let cli = Cli::parse();
+
+    // Get database connection
+    let database_url = std::env::var("DATABASE_PATH").unwrap_or_else(|_| "argus.db".to_string());
+    let db = Database::new(&database_url)
+        .await
+        .context("Failed to connect to database")?;
+
+    match cli.command {
+        Commands::List => {
+            let people = db.fetch_people().await?;
+            if people.is_empty() {
+                println!("The places registry is empty");
+            }
+            for person in people {
+                let coordinates = match person.coordinates() {
+                    Some((lat, lon)) if person.home_latitude.is_some() => {
+                        format!(" (home {:.4}, {:.4})", lat, lon)
+                    }
+                    Some((lat, lon)) => format!(" ({:.4}, {:.4})", lat, lon),
+                    None => String::new(),
+                };
+                println!(
+                    "{:>4}  {} {} - {} / {} / {} / {}{} [{}; {}]",
+                    person.id,
+                    person.first_name,
+                    person.last_name,
+                    person.location.continent,
+                    person.location.country,
+                    person.location.region,
+                    person.location.city,
+                    coordinates,
+                    person.timezone.as_deref().unwrap_or("no time zone"),
+                    person.slack_id.as_deref().unwrap_or("no Slack ID"),
+                );
+                if let Some(quiet_hours) = &person.quiet_hours {
+                    println!("      quiet hours {}", quiet_hours);
+                }
+                for channel in db.fetch_person_channels(person.id).await? {
+                    println!(
+                        "      channel {}: {} {}",
+                        channel.id,
+                        channel.kind.as_str(),
+                        channel.address
+                    );
+                }
+            }
+        }
+
+        Commands::Add {
+            first,
+            last,
+            location,
+            timezone,
+            slack_id,
+        } => {
+            let location_id = upsert_location(&db, &location).await?;
+            let person_id = db
+                .add_person(
+                    &first,
+                    &last,
+                    location_id,
+                    timezone.as_deref(),
+                    slack_id.as_deref(),
+                )
+                .await?;
+            info!("Added {} {} in {}", first, last, location.city);
+            println!("Added {} {} with ID {}", first, last, person_id);
+        }
+
+        Commands::Move { id, location } => {
+            let location_id = upsert_location(&db, &location).await?;
+            if !db.move_person(id, location_id).await? {
+                return Err(anyhow!("No person with ID {}", id));
+            }
+            println!("Moved person {} to {}", id, location.city);
+        }
+
+        Commands::SetHome { id, lat, lon, .. } => {
+            if !db.set_person_home(id, lat, lon).await? {
+                return Err(anyhow!("No person with ID {}", id));
+            }
+            match (lat, lon) {
+                (Some(lat), Some(lon)) => {
+                    println!("Set home of person {} to ({}, {})", id, lat, lon)
+                }
+                _ => println!("Cleared home of person {}", id),
+            }
+        }
+
+        Commands::AddChannel { id, kind, address } => {
+            let kind = ChannelKind::from_name(&kind)
+                .ok_or_else(|| anyhow!("Unknown channel kind {}", kind))?;
+            let address = match (address, kind) {
+                (Some(address), _) => address,
+                (None, ChannelKind::Slack) => db
+                    .fetch_people()
+                    .await?
+                    .into_iter()
+                    .find(|person| person.id == id)
+                    .ok_or_else(|| anyhow!("No person with ID {}", id))?
+                    .slack_id
+                    .ok_or_else(|| anyhow!("Person {} has no Slack ID, give --address", id))?,
+                (None, _) => return Err(anyhow!("--address is required for {}", kind.as_str())),
+            };
+            let channel_id = db
+                .add_person_channel(id, kind, &address)
+                .await
+                .with_context(|| format!("Failed to add channel for person {}", id))?;
+            println!(
+                "Added {} channel {} for person {}",
+                kind.as_str(),
+                channel_id,
+                id
+            );
+        }
+
+        Commands::RemoveChannel { channel_id } => {
+            if !db.remove_person_channel(channel_id).await? {
+                return Err(anyhow!("No channel with ID {}", channel_id));
+            }
+            println!("Removed channel {}", channel_id);
+        }
+
+        Commands::QuietHours { id, hours, .. } => {
+            if let Some(hours) = &hours {
+                if QuietHours::parse(hours).is_none() {
+                    return Err(anyhow!("Quiet hours must look like 22:00-07:00"));
+                }
+            }
+            if !db.set_person_quiet_hours(id, hours.as_deref()).await? {
+                return Err(anyhow!("No person with ID {}", id));
+            }
+            match hours {
+                Some(hours) => println!("Set quiet hours of person {} to {}", id, hours),
+                None => println!("Cleared quiet hours of person {}", id),
+            }
+        }
+
+        Commands::Remove { id } => {
+            if !db.remove_person(id).await? {
+                return Err(anyhow!("No person with ID {}", id));
+            }
+            println!("Removed person {}", id);
+        }
+
+        Commands::Import { path } => {
+            let count = places::import_places_json(&db, &path)
+                .await
+                .map_err(|e| anyhow!(e))?;
+            println!("Imported {} people from {}", count, path);
+        }
+    }
+
+    Ok(())
+}
+
+async fn upsert_location(db: &Database, location: &LocationArgs) -> Result<i64> {
+    if location.lat.is_some() != location.lon.is_some() {
+        return Err(anyhow!("--lat and --lon must be given together"));
+    }
+    Ok(db
+        .upsert_location(
+            &location.continent,
+            &location.country,
+            &location.region,
+            &location.city,
+            location.lat,
+            location.lon,
+        )
+        .await?)
+}
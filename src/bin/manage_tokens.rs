@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use argus::app::auth;
+use argus::db::Database;
+use clap::{Parser, Subcommand};
+use tokio::main;
+
+#[derive(Parser)]
+#[command(author, version, about = "Manage app API signing keys and tokens", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Print a new random signing key secret
+    GenerateKey,
+
+    /// Revoke every token issued to a device so far
+    Revoke {
+        /// The device ID the tokens were issued to
+        #[arg(short, long)]
+        device_id: String,
+    },
+
+    /// Delete expired refresh tokens and revocations
+    Prune,
+}
+
+#[main]
+async fn main() -> Result<()> {
+    // Initialize tracing
+    argus::logging::configure_logging();
+
+    let cli = Cli::parse();
+    if let Commands::GenerateKey = cli.command {
+        println!("{}", auth::random_token());
+        return Ok(());
+    }
+
+    // Get database connection
+    let database_url = std::env::var("DATABASE_PATH").unwrap_or_else(|_| "argus.db".to_string());
+    let db = Database::new(&database_url)
+        .await
+        .context("Failed to connect to database")?;
+
+    match cli.command {
+        Commands::GenerateKey => {}
+
+        Commands::Revoke { device_id } => {
+            db.revoke_device(&device_id).await?;
+            println!("Revoked all tokens of device {}", device_id);
+        }
+
+        Commands::Prune => {
+            let count = db.prune_auth_tokens(auth::ACCESS_TOKEN_SECONDS).await?;
+            println!("Deleted {} expired tokens and revocations", count);
+        }
+    }
+
+    Ok(())
+}
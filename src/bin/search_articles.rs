@@ -0,0 +1,113 @@
+use anyhow::{Context, Result};
+use argus::db::Database;
+use argus::search::{self, SearchOptions, DEFAULT_SEMANTIC_WEIGHT};
+use clap::{Parser, Subcommand};
+use prettytable::{row, Table};
+use tokio::main;
+
+#[derive(Parser)]
+#[command(author, version, about = "Search analyzed articles by keyword", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Find articles containing every word of the query, best match first
+    Search {
+        /// Words to search for; a trailing * matches prefixes
+        query: Vec<String>,
+
+        /// Only search articles about this topic
+        #[arg(short, long)]
+        topic: Option<String>,
+
+        /// Maximum number of results
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+
+        /// Merge with semantic similarity from Qdrant
+        #[arg(long)]
+        hybrid: bool,
+
+        /// Share of the semantic similarity in hybrid scores (0.0 to 1.0)
+        #[arg(long, default_value_t = DEFAULT_SEMANTIC_WEIGHT)]
+        semantic_weight: f64,
+
+        /// Pass the query to FTS5 as is, e.g. '"rust async" NOT tokio'
+        #[arg(long)]
+        raw: bool,
+
+        /// Print results as JSON lines instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Index every analyzed article again
+    Reindex,
+}
+
+#[main]
+async fn main() -> Result<()> {
+    // Initialize tracing
+    argus::logging::configure_logging();
+
+    let cli = Cli::parse();
+
+    // Get database connection
+    let database_url = std::env::var("DATABASE_PATH").unwrap_or_else(|_| "argus.db".to_string());
+    let db = Database::new(&database_url)
+        .await
+        .context("Failed to connect to database")?;
+
+    match cli.command {
+        Commands::Search {
+            query,
+            topic,
+            limit,
+            hybrid,
+            semantic_weight,
+            raw,
+            json,
+        } => {
+            let options = SearchOptions {
+                topic,
+                limit,
+                semantic_weight: hybrid.then_some(semantic_weight),
+                raw,
+            };
+            let results = search::search(&db, &query.join(" "), &options).await?;
+            if json {
+                for result in &results {
+                    println!("{}", serde_json::to_string(result)?);
+                }
+                return Ok(());
+            }
+            if results.is_empty() {
+                println!("No articles found");
+                return Ok(());
+            }
+
+            let mut table = Table::new();
+            table.add_row(row!["ID", "Score", "Category", "Title", "Match"]);
+            for result in results {
+                table.add_row(row![
+                    result.id,
+                    format!("{:.3}", result.score),
+                    result.category.unwrap_or_default(),
+                    result.title.unwrap_or(result.url),
+                    result.snippet.unwrap_or_default()
+                ]);
+            }
+            table.printstd();
+        }
+
+        Commands::Reindex => {
+            let count = db.rebuild_search_index().await?;
+            println!("Indexed {} articles", count);
+        }
+    }
+
+    Ok(())
+}
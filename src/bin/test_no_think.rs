@@ -94,7 +94,8 @@ async fn main() -> anyhow::Result<()> {
     info!("Sending prompt: {}", test_prompt);
     let start = Instant::now();
 
-    match argus::llm::generate_llm_response(test_prompt, &llm_params, &worker_detail).await {
+    match argus::llm::generate_llm_response(test_prompt, &llm_params, &worker_detail, "test").await
+    {
         Some(response) => {
             let elapsed = start.elapsed();
             info!("Response received in {:?}:", elapsed);
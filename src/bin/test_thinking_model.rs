@@ -300,7 +300,9 @@ async fn main() -> anyhow::Result<()> {
         }
 
         // Use standard argus LLM processing
-        match argus::llm::generate_llm_response(&args.prompt, &llm_params, &worker_detail).await {
+        match argus::llm::generate_llm_response(&args.prompt, &llm_params, &worker_detail, "test")
+            .await
+        {
             Some(response) => {
                 info!("Response from thinking model:");
                 println!("\n{}", response);
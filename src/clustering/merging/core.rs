@@ -67,8 +67,7 @@ pub async fn merge_clusters(
     cluster::update_cluster_article_count(db, new_cluster_id, total_articles as i32).await?;
 
     // Step 5: Update user preferences
-    // TODO: Move this to db/cluster.rs module
-    // transfer_user_preferences(db, source_cluster_ids, new_cluster_id).await?;
+    cluster::transfer_user_preferences(db, source_cluster_ids, new_cluster_id).await?;
 
     // Step 6: Generate a new summary
     let _summary = match crate::clustering::summary::generate_cluster_summary(
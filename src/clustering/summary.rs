@@ -1,9 +1,12 @@
 use anyhow::{anyhow, Result};
+use serde_json::json;
 use std::collections::HashMap;
 
+use crate::app::util::send_story_update;
 use crate::clustering::types::{ClusterArticle, EntityDetail};
 use crate::db::cluster;
 use crate::db::core::Database;
+use crate::events::{self, EventKind};
 use crate::llm::generate_llm_response;
 use crate::{LLMClient, LLMParams, WorkerDetail};
 
@@ -69,7 +72,14 @@ pub async fn generate_cluster_summary(
     };
 
     // Generate the summary
-    let summary = match generate_llm_response(&prompt, &llm_params, &worker_detail).await {
+    let summary = match generate_llm_response(
+        &prompt,
+        &llm_params,
+        &worker_detail,
+        "cluster_summary",
+    )
+    .await
+    {
         Some(response) => response,
         None => return Err(anyhow!("Failed to generate summary")),
     };
@@ -77,6 +87,19 @@ pub async fn generate_cluster_summary(
     // Update the cluster with the new summary
     cluster::update_cluster_summary(db, cluster_id, &summary).await?;
 
+    // Let the devices following the story and the event stream know
+    if let Some(info) = cluster::get_cluster_info(db, cluster_id).await? {
+        send_story_update(db, cluster_id, info.summary_version, &summary).await;
+        let event = json!({
+            "cluster_id": cluster_id,
+            "version": info.summary_version,
+            "summary": summary,
+            "article_count": info.article_count,
+            "importance_score": info.importance_score,
+        });
+        events::publish(db, EventKind::ClusterUpdate, &[], &event).await;
+    }
+
     Ok(summary)
 }
 
@@ -0,0 +1,339 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::{debug, instrument};
+
+use super::core::Database;
+use crate::db::Row;
+use crate::TARGET_DB;
+
+/// The number of entries waiting in each queue.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueDepths {
+    pub rss_queue: i64,
+    pub matched_topics_queue: i64,
+    pub life_safety_queue: i64,
+    pub notification_batches: i64,
+    /// Personal alerts waiting to be sent or being sent
+    pub pending_alert_deliveries: i64,
+}
+
+impl QueueDepths {
+    /// Entries waiting in all queues together.
+    pub fn total(&self) -> i64 {
+        self.rss_queue
+            + self.matched_topics_queue
+            + self.life_safety_queue
+            + self.notification_batches
+            + self.pending_alert_deliveries
+    }
+}
+
+/// How much the workers got done recently, as recorded in the articles table.
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineActivity {
+    pub articles_last_hour: i64,
+    pub analyses_last_hour: i64,
+    /// When the latest article was decided on, RFC 3339
+    pub last_article_at: Option<String>,
+    /// When the latest analysis was stored, RFC 3339
+    pub last_analysis_at: Option<String>,
+}
+
+/// A notification or personal alert that was given up on.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentFailure {
+    /// `notification` or `alert`
+    pub kind: String,
+    /// The backend or channel, and the article or address
+    pub target: String,
+    pub error: Option<String>,
+    pub attempts: i64,
+    pub at: String,
+}
+
+/// An alias suggestion waiting for review.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingAlias {
+    pub id: i64,
+    pub canonical_name: String,
+    pub alias_text: String,
+    pub entity_type: String,
+    pub source: String,
+    pub confidence: f64,
+    pub created_at: String,
+}
+
+/// The alias review backlog.
+#[derive(Debug, Clone, Serialize)]
+pub struct AliasReviews {
+    pub pending: i64,
+    pub open_batches: i64,
+    /// The most confident pending suggestions
+    pub aliases: Vec<PendingAlias>,
+}
+
+/// A cluster merged into another.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterMerge {
+    pub original_cluster_id: i64,
+    pub merged_into_cluster_id: i64,
+    pub merge_date: String,
+    pub merge_reason: Option<String>,
+}
+
+/// The outcome of the latest fetch of an RSS feed.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedStatus {
+    pub url: String,
+    /// RFC 3339
+    pub last_fetched_at: Option<String>,
+    /// RFC 3339
+    pub last_success_at: Option<String>,
+    pub consecutive_failures: i64,
+    pub last_error: Option<String>,
+    pub last_new_articles: i64,
+}
+
+fn rfc3339(seconds: Option<i64>) -> Option<String> {
+    seconds
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        .map(|time| time.to_rfc3339())
+}
+
+impl Database {
+    /// Counts the entries waiting in each queue.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn queue_depths(&self) -> Result<QueueDepths, sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT (SELECT COUNT(*) FROM rss_queue) AS rss_queue,
+                   (SELECT COUNT(*) FROM matched_topics_queue) AS matched_topics_queue,
+                   (SELECT COUNT(*) FROM life_safety_queue) AS life_safety_queue,
+                   (SELECT COUNT(*) FROM notification_batches) AS notification_batches,
+                   (SELECT COUNT(*) FROM alert_deliveries
+                    WHERE status IN ('pending', 'sending')) AS pending_alert_deliveries
+            "#,
+        )
+        .fetch_one(self.pool())
+        .await?;
+        Ok(QueueDepths {
+            rss_queue: row.get("rss_queue"),
+            matched_topics_queue: row.get("matched_topics_queue"),
+            life_safety_queue: row.get("life_safety_queue"),
+            notification_batches: row.get("notification_batches"),
+            pending_alert_deliveries: row.get("pending_alert_deliveries"),
+        })
+    }
+
+    /// Summarizes the articles decided on and analyzed in the last hour.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn pipeline_activity(&self) -> Result<PipelineActivity, sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT COUNT(*) AS articles_last_hour,
+                   COUNT(analysis) AS analyses_last_hour,
+                   (SELECT MAX(CAST(seen_at AS INTEGER)) FROM articles) AS last_article_at,
+                   (SELECT CAST(seen_at AS INTEGER) FROM articles
+                    WHERE analysis IS NOT NULL ORDER BY id DESC LIMIT 1) AS last_analysis_at
+            FROM articles
+            WHERE CAST(seen_at AS INTEGER) >= ?1
+            "#,
+        )
+        .bind(Utc::now().timestamp() - 3600)
+        .fetch_one(self.pool())
+        .await?;
+        Ok(PipelineActivity {
+            articles_last_hour: row.get("articles_last_hour"),
+            analyses_last_hour: row.get("analyses_last_hour"),
+            last_article_at: rfc3339(row.get("last_article_at")),
+            last_analysis_at: rfc3339(row.get("last_analysis_at")),
+        })
+    }
+
+    /// Returns the latest notifications and personal alerts that failed for good.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn recent_failures(&self, limit: i64) -> Result<Vec<RecentFailure>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT 'notification' AS kind, backend || ': ' || article_url AS target,
+                   last_error, attempts, created_at AS at
+            FROM notification_deliveries WHERE status = 'failed'
+            UNION ALL
+            SELECT 'alert' AS kind, kind || ': ' || address AS target,
+                   last_error, attempts, created_at AS at
+            FROM alert_deliveries WHERE status = 'failed'
+            ORDER BY at DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(self.pool())
+        .await?;
+        Ok(rows
+            .iter()
+            .map(|row| RecentFailure {
+                kind: row.get("kind"),
+                target: row.get("target"),
+                error: row.get("last_error"),
+                attempts: row.get("attempts"),
+                at: row.get("at"),
+            })
+            .collect())
+    }
+
+    /// Returns the alias review backlog with up to `limit` of the most confident pending
+    /// suggestions.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn alias_reviews(&self, limit: i64) -> Result<AliasReviews, sqlx::Error> {
+        let pending: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM entity_aliases WHERE status = 'PENDING'")
+                .fetch_one(self.pool())
+                .await?;
+        let open_batches: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM alias_review_batches WHERE status = 'OPEN'")
+                .fetch_one(self.pool())
+                .await?;
+        let rows = sqlx::query(
+            r#"
+            SELECT id, canonical_name, alias_text, entity_type, source, confidence, created_at
+            FROM entity_aliases
+            WHERE status = 'PENDING'
+            ORDER BY confidence DESC, id
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(self.pool())
+        .await?;
+        Ok(AliasReviews {
+            pending,
+            open_batches,
+            aliases: rows
+                .iter()
+                .map(|row| PendingAlias {
+                    id: row.get("id"),
+                    canonical_name: row.get("canonical_name"),
+                    alias_text: row.get("alias_text"),
+                    entity_type: row.get("entity_type"),
+                    source: row.get("source"),
+                    confidence: row.get("confidence"),
+                    created_at: row.get("created_at"),
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns the latest cluster merges. Fails on databases that haven't been migrated
+    /// with `migrate_cluster_merge_schema`.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn recent_cluster_merges(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<ClusterMerge>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT original_cluster_id, merged_into_cluster_id, merge_date, merge_reason
+            FROM cluster_merge_history
+            ORDER BY merge_date DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(self.pool())
+        .await?;
+        Ok(rows
+            .iter()
+            .map(|row| ClusterMerge {
+                original_cluster_id: row.get("original_cluster_id"),
+                merged_into_cluster_id: row.get("merged_into_cluster_id"),
+                merge_date: row.get("merge_date"),
+                merge_reason: row.get("merge_reason"),
+            })
+            .collect())
+    }
+
+    /// Counts story clusters by status. Before `migrate_cluster_merge_schema` added the
+    /// status column nothing could be merged, so every cluster counts as active.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn cluster_counts(&self) -> Result<Vec<(String, i64)>, sqlx::Error> {
+        let rows = match sqlx::query(
+            "SELECT status, COUNT(*) AS count FROM article_clusters GROUP BY status ORDER BY status",
+        )
+        .fetch_all(self.pool())
+        .await
+        {
+            Ok(rows) => rows,
+            Err(e) => {
+                debug!(target: TARGET_DB, "Counting clusters without status: {:?}", e);
+                sqlx::query("SELECT 'active' AS status, COUNT(*) AS count FROM article_clusters")
+                    .fetch_all(self.pool())
+                    .await?
+            }
+        };
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("status"), row.get("count")))
+            .collect())
+    }
+
+    /// Records the outcome of fetching an RSS feed: the number of new articles, or the
+    /// error it failed with after all retries.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn record_feed_fetch(
+        &self,
+        url: &str,
+        new_articles: i64,
+        error: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO feed_status
+                (url, last_fetched_at, last_success_at, consecutive_failures, last_error,
+                 last_new_articles)
+            VALUES (?1, ?2, CASE WHEN ?3 IS NULL THEN ?2 END, CASE WHEN ?3 IS NULL THEN 0 ELSE 1 END,
+                    ?3, ?4)
+            ON CONFLICT (url) DO UPDATE SET
+                last_fetched_at = ?2,
+                last_success_at = CASE WHEN ?3 IS NULL THEN ?2 ELSE last_success_at END,
+                consecutive_failures =
+                    CASE WHEN ?3 IS NULL THEN 0 ELSE consecutive_failures + 1 END,
+                last_error = ?3,
+                last_new_articles = ?4
+            "#,
+        )
+        .bind(url)
+        .bind(Utc::now().timestamp())
+        .bind(error)
+        .bind(new_articles)
+        .execute(self.pool())
+        .await?;
+        debug!(target: TARGET_DB, "Recorded fetch of feed {}", url);
+        Ok(())
+    }
+
+    /// Returns the latest fetch outcome of every feed, failing feeds first.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn feed_health(&self) -> Result<Vec<FeedStatus>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT url, last_fetched_at, last_success_at, consecutive_failures, last_error,
+                   last_new_articles
+            FROM feed_status
+            ORDER BY consecutive_failures DESC, url
+            "#,
+        )
+        .fetch_all(self.pool())
+        .await?;
+        Ok(rows
+            .iter()
+            .map(|row| FeedStatus {
+                url: row.get("url"),
+                last_fetched_at: rfc3339(row.get("last_fetched_at")),
+                last_success_at: rfc3339(row.get("last_success_at")),
+                consecutive_failures: row.get("consecutive_failures"),
+                last_error: row.get("last_error"),
+                last_new_articles: row.get("last_new_articles"),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,285 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use tracing::{debug, instrument};
+
+use super::core::Database;
+use crate::alerts::ChannelKind;
+use crate::db::Row;
+use crate::TARGET_DB;
+
+/// Delivery times are stored to the second in UTC so they compare correctly as text.
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Somewhere a person receives life safety alerts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonChannel {
+    pub id: i64,
+    pub person_id: i64,
+    pub kind: ChannelKind,
+    /// Slack user ID, email address or webhook URL
+    pub address: String,
+}
+
+/// One alert to one person on one channel.
+#[derive(Debug, Clone)]
+pub struct AlertDelivery {
+    pub id: i64,
+    pub person_id: i64,
+    pub article_id: Option<i64>,
+    pub incident_id: Option<i64>,
+    pub kind: ChannelKind,
+    pub address: String,
+    pub urgency: String,
+    pub subject: String,
+    pub body: String,
+    pub deliver_after: DateTime<Utc>,
+    pub attempts: i64,
+    pub ack_token: String,
+}
+
+impl Database {
+    /// Adds a delivery channel for a person, returning its id.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn add_person_channel(
+        &self,
+        person_id: i64,
+        kind: ChannelKind,
+        address: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let (id,): (i64,) = sqlx::query_as(
+            r#"
+            INSERT INTO person_channels (person_id, kind, address, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(person_id, kind, address) DO UPDATE SET address = excluded.address
+            RETURNING id
+            "#,
+        )
+        .bind(person_id)
+        .bind(kind.as_str())
+        .bind(address.trim())
+        .bind(Utc::now().to_rfc3339())
+        .fetch_one(self.pool())
+        .await?;
+        Ok(id)
+    }
+
+    /// Removes a delivery channel. Returns false if there is no such channel.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn remove_person_channel(&self, channel_id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM person_channels WHERE id = ?1")
+            .bind(channel_id)
+            .execute(self.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns a person's delivery channels.
+    pub async fn fetch_person_channels(
+        &self,
+        person_id: i64,
+    ) -> Result<Vec<PersonChannel>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, person_id, kind, address FROM person_channels WHERE person_id = ?1 ORDER BY id",
+        )
+        .bind(person_id)
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let kind: String = row.get("kind");
+                Some(PersonChannel {
+                    id: row.get("id"),
+                    person_id: row.get("person_id"),
+                    kind: ChannelKind::from_name(&kind)?,
+                    address: row.get("address"),
+                })
+            })
+            .collect())
+    }
+
+    /// Queues an alert for delivery, returning its id.
+    pub async fn add_alert_delivery(&self, delivery: &AlertDelivery) -> Result<i64, sqlx::Error> {
+        let (id,): (i64,) = sqlx::query_as(
+            r#"
+            INSERT INTO alert_deliveries (
+                person_id, article_id, incident_id, kind, address, urgency, subject, body,
+                status, deliver_after, ack_token, created_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'pending', ?9, ?10, ?11)
+            RETURNING id
+            "#,
+        )
+        .bind(delivery.person_id)
+        .bind(delivery.article_id)
+        .bind(delivery.incident_id)
+        .bind(delivery.kind.as_str())
+        .bind(&delivery.address)
+        .bind(&delivery.urgency)
+        .bind(&delivery.subject)
+        .bind(&delivery.body)
+        .bind(timestamp(delivery.deliver_after))
+        .bind(&delivery.ack_token)
+        .bind(Utc::now().to_rfc3339())
+        .fetch_one(self.pool())
+        .await?;
+        debug!(target: TARGET_DB, "Queued {} alert {} for person {}", delivery.kind.as_str(), id, delivery.person_id);
+        Ok(id)
+    }
+
+    /// Returns pending alerts that are due, oldest first.
+    pub async fn fetch_due_alert_deliveries(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<AlertDelivery>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, person_id, article_id, incident_id, kind, address, urgency, subject, body,
+                   deliver_after, attempts, ack_token
+            FROM alert_deliveries
+            WHERE status = 'pending' AND deliver_after <= ?1
+            ORDER BY deliver_after
+            LIMIT ?2
+            "#,
+        )
+        .bind(timestamp(Utc::now()))
+        .bind(limit)
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let kind: String = row.get("kind");
+                let deliver_after: String = row.get("deliver_after");
+                Some(AlertDelivery {
+                    id: row.get("id"),
+                    person_id: row.get("person_id"),
+                    article_id: row.get("article_id"),
+                    incident_id: row.get("incident_id"),
+                    kind: ChannelKind::from_name(&kind)?,
+                    address: row.get("address"),
+                    urgency: row.get("urgency"),
+                    subject: row.get("subject"),
+                    body: row.get("body"),
+                    deliver_after: DateTime::parse_from_rfc3339(&deliver_after)
+                        .ok()?
+                        .with_timezone(&Utc),
+                    attempts: row.get("attempts"),
+                    ack_token: row.get("ack_token"),
+                })
+            })
+            .collect())
+    }
+
+    /// Claims a pending alert for sending. Returns false if another worker got it first.
+    pub async fn claim_alert_delivery(&self, delivery_id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE alert_deliveries SET status = 'sending', attempts = attempts + 1, claimed_at = ?1
+            WHERE id = ?2 AND status = 'pending'
+            "#,
+        )
+        .bind(timestamp(Utc::now()))
+        .bind(delivery_id)
+        .execute(self.pool())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Puts alerts claimed before `before` back in the queue, so alerts whose sender died
+    /// mid-delivery are sent again. Returns how many were released.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn release_stale_alert_claims(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE alert_deliveries SET status = 'pending', claimed_at = NULL
+            WHERE status = 'sending' AND (claimed_at IS NULL OR claimed_at < ?1)
+            "#,
+        )
+        .bind(timestamp(before))
+        .execute(self.pool())
+        .await?;
+        if result.rows_affected() > 0 {
+            debug!(target: TARGET_DB, "Released {} stale alert claims", result.rows_affected());
+        }
+        Ok(result.rows_affected())
+    }
+
+    pub async fn mark_alert_delivered(&self, delivery_id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE alert_deliveries SET status = 'sent', sent_at = ?1, last_error = NULL WHERE id = ?2",
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(delivery_id)
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Records a failed attempt. The alert is retried at `retry_at`, or given up on if
+    /// there is none.
+    pub async fn mark_alert_failed(
+        &self,
+        delivery_id: i64,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), sqlx::Error> {
+        let (status, deliver_after) = match retry_at {
+            Some(retry_at) => ("pending", Some(timestamp(retry_at))),
+            None => ("failed", None),
+        };
+        sqlx::query(
+            r#"
+            UPDATE alert_deliveries
+            SET status = ?1, last_error = ?2, deliver_after = COALESCE(?3, deliver_after)
+            WHERE id = ?4
+            "#,
+        )
+        .bind(status)
+        .bind(error)
+        .bind(deliver_after)
+        .bind(delivery_id)
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Records that a person acknowledged an alert, on every channel it was sent to.
+    /// Returns false if the token is unknown.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn acknowledge_alert(&self, ack_token: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE alert_deliveries SET acknowledged_at = COALESCE(acknowledged_at, ?1)
+            WHERE (person_id, IFNULL(article_id, -1), IFNULL(incident_id, -1)) = (
+                SELECT person_id, IFNULL(article_id, -1), IFNULL(incident_id, -1)
+                FROM alert_deliveries WHERE ack_token = ?2
+            )
+            "#,
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(ack_token)
+        .execute(self.pool())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns the people who were sent alerts about an incident.
+    pub async fn fetch_incident_recipients(
+        &self,
+        incident_id: i64,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT DISTINCT person_id FROM alert_deliveries WHERE incident_id = ?1 AND status = 'sent'",
+        )
+        .bind(incident_id)
+        .fetch_all(self.pool())
+        .await
+    }
+}
@@ -7,6 +7,8 @@ use url::Url;
 use urlnorm::UrlNormalizer;
 
 use super::core::{Database, DbLockErrorExt};
+use crate::fingerprint::{self, Signature};
+use crate::topics::TopicMatch;
 use crate::TARGET_DB;
 
 impl Database {
@@ -23,6 +25,7 @@ impl Database {
         r2_url: Option<&str>,
         pub_date: Option<&str>,
         event_date: Option<&str>,
+        language: Option<&str>,
     ) -> Result<i64, sqlx::Error> {
         // Parse the URL
         let parsed_url = match Url::parse(url) {
@@ -48,8 +51,8 @@ impl Database {
         for attempt in 1..=max_retries {
             match sqlx::query_as::<_, (i64,)>(
             r#"
-            INSERT INTO articles (url, normalized_url, seen_at, pub_date, event_date, is_relevant, category, analysis, tiny_summary, hash, title_domain_hash, r2_url)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            INSERT INTO articles (url, normalized_url, seen_at, pub_date, event_date, is_relevant, category, analysis, tiny_summary, hash, title_domain_hash, r2_url, language)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
             ON CONFLICT(normalized_url) DO UPDATE SET
                 url = excluded.url,
                 seen_at = excluded.seen_at,
@@ -61,7 +64,8 @@ impl Database {
                 tiny_summary = excluded.tiny_summary,
                 hash = excluded.hash,
                 title_domain_hash = excluded.title_domain_hash,
-                r2_url = excluded.r2_url
+                r2_url = excluded.r2_url,
+                language = COALESCE(excluded.language, language)
             RETURNING id
             "#,
         )
@@ -77,10 +81,16 @@ impl Database {
         .bind(hash)
         .bind(title_domain_hash)
         .bind(r2_url)
+        .bind(language)
         .fetch_one(self.pool())
         .await {
             Ok((id,)) => {
                 debug!(target: TARGET_DB, "Article added/updated: {} with id {}", url, id);
+                if analysis.is_some() {
+                    if let Err(e) = self.index_article_for_search(id).await {
+                        error!(target: TARGET_DB, "Failed to index article {} for search: {}", id, e);
+                    }
+                }
                 return Ok(id);
             }
             Err(err) => {
@@ -140,6 +150,130 @@ impl Database {
         }
     }
 
+    /// Records every topic an article matched, with the confidence of each match.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn add_article_topics(
+        &self,
+        article_id: i64,
+        matches: &[TopicMatch],
+    ) -> Result<(), sqlx::Error> {
+        let matched_at = chrono::Utc::now().to_rfc3339();
+        for topic_match in matches {
+            sqlx::query(
+                r#"
+                INSERT INTO article_topics (article_id, topic, confidence, matched_at)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT(article_id, topic) DO UPDATE SET confidence = excluded.confidence
+                "#,
+            )
+            .bind(article_id)
+            .bind(&topic_match.topic)
+            .bind(topic_match.confidence)
+            .bind(&matched_at)
+            .execute(self.pool())
+            .await?;
+        }
+        debug!(target: TARGET_DB, "Recorded {} topics for article {}", matches.len(), article_id);
+        Ok(())
+    }
+
+    /// Stores an article's MinHash signature and its LSH bands.
+    #[instrument(target = "db", level = "info", skip(self, signature))]
+    pub async fn set_article_fingerprint(
+        &self,
+        article_id: i64,
+        signature: &Signature,
+    ) -> Result<(), sqlx::Error> {
+        let mut transaction = self.pool().begin().await?;
+
+        sqlx::query("UPDATE articles SET minhash = ?1 WHERE id = ?2")
+            .bind(fingerprint::to_bytes(signature))
+            .bind(article_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        for (band, band_hash) in fingerprint::band_hashes(signature).iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO article_minhash_bands (article_id, band, band_hash)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT(article_id, band) DO UPDATE SET band_hash = excluded.band_hash
+                "#,
+            )
+            .bind(article_id)
+            .bind(band as i64)
+            .bind(band_hash)
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Finds an earlier article whose estimated Jaccard similarity to `signature` is at
+    /// least `threshold`.
+    ///
+    /// Returns the canonical article (following an existing duplicate link) and the
+    /// similarity of the closest match.
+    #[instrument(target = "db", level = "info", skip(self, signature))]
+    pub async fn find_near_duplicate(
+        &self,
+        signature: &Signature,
+        threshold: f64,
+    ) -> Result<Option<(i64, f64)>, sqlx::Error> {
+        let band_hashes = fingerprint::band_hashes(signature);
+        let conditions = (0..band_hashes.len())
+            .map(|band| format!("(b.band = {} AND b.band_hash = ?{})", band, band + 1))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        let query = format!(
+            r#"
+            SELECT DISTINCT a.id, a.minhash, a.canonical_article_id
+            FROM article_minhash_bands b
+            JOIN articles a ON a.id = b.article_id
+            WHERE {}
+            "#,
+            conditions
+        );
+
+        let mut query_builder = sqlx::query(&query);
+        for band_hash in band_hashes {
+            query_builder = query_builder.bind(band_hash);
+        }
+        let rows = query_builder.fetch_all(self.pool()).await?;
+
+        let best = rows
+            .into_iter()
+            .filter_map(|row| {
+                let id: i64 = row.get("id");
+                let canonical_id: Option<i64> = row.get("canonical_article_id");
+                let bytes: Option<Vec<u8>> = row.get("minhash");
+                let candidate = fingerprint::from_bytes(&bytes?)?;
+                let similarity = fingerprint::similarity(signature, &candidate);
+                Some((canonical_id.unwrap_or(id), similarity))
+            })
+            .filter(|(_, similarity)| *similarity >= threshold)
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        Ok(best)
+    }
+
+    /// Marks an article as a near-duplicate of `canonical_article_id`.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn link_to_canonical(
+        &self,
+        article_id: i64,
+        canonical_article_id: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE articles SET canonical_article_id = ?1 WHERE id = ?2")
+            .bind(canonical_article_id)
+            .bind(article_id)
+            .execute(self.pool())
+            .await?;
+        Ok(())
+    }
+
     #[instrument(target = "db", level = "info", skip(self))]
     pub async fn has_seen(&self, url: &str) -> Result<bool, sqlx::Error> {
         debug!(target: TARGET_DB, "Checking if article has been seen: {}", url);
@@ -282,10 +416,17 @@ impl Database {
             .collect::<Vec<_>>()
             .join(",");
 
+        // Articles match on their primary category or on any other topic they matched
         let category_condition = if include_alerts {
-            format!("(category IN ({}) OR category IS NULL)", topic_placeholders)
+            format!(
+                "(category IN ({0}) OR id IN (SELECT article_id FROM article_topics WHERE topic IN ({0})) OR category IS NULL)",
+                topic_placeholders
+            )
         } else {
-            format!("category IN ({})", topic_placeholders)
+            format!(
+                "(category IN ({0}) OR id IN (SELECT article_id FROM article_topics WHERE topic IN ({0})))",
+                topic_placeholders
+            )
         };
 
         let query = if seen_articles.is_empty() {
@@ -325,8 +466,8 @@ impl Database {
             query_builder = query_builder.bind(article);
         }
 
-        // Bind subscribed topics
-        for topic in &subscribed_topics {
+        // Bind subscribed topics, once for the category and once for article_topics
+        for topic in subscribed_topics.iter().chain(subscribed_topics.iter()) {
             query_builder = query_builder.bind(topic);
         }
 
@@ -0,0 +1,349 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{QueryBuilder, Sqlite};
+use tracing::{debug, instrument};
+
+use super::core::Database;
+use crate::db::Row;
+use crate::TARGET_DB;
+
+/// Filters for listing analyzed articles. Every filter that is set must match.
+#[derive(Debug, Clone, Default)]
+pub struct ArticleFilter {
+    /// The article's category or any other topic it matched
+    pub topic: Option<String>,
+    /// Seen at or after
+    pub since: Option<DateTime<Utc>>,
+    /// Seen before
+    pub until: Option<DateTime<Utc>>,
+    /// Minimum combined quality score (-2 to 4)
+    pub min_quality: Option<i64>,
+    /// Source domain, with or without "www."
+    pub domain: Option<String>,
+    pub cluster_id: Option<i64>,
+    /// Only articles with a lower ID, i.e. the cursor of the previous page
+    pub before_id: Option<i64>,
+}
+
+/// An analyzed article as served by the read API.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArticleRecord {
+    pub id: i64,
+    pub url: String,
+    pub title: Option<String>,
+    pub tiny_title: Option<String>,
+    pub tiny_summary: Option<String>,
+    pub category: Option<String>,
+    pub topics: Vec<String>,
+    pub quality: Option<i64>,
+    pub pub_date: Option<String>,
+    pub event_date: Option<String>,
+    /// When Argus first saw the article, RFC 3339
+    pub seen_at: Option<String>,
+    pub language: Option<String>,
+    pub cluster_id: Option<i64>,
+    pub r2_url: Option<String>,
+    /// The full analysis, only included when fetching a single article
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<Value>,
+}
+
+const ARTICLE_COLUMNS: &str = r#"
+    SELECT a.id, a.url, a.category, a.tiny_summary, a.analysis, a.pub_date, a.event_date,
+           a.seen_at, a.language, a.cluster_id, a.r2_url,
+           (SELECT GROUP_CONCAT(t.topic, '|') FROM article_topics t WHERE t.article_id = a.id) AS topics
+    FROM articles a
+    WHERE a.analysis IS NOT NULL
+"#;
+
+/// LIKE patterns matching URLs whose host is `domain` or its "www." subdomain, followed
+/// by the end of the URL, a port, a path, a query or a fragment.
+fn domain_patterns(domain: &str) -> Vec<String> {
+    let domain = domain.trim().trim_start_matches("www.").to_lowercase();
+    let escaped = domain
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    let mut patterns = Vec::new();
+    for host in [escaped.clone(), format!("www.{}", escaped)] {
+        for end in ["", ":%", "/%", "?%", "#%"] {
+            patterns.push(format!("%://{}{}", host, end));
+        }
+    }
+    patterns
+}
+
+fn article_from_row(row: &sqlx::sqlite::SqliteRow, with_analysis: bool) -> ArticleRecord {
+    let analysis: Option<String> = row.get("analysis");
+    let analysis: Value = analysis
+        .and_then(|analysis| serde_json::from_str(&analysis).ok())
+        .unwrap_or_default();
+    let category: Option<String> = row.get("category");
+    let topics: Option<String> = row.get("topics");
+    let mut topics: Vec<String> = topics
+        .map(|topics| topics.split('|').map(str::to_string).collect())
+        .unwrap_or_default();
+    if topics.is_empty() {
+        topics.extend(category.clone());
+    }
+    let seen_at: Option<String> = row.get("seen_at");
+    ArticleRecord {
+        id: row.get("id"),
+        url: row.get("url"),
+        title: analysis["title"].as_str().map(str::to_string),
+        tiny_title: analysis["tiny_title"].as_str().map(str::to_string),
+        tiny_summary: row.get("tiny_summary"),
+        category,
+        topics,
+        quality: analysis["quality"].as_i64(),
+        pub_date: row.get("pub_date"),
+        event_date: row.get("event_date"),
+        seen_at: seen_at
+            .and_then(|seen_at| seen_at.parse::<i64>().ok())
+            .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+            .map(|seen_at| seen_at.to_rfc3339()),
+        language: row.get("language"),
+        cluster_id: row.get("cluster_id"),
+        r2_url: row.get("r2_url"),
+        analysis: with_analysis.then_some(analysis),
+    }
+}
+
+impl Database {
+    /// Returns an analyzed article with its full analysis.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn fetch_article(&self, id: i64) -> Result<Option<ArticleRecord>, sqlx::Error> {
+        let row = sqlx::query(&format!("{} AND a.id = ?1", ARTICLE_COLUMNS))
+            .bind(id)
+            .fetch_optional(self.pool())
+            .await?;
+        Ok(row.map(|row| article_from_row(&row, true)))
+    }
+
+    /// Returns up to `limit` analyzed articles matching `filter`, newest first.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn list_articles(
+        &self,
+        filter: &ArticleFilter,
+        limit: i64,
+    ) -> Result<Vec<ArticleRecord>, sqlx::Error> {
+        let mut query: QueryBuilder<Sqlite> = QueryBuilder::new(ARTICLE_COLUMNS);
+        if let Some(topic) = &filter.topic {
+            query
+                .push(" AND (a.category = ")
+                .push_bind(topic)
+                .push(" OR a.id IN (SELECT article_id FROM article_topics WHERE topic = ")
+                .push_bind(topic)
+                .push("))");
+        }
+        if let Some(since) = filter.since {
+            query
+                .push(" AND CAST(a.seen_at AS INTEGER) >= ")
+                .push_bind(since.timestamp());
+        }
+        if let Some(until) = filter.until {
+            query
+                .push(" AND CAST(a.seen_at AS INTEGER) < ")
+                .push_bind(until.timestamp());
+        }
+        if let Some(min_quality) = filter.min_quality {
+            query
+                .push(" AND CAST(json_extract(a.analysis, '$.quality') AS INTEGER) >= ")
+                .push_bind(min_quality);
+        }
+        if let Some(domain) = &filter.domain {
+            query.push(" AND (");
+            let mut patterns = query.separated(" OR ");
+            for pattern in domain_patterns(domain) {
+                patterns
+                    .push("lower(a.url) LIKE ")
+                    .push_bind_unseparated(pattern)
+                    .push_unseparated(" ESCAPE '\\'");
+            }
+            query.push(")");
+        }
+        if let Some(cluster_id) = filter.cluster_id {
+            query.push(" AND a.cluster_id = ").push_bind(cluster_id);
+        }
+        if let Some(before_id) = filter.before_id {
+            query.push(" AND a.id < ").push_bind(before_id);
+        }
+        query.push(" ORDER BY a.id DESC LIMIT ").push_bind(limit);
+
+        let rows = query.build().fetch_all(self.pool()).await?;
+        debug!(target: TARGET_DB, "Listed {} articles", rows.len());
+        Ok(rows
+            .iter()
+            .map(|row| article_from_row(row, false))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn add_article(
+        db: &Database,
+        url: &str,
+        seen_at: i64,
+        category: &str,
+        quality: i64,
+        cluster_id: Option<i64>,
+    ) -> i64 {
+        let analysis = json!({ "title": url, "quality": quality }).to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO articles (url, normalized_url, seen_at, is_relevant, category, analysis, cluster_id)
+            VALUES (?1, ?1, ?2, 1, ?3, ?4, ?5)
+            RETURNING id
+            "#,
+        )
+        .bind(url)
+        .bind(seen_at.to_string())
+        .bind(category)
+        .bind(analysis)
+        .bind(cluster_id)
+        .fetch_one(db.pool())
+        .await
+        .unwrap()
+        .get("id")
+    }
+
+    async fn list(db: &Database, filter: ArticleFilter) -> Vec<String> {
+        db.list_articles(&filter, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|article| article.url)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_list_articles_filters() {
+        let db = Database::in_memory().await;
+        let day = 86_400;
+        add_article(&db, "https://example.com", day, "Rust", 3, Some(7)).await;
+        let port = add_article(
+            &db,
+            "https://www.example.com:8443/b",
+            2 * day,
+            "Go",
+            1,
+            None,
+        )
+        .await;
+        add_article(
+            &db,
+            "https://notexample.com/c",
+            3 * day,
+            "Rust",
+            -1,
+            Some(7),
+        )
+        .await;
+        add_article(
+            &db,
+            "https://example.com.evil.org/d",
+            4 * day,
+            "Rust",
+            2,
+            None,
+        )
+        .await;
+        add_article(&db, "https://exxmple.com/e?q=1", 5 * day, "Rust", 2, None).await;
+        sqlx::query(
+            "INSERT INTO article_topics (article_id, topic, matched_at) VALUES (?1, 'Rust', '')",
+        )
+        .bind(port)
+        .execute(db.pool())
+        .await
+        .unwrap();
+        // Articles that were never analyzed are not listed
+        sqlx::query(
+            "INSERT INTO articles (url, normalized_url, seen_at, is_relevant) VALUES ('https://example.com/raw', 'raw', '0', 0)",
+        )
+        .execute(db.pool())
+        .await
+        .unwrap();
+
+        let domain = |domain: &str| ArticleFilter {
+            domain: Some(domain.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            list(&db, domain("www.Example.com")).await,
+            vec!["https://www.example.com:8443/b", "https://example.com"]
+        );
+        assert!(list(&db, domain("ex_mple.com")).await.is_empty());
+        assert!(list(&db, domain("%")).await.is_empty());
+
+        let topic = ArticleFilter {
+            topic: Some("Go".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            list(&db, topic).await,
+            vec!["https://www.example.com:8443/b"]
+        );
+        let topic = ArticleFilter {
+            topic: Some("Rust".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(list(&db, topic).await.len(), 5);
+
+        let dates = ArticleFilter {
+            since: DateTime::from_timestamp(2 * day, 0),
+            until: DateTime::from_timestamp(4 * day, 0),
+            ..Default::default()
+        };
+        assert_eq!(
+            list(&db, dates).await,
+            vec!["https://notexample.com/c", "https://www.example.com:8443/b"]
+        );
+
+        let quality = ArticleFilter {
+            min_quality: Some(2),
+            cluster_id: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(list(&db, quality).await, vec!["https://example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_articles_cursor() {
+        let db = Database::in_memory().await;
+        let mut ids = Vec::new();
+        for n in 0..5 {
+            let url = format!("https://example.com/{}", n);
+            ids.push(add_article(&db, &url, n, "Rust", 2, None).await);
+        }
+
+        let first = db
+            .list_articles(&ArticleFilter::default(), 2)
+            .await
+            .unwrap();
+        let first: Vec<i64> = first.iter().map(|article| article.id).collect();
+        assert_eq!(first, vec![ids[4], ids[3]]);
+
+        let next = ArticleFilter {
+            before_id: first.last().copied(),
+            ..Default::default()
+        };
+        let second: Vec<i64> = db
+            .list_articles(&next, 10)
+            .await
+            .unwrap()
+            .iter()
+            .map(|article| article.id)
+            .collect();
+        assert_eq!(second, vec![ids[2], ids[1], ids[0]]);
+
+        let article = db.fetch_article(ids[0]).await.unwrap().unwrap();
+        assert_eq!(article.analysis.unwrap()["quality"], 2);
+        assert_eq!(article.topics, vec!["Rust"]);
+        assert!(db.fetch_article(ids[4] + 1).await.unwrap().is_none());
+    }
+}
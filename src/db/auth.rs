@@ -0,0 +1,158 @@
+use chrono::Utc;
+use tracing::{debug, instrument};
+
+use super::core::Database;
+use crate::TARGET_DB;
+
+impl Database {
+    /// Stores the hash of a refresh token issued to a device, valid until `expires_at`
+    /// (unix seconds).
+    #[instrument(target = "db", level = "info", skip(self, token_hash))]
+    pub async fn store_refresh_token(
+        &self,
+        token_hash: &str,
+        device_id: &str,
+        expires_at: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (token_hash, device_id, issued_at, expires_at)
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+        )
+        .bind(token_hash)
+        .bind(device_id)
+        .bind(Utc::now().timestamp())
+        .bind(expires_at)
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Consumes a refresh token. Returns false unless it exists, hasn't expired and was
+    /// issued to `device_id`; a token presented for another device is left alone.
+    #[instrument(target = "db", level = "info", skip(self, token_hash))]
+    pub async fn take_refresh_token(
+        &self,
+        token_hash: &str,
+        device_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM refresh_tokens
+            WHERE token_hash = ?1 AND device_id = ?2 AND expires_at > ?3
+            "#,
+        )
+        .bind(token_hash)
+        .bind(device_id)
+        .bind(Utc::now().timestamp())
+        .execute(self.pool())
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Adds an access token to the revocation list until it expires.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn revoke_token(
+        &self,
+        jti: &str,
+        device_id: &str,
+        expires_at: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO revoked_tokens (jti, device_id, revoked_at, expires_at)
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+        )
+        .bind(jti)
+        .bind(device_id)
+        .bind(Utc::now().timestamp())
+        .bind(expires_at)
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes every refresh token issued to a device.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn revoke_refresh_tokens(&self, device_id: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM refresh_tokens WHERE device_id = ?1")
+            .bind(device_id)
+            .execute(self.pool())
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Revokes every access token issued to a device so far and deletes its refresh
+    /// tokens. The device can authenticate again afterwards.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn revoke_device(&self, device_id: &str) -> Result<(), sqlx::Error> {
+        let now = Utc::now().timestamp();
+        let mut transaction = self.pool().begin().await?;
+        sqlx::query("DELETE FROM refresh_tokens WHERE device_id = ?1")
+            .bind(device_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO revoked_devices (device_id, revoked_before) VALUES (?1, ?2)
+            ON CONFLICT (device_id) DO UPDATE SET revoked_before = excluded.revoked_before
+            "#,
+        )
+        .bind(device_id)
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        debug!(target: TARGET_DB, "Revoked all tokens of device {}", device_id);
+        Ok(())
+    }
+
+    /// Returns whether an access token is on the revocation list, by its own ID or
+    /// because its device was revoked after `issued_at` (unix seconds).
+    pub async fn is_token_revoked(
+        &self,
+        jti: &str,
+        device_id: &str,
+        issued_at: i64,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM revoked_tokens WHERE jti = ?1)
+                OR EXISTS (SELECT 1 FROM revoked_devices
+                           WHERE device_id = ?2 AND revoked_before > ?3)
+            "#,
+        )
+        .bind(jti)
+        .bind(device_id)
+        .bind(issued_at)
+        .fetch_one(self.pool())
+        .await
+    }
+
+    /// Deletes expired refresh tokens and revocations that no longer matter, given how
+    /// long access tokens live. Returns the number of rows deleted.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn prune_auth_tokens(&self, access_token_seconds: i64) -> Result<u64, sqlx::Error> {
+        let now = Utc::now().timestamp();
+        let mut deleted = 0;
+        deleted += sqlx::query("DELETE FROM refresh_tokens WHERE expires_at <= ?1")
+            .bind(now)
+            .execute(self.pool())
+            .await?
+            .rows_affected();
+        deleted += sqlx::query("DELETE FROM revoked_tokens WHERE expires_at <= ?1")
+            .bind(now)
+            .execute(self.pool())
+            .await?
+            .rows_affected();
+        deleted += sqlx::query("DELETE FROM revoked_devices WHERE revoked_before <= ?1")
+            .bind(now - access_token_seconds)
+            .execute(self.pool())
+            .await?
+            .rows_affected();
+        debug!(target: TARGET_DB, "Pruned {} expired auth tokens", deleted);
+        Ok(deleted)
+    }
+}
@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Result};
 use chrono::Utc;
+use serde::Serialize;
 use sqlx::{self, Row};
 use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
@@ -577,6 +578,41 @@ pub async fn get_active_clusters(db: &Database) -> Result<Vec<ActiveClusterInfo>
     Ok(clusters)
 }
 
+/// Gets a cluster by ID, whatever its status
+///
+/// # Arguments
+/// * `db` - Database instance
+/// * `cluster_id` - ID of the cluster
+///
+/// # Returns
+/// * `Ok(Some(ActiveClusterInfo))` - The cluster information
+/// * `Ok(None)` - If there is no such cluster
+/// * `Err` - If there was an error during retrieval
+pub async fn get_cluster_info(db: &Database, cluster_id: i64) -> Result<Option<ActiveClusterInfo>> {
+    let row = sqlx::query(
+        r#"
+        SELECT id, summary_version, creation_date, last_updated, summary,
+               article_count, importance_score, has_timeline
+        FROM article_clusters
+        WHERE id = ?
+        "#,
+    )
+    .bind(cluster_id)
+    .fetch_optional(db.pool())
+    .await?;
+
+    Ok(row.map(|row| ActiveClusterInfo {
+        id: row.get("id"),
+        summary_version: row.get("summary_version"),
+        creation_date: row.get("creation_date"),
+        last_updated: row.get("last_updated"),
+        summary: row.get("summary"),
+        article_count: row.get("article_count"),
+        importance_score: row.get("importance_score"),
+        has_timeline: row.get::<i32, _>("has_timeline") != 0,
+    }))
+}
+
 /// Struct to hold active cluster information
 pub struct ActiveClusterInfo {
     pub id: i64,
@@ -897,3 +933,231 @@ pub async fn update_cluster_article_count(
 
     Ok(())
 }
+
+/// A device's preference for one cluster
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterPreference {
+    pub cluster_id: i64,
+    pub followed: bool,
+    pub silenced: bool,
+    pub last_seen_version: i32,
+}
+
+/// Sets whether a device follows and/or silences a cluster, leaving whatever is `None`
+/// unchanged (or off, for a cluster the device had no preference for)
+///
+/// # Arguments
+/// * `db` - Database instance
+/// * `device_id` - The device token
+/// * `cluster_id` - ID of the cluster
+/// * `followed` - Whether the device follows the cluster's story updates
+/// * `silenced` - Whether the device gets no notifications about the cluster at all
+///
+/// # Returns
+/// * `Ok(())` - If the preference was stored
+/// * `Err` - If there was an error during the update
+pub async fn set_cluster_preference(
+    db: &Database,
+    device_id: &str,
+    cluster_id: i64,
+    followed: Option<bool>,
+    silenced: Option<bool>,
+) -> Result<()> {
+    let user_id = db.add_device(device_id).await?;
+    let now = Utc::now().to_rfc3339();
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_cluster_preferences
+            (user_id, cluster_id, silenced, followed, last_interaction)
+        VALUES (?1, ?2, COALESCE(?3, 0), COALESCE(?4, 0), ?5)
+        ON CONFLICT (user_id, cluster_id) DO UPDATE SET
+            silenced = COALESCE(?3, silenced),
+            followed = COALESCE(?4, followed),
+            last_interaction = ?5
+        "#,
+    )
+    .bind(user_id)
+    .bind(cluster_id)
+    .bind(silenced)
+    .bind(followed)
+    .bind(&now)
+    .execute(db.pool())
+    .await?;
+
+    debug!(
+        "Device {} cluster {} preference: followed {:?}, silenced {:?}",
+        device_id, cluster_id, followed, silenced
+    );
+    Ok(())
+}
+
+/// Gets a device's cluster preferences
+///
+/// # Arguments
+/// * `db` - Database instance
+/// * `device_id` - The device token
+///
+/// # Returns
+/// * `Ok(Vec<ClusterPreference>)` - The preferences, by cluster ID
+/// * `Err` - If there was an error during retrieval
+pub async fn get_cluster_preferences(
+    db: &Database,
+    device_id: &str,
+) -> Result<Vec<ClusterPreference>> {
+    let rows = sqlx::query(
+        r#"
+        SELECT p.cluster_id, p.followed, p.silenced, p.last_seen_version
+        FROM user_cluster_preferences p
+        JOIN devices d ON d.id = p.user_id
+        WHERE d.device_id = ?
+        ORDER BY p.cluster_id
+        "#,
+    )
+    .bind(device_id)
+    .fetch_all(db.pool())
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ClusterPreference {
+            cluster_id: row.get("cluster_id"),
+            followed: row.get::<i32, _>("followed") != 0,
+            silenced: row.get::<i32, _>("silenced") != 0,
+            last_seen_version: row.get("last_seen_version"),
+        })
+        .collect())
+}
+
+/// Records the summary versions a device has seen of the clusters it has preferences for
+///
+/// # Arguments
+/// * `db` - Database instance
+/// * `device_id` - The device token
+/// * `versions` - Cluster IDs and the summary version the device has of each
+///
+/// # Returns
+/// * `Ok(())` - If the versions were recorded
+/// * `Err` - If there was an error during the update
+pub async fn record_seen_cluster_versions(
+    db: &Database,
+    device_id: &str,
+    versions: &HashMap<i64, i32>,
+) -> Result<()> {
+    let mut tx = db.pool().begin().await?;
+    for (&cluster_id, &version) in versions {
+        sqlx::query(
+            r#"
+            UPDATE user_cluster_preferences
+            SET last_seen_version = MAX(last_seen_version, ?1)
+            WHERE cluster_id = ?2
+              AND user_id = (SELECT id FROM devices WHERE device_id = ?3)
+            "#,
+        )
+        .bind(version)
+        .bind(cluster_id)
+        .bind(device_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Gets the devices following a cluster that haven't seen a summary version yet
+///
+/// # Arguments
+/// * `db` - Database instance
+/// * `cluster_id` - ID of the cluster
+/// * `version` - The new summary version
+///
+/// # Returns
+/// * `Ok(Vec<String>)` - The device tokens to send a story update to
+/// * `Err` - If there was an error during retrieval
+pub async fn get_cluster_followers(
+    db: &Database,
+    cluster_id: i64,
+    version: i32,
+) -> Result<Vec<String>> {
+    let followers = sqlx::query_scalar(
+        r#"
+        SELECT d.device_id
+        FROM user_cluster_preferences p
+        JOIN devices d ON d.id = p.user_id
+        WHERE p.cluster_id = ? AND p.followed = 1 AND p.silenced = 0
+          AND p.last_seen_version < ?
+        "#,
+    )
+    .bind(cluster_id)
+    .bind(version)
+    .fetch_all(db.pool())
+    .await?;
+
+    Ok(followers)
+}
+
+/// Gets the devices that silenced a cluster
+///
+/// # Arguments
+/// * `db` - Database instance
+/// * `cluster_id` - ID of the cluster
+///
+/// # Returns
+/// * `Ok(HashSet<String>)` - The device tokens not to notify about the cluster
+/// * `Err` - If there was an error during retrieval
+pub async fn get_devices_silencing_cluster(
+    db: &Database,
+    cluster_id: i64,
+) -> Result<HashSet<String>> {
+    let devices: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT d.device_id
+        FROM user_cluster_preferences p
+        JOIN devices d ON d.id = p.user_id
+        WHERE p.cluster_id = ? AND p.silenced = 1
+        "#,
+    )
+    .bind(cluster_id)
+    .fetch_all(db.pool())
+    .await?;
+
+    Ok(devices.into_iter().collect())
+}
+
+/// Carries the preferences for merged clusters over to the cluster they were merged
+/// into. A device with preferences for several of them keeps the first.
+///
+/// # Arguments
+/// * `db` - Database instance
+/// * `source_cluster_ids` - IDs of the merged clusters
+/// * `target_cluster_id` - ID of the cluster they were merged into
+///
+/// # Returns
+/// * `Ok(())` - If the preferences were carried over
+/// * `Err` - If there was an error during the update
+pub async fn transfer_user_preferences(
+    db: &Database,
+    source_cluster_ids: &[i64],
+    target_cluster_id: i64,
+) -> Result<()> {
+    let mut tx = db.pool().begin().await?;
+    for &source_id in source_cluster_ids {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO user_cluster_preferences
+                (user_id, cluster_id, silenced, followed, last_seen_version, last_interaction)
+            SELECT user_id, ?1, silenced, followed, 0, last_interaction
+            FROM user_cluster_preferences
+            WHERE cluster_id = ?2
+            "#,
+        )
+        .bind(target_cluster_id)
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
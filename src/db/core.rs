@@ -68,6 +68,26 @@ impl Database {
         Ok(db)
     }
 
+    /// Opens a fresh in-memory database with the full schema, for tests. Connections of
+    /// the pool share the database, which lives as long as the pool.
+    #[cfg(test)]
+    pub(crate) async fn in_memory() -> Self {
+        let connect_options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .expect("Invalid in-memory database URL");
+        let pool = SqlitePoolOptions::new()
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(connect_options)
+            .await
+            .expect("Failed to open in-memory database");
+        let db = Database { pool };
+        db.initialize_schema()
+            .await
+            .expect("Failed to initialize schema");
+        db
+    }
+
     pub async fn instance() -> &'static Database {
         static INSTANCE: OnceCell<Database> = OnceCell::const_new();
 
@@ -37,6 +37,44 @@ impl Database {
         }
     }
 
+    /// Records the platform a device authenticated from, and for web push devices the
+    /// keys of their subscription (as JSON).
+    pub async fn register_device(
+        &self,
+        device_id: &str,
+        platform: &str,
+        push_keys: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO devices (device_id, platform, push_keys)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(device_id) DO UPDATE SET platform = ?2, push_keys = ?3
+            "#,
+        )
+        .bind(device_id)
+        .bind(platform)
+        .bind(push_keys)
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Fetch the platform and web push keys of a device, if it is known
+    pub async fn get_push_device(
+        &self,
+        device_id: &str,
+    ) -> Result<Option<(String, Option<String>)>, sqlx::Error> {
+        sqlx::query_as::<_, (String, Option<String>)>(
+            r#"
+            SELECT platform, push_keys FROM devices WHERE device_id = ?1;
+            "#,
+        )
+        .bind(device_id)
+        .fetch_optional(self.pool())
+        .await
+    }
+
     /// Subscribe a device to a specific topic
     pub async fn subscribe_to_topic(
         &self,
@@ -98,6 +136,17 @@ impl Database {
         .execute(&mut *transaction)
         .await?;
 
+        // Delete its cluster preferences
+        sqlx::query(
+            r#"
+                DELETE FROM user_cluster_preferences
+                WHERE user_id = (SELECT id FROM devices WHERE device_id = ?1);
+                "#,
+        )
+        .bind(device_token)
+        .execute(&mut *transaction)
+        .await?;
+
         // Delete the device itself
         sqlx::query(
             r#"
@@ -0,0 +1,135 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use tracing::{debug, instrument};
+
+use super::core::Database;
+use crate::db::Row;
+use crate::TARGET_DB;
+
+/// An analyzed article considered for a digest.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DigestArticle {
+    pub id: i64,
+    pub url: String,
+    pub title: String,
+    pub tiny_summary: String,
+    pub quality: i64,
+    /// Every topic the article matched
+    pub topics: Vec<String>,
+    pub cluster_id: Option<i64>,
+    pub cluster_summary: Option<String>,
+    pub importance_score: f64,
+}
+
+impl Database {
+    /// Returns the analyzed articles seen since `since`, with the summary and importance of
+    /// the story cluster each one belongs to.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn fetch_digest_articles(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DigestArticle>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT a.id, a.url, a.category, a.tiny_summary, a.analysis, a.cluster_id,
+                   c.summary AS cluster_summary, c.importance_score,
+                   GROUP_CONCAT(t.topic, '|') AS topics
+            FROM articles a
+            LEFT JOIN article_topics t ON t.article_id = a.id
+            LEFT JOIN article_clusters c ON c.id = a.cluster_id
+            WHERE CAST(a.seen_at AS INTEGER) >= ?1
+              AND a.is_relevant = 1
+              AND a.analysis IS NOT NULL
+            GROUP BY a.id
+            "#,
+        )
+        .bind(since.timestamp())
+        .fetch_all(self.pool())
+        .await?;
+
+        let articles: Vec<DigestArticle> = rows
+            .into_iter()
+            .map(|row| {
+                let analysis: Option<String> = row.get("analysis");
+                let analysis: serde_json::Value = analysis
+                    .and_then(|analysis| serde_json::from_str(&analysis).ok())
+                    .unwrap_or_default();
+                let topics: Option<String> = row.get("topics");
+                let mut topics: Vec<String> = topics
+                    .map(|topics| topics.split('|').map(str::to_string).collect())
+                    .unwrap_or_default();
+                if topics.is_empty() {
+                    topics.extend(row.get::<Option<String>, _>("category"));
+                }
+                let url: String = row.get("url");
+                DigestArticle {
+                    id: row.get("id"),
+                    title: analysis["tiny_title"]
+                        .as_str()
+                        .or(analysis["title"].as_str())
+                        .unwrap_or(&url)
+                        .to_string(),
+                    url,
+                    tiny_summary: row
+                        .get::<Option<String>, _>("tiny_summary")
+                        .unwrap_or_default(),
+                    quality: analysis["quality"].as_i64().unwrap_or(0),
+                    topics,
+                    cluster_id: row.get("cluster_id"),
+                    cluster_summary: row.get("cluster_summary"),
+                    importance_score: row
+                        .get::<Option<f64>, _>("importance_score")
+                        .unwrap_or(0.0),
+                }
+            })
+            .collect();
+        debug!(target: TARGET_DB, "Found {} articles for digests since {}", articles.len(), since);
+        Ok(articles)
+    }
+
+    /// Claims a scheduled run of a digest. Returns false if it was already claimed, so each
+    /// run is only sent once even across restarts.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn claim_digest_run(
+        &self,
+        name: &str,
+        scheduled_for: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO digest_runs (name, scheduled_for, created_at)
+            VALUES (?1, ?2, ?3)
+            "#,
+        )
+        .bind(name)
+        .bind(scheduled_for.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Records what a digest run produced.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn complete_digest_run(
+        &self,
+        name: &str,
+        scheduled_for: DateTime<Utc>,
+        stories: usize,
+        r2_url: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE digest_runs SET stories = ?1, r2_url = ?2, completed_at = ?3
+            WHERE name = ?4 AND scheduled_for = ?5
+            "#,
+        )
+        .bind(stories as i64)
+        .bind(r2_url)
+        .bind(Utc::now().to_rfc3339())
+        .bind(name)
+        .bind(scheduled_for.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+}
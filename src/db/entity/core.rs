@@ -1,5 +1,7 @@
 use crate::db::core::Database;
+use crate::TARGET_DB;
 use sqlx::Row;
+use tracing::warn;
 
 impl Database {
     /// Add a new entity to the database or return existing entity ID if it already exists
@@ -114,6 +116,11 @@ impl Database {
             }
         }
 
+        // Entity names are searchable, so refresh the article's full-text entry
+        if let Err(e) = self.index_article_for_search(article_id).await {
+            warn!(target: TARGET_DB, "Failed to index entities of article {} for search: {}", article_id, e);
+        }
+
         Ok(added_entity_ids)
     }
 
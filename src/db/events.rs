@@ -0,0 +1,189 @@
+use chrono::Utc;
+use sqlx::{QueryBuilder, Sqlite};
+use tracing::{debug, instrument};
+
+use super::core::Database;
+use crate::db::Row;
+use crate::TARGET_DB;
+
+/// Which logged events a stream client wants. Empty lists match everything.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub kinds: Vec<String>,
+    /// Events without topics, such as cluster updates, always match
+    pub topics: Vec<String>,
+}
+
+/// An event from the event log.
+#[derive(Debug, Clone)]
+pub struct LoggedEvent {
+    pub id: i64,
+    pub kind: String,
+    pub topics: Vec<String>,
+    /// JSON
+    pub payload: String,
+    /// Unix seconds
+    pub created_at: i64,
+}
+
+impl Database {
+    /// Appends an event to the event log and returns its ID.
+    #[instrument(target = "db", level = "debug", skip(self, payload))]
+    pub async fn append_event(
+        &self,
+        kind: &str,
+        topics: &[String],
+        payload: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let topics = (!topics.is_empty()).then(|| serde_json::json!(topics).to_string());
+        let result = sqlx::query(
+            "INSERT INTO event_log (kind, topics, payload, created_at) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(kind)
+        .bind(topics)
+        .bind(payload)
+        .bind(Utc::now().timestamp())
+        .execute(self.pool())
+        .await?;
+        let id = result.last_insert_rowid();
+        debug!(target: TARGET_DB, "Logged {} event {}", kind, id);
+        Ok(id)
+    }
+
+    /// Returns up to `limit` events after `after_id` that match the filter, oldest first.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn events_after(
+        &self,
+        after_id: i64,
+        filter: &EventFilter,
+        limit: i64,
+    ) -> Result<Vec<LoggedEvent>, sqlx::Error> {
+        let mut query: QueryBuilder<Sqlite> = QueryBuilder::new(
+            "SELECT id, kind, topics, payload, created_at FROM event_log WHERE id > ",
+        );
+        query.push_bind(after_id);
+        if !filter.kinds.is_empty() {
+            query.push(" AND kind IN (");
+            let mut kinds = query.separated(", ");
+            for kind in &filter.kinds {
+                kinds.push_bind(kind);
+            }
+            query.push(")");
+        }
+        if !filter.topics.is_empty() {
+            query.push(
+                " AND (topics IS NULL OR EXISTS (SELECT 1 FROM json_each(event_log.topics) WHERE value IN (",
+            );
+            let mut topics = query.separated(", ");
+            for topic in &filter.topics {
+                topics.push_bind(topic);
+            }
+            query.push(")))");
+        }
+        query.push(" ORDER BY id LIMIT ").push_bind(limit);
+
+        let rows = query.build().fetch_all(self.pool()).await?;
+        Ok(rows
+            .iter()
+            .map(|row| LoggedEvent {
+                id: row.get("id"),
+                kind: row.get("kind"),
+                topics: row
+                    .get::<Option<String>, _>("topics")
+                    .and_then(|topics| serde_json::from_str(&topics).ok())
+                    .unwrap_or_default(),
+                payload: row.get("payload"),
+                created_at: row.get("created_at"),
+            })
+            .collect())
+    }
+
+    /// Returns the ID of the latest logged event, or 0 if there are none.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn latest_event_id(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM event_log")
+            .fetch_one(self.pool())
+            .await
+    }
+
+    /// Deletes events logged before `before` (unix seconds). IDs are never reused, so
+    /// clients resuming from a pruned event just miss the pruned ones.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn prune_events(&self, before: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM event_log WHERE created_at < ?1")
+            .bind(before)
+            .execute(self.pool())
+            .await?;
+        debug!(target: TARGET_DB, "Pruned {} logged events", result.rows_affected());
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topics(topics: &[&str]) -> Vec<String> {
+        topics.iter().map(|topic| topic.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_events_after_filters() {
+        let db = Database::in_memory().await;
+        let rust = db
+            .append_event("analysis", &topics(&["Rust"]), "{}")
+            .await
+            .unwrap();
+        let both = db
+            .append_event("analysis", &topics(&["Go", "Linux"]), "{}")
+            .await
+            .unwrap();
+        let alert = db
+            .append_event("life_safety_alert", &topics(&["Rust"]), "{}")
+            .await
+            .unwrap();
+        let cluster = db.append_event("cluster_update", &[], "{}").await.unwrap();
+        assert_eq!(db.latest_event_id().await.unwrap(), cluster);
+
+        let ids = |events: Vec<LoggedEvent>| events.iter().map(|e| e.id).collect::<Vec<_>>();
+        let all = db
+            .events_after(0, &EventFilter::default(), 10)
+            .await
+            .unwrap();
+        assert_eq!(all[1].topics, topics(&["Go", "Linux"]));
+        assert_eq!(ids(all), vec![rust, both, alert, cluster]);
+
+        // Secondary topics match, and events without topics always do
+        let linux = EventFilter {
+            kinds: Vec::new(),
+            topics: topics(&["Linux"]),
+        };
+        assert_eq!(
+            ids(db.events_after(0, &linux, 10).await.unwrap()),
+            vec![both, cluster]
+        );
+
+        let rust_analyses = EventFilter {
+            kinds: topics(&["analysis"]),
+            topics: topics(&["Rust", "Python"]),
+        };
+        assert_eq!(
+            ids(db.events_after(0, &rust_analyses, 10).await.unwrap()),
+            vec![rust]
+        );
+
+        // Resuming after an event, a page at a time
+        assert_eq!(
+            ids(db
+                .events_after(rust, &EventFilter::default(), 2)
+                .await
+                .unwrap()),
+            vec![both, alert]
+        );
+        assert!(db
+            .events_after(cluster, &EventFilter::default(), 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
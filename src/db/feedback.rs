@@ -0,0 +1,101 @@
+use chrono::Utc;
+use tracing::{debug, instrument};
+
+use super::core::Database;
+use crate::db::Row;
+use crate::feedback::{FeedbackKind, FeedbackVotes, LabelledArticle};
+use crate::TARGET_DB;
+
+impl Database {
+    /// Records a reader's feedback on an article under a topic. A reader has one vote per
+    /// article and topic, so voting again replaces their earlier vote.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn record_article_feedback(
+        &self,
+        article_id: i64,
+        topic: &str,
+        kind: FeedbackKind,
+        user_id: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO article_feedback (article_id, topic, feedback, user_id, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(article_id, topic, user_id) DO UPDATE SET
+                feedback = excluded.feedback,
+                created_at = excluded.created_at
+            "#,
+        )
+        .bind(article_id)
+        .bind(topic)
+        .bind(kind.as_str())
+        .bind(user_id)
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool())
+        .await?;
+        debug!(target: TARGET_DB, "Recorded {} feedback on article {} for {}", kind.as_str(), article_id, topic);
+        Ok(())
+    }
+
+    /// Returns every article that received feedback, labelled by its most common vote, per
+    /// topic. Optionally limited to one topic.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn fetch_labelled_articles(
+        &self,
+        topic: Option<&str>,
+    ) -> Result<Vec<LabelledArticle>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT f.article_id, f.topic, f.feedback, COUNT(*) AS votes,
+                   a.url, a.tiny_summary, a.analysis, t.confidence
+            FROM article_feedback f
+            JOIN articles a ON a.id = f.article_id
+            LEFT JOIN article_topics t ON t.article_id = f.article_id AND t.topic = f.topic
+            WHERE ?1 IS NULL OR f.topic = ?1
+            GROUP BY f.article_id, f.topic, f.feedback
+            ORDER BY f.article_id, f.topic
+            "#,
+        )
+        .bind(topic)
+        .fetch_all(self.pool())
+        .await?;
+
+        let mut labelled: Vec<LabelledArticle> = Vec::new();
+        for row in rows {
+            let article_id: i64 = row.get("article_id");
+            let topic: String = row.get("topic");
+            let Some(kind) = FeedbackKind::from_name(row.get("feedback")) else {
+                continue;
+            };
+            let votes: i64 = row.get("votes");
+
+            let same_article = labelled
+                .last()
+                .is_some_and(|last| last.article_id == article_id && last.topic == topic);
+            if !same_article {
+                let analysis: Option<String> = row.get("analysis");
+                let analysis: serde_json::Value = analysis
+                    .and_then(|analysis| serde_json::from_str(&analysis).ok())
+                    .unwrap_or_default();
+                let text = |name: &str| analysis[name].as_str().map(str::to_string);
+                labelled.push(LabelledArticle {
+                    article_id,
+                    url: row.get("url"),
+                    topic,
+                    label: kind,
+                    votes: FeedbackVotes::default(),
+                    confidence: row.get("confidence"),
+                    quality: analysis["quality"].as_i64(),
+                    title: text("title"),
+                    tiny_summary: row.get("tiny_summary"),
+                    article_body: text("article_body"),
+                });
+            }
+            if let Some(article) = labelled.last_mut() {
+                article.votes.add(kind, votes as u32);
+                article.label = article.votes.label().unwrap_or(kind);
+            }
+        }
+        Ok(labelled)
+    }
+}
@@ -0,0 +1,196 @@
+use chrono::{DateTime, Utc};
+use tracing::{debug, instrument};
+
+use super::core::Database;
+use crate::db::Row;
+use crate::threat::{ThreatCategory, ThreatResponse, ThreatSeverity};
+use crate::TARGET_DB;
+
+/// An ongoing emergency that one or more life safety articles report on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Incident {
+    pub id: i64,
+    pub title: String,
+    /// The alert topic the incident was first reported under, e.g. "Alert: Direct"
+    pub topic: String,
+    pub category: ThreatCategory,
+    pub severity: ThreatSeverity,
+    pub response: ThreatResponse,
+    /// Center latitude, longitude and radius in kilometers, if the threat was placed on the map
+    pub area: Option<(f64, f64, f64)>,
+    pub cluster_id: Option<i64>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Database {
+    /// Opens a new incident, returning its id.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn create_incident(&self, incident: &Incident) -> Result<i64, sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        let (id,): (i64,) = sqlx::query_as(
+            r#"
+            INSERT INTO incidents (
+                title, topic, category, severity, response, latitude, longitude, radius_km,
+                cluster_id, status, expires_at, created_at, updated_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'active', ?10, ?11, ?11)
+            RETURNING id
+            "#,
+        )
+        .bind(&incident.title)
+        .bind(&incident.topic)
+        .bind(incident.category.as_str())
+        .bind(incident.severity.as_str())
+        .bind(incident.response.as_str())
+        .bind(incident.area.map(|area| area.0))
+        .bind(incident.area.map(|area| area.1))
+        .bind(incident.area.map(|area| area.2))
+        .bind(incident.cluster_id)
+        .bind(incident.expires_at.to_rfc3339())
+        .bind(now)
+        .fetch_one(self.pool())
+        .await?;
+        debug!(target: TARGET_DB, "Created incident {}: {}", id, incident.title);
+        Ok(id)
+    }
+
+    /// Replaces the current state of an active incident.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn update_incident(&self, incident: &Incident) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE incidents SET
+                title = ?1, severity = ?2, response = ?3, latitude = ?4, longitude = ?5,
+                radius_km = ?6, cluster_id = COALESCE(?7, cluster_id), expires_at = ?8,
+                updated_at = ?9
+            WHERE id = ?10
+            "#,
+        )
+        .bind(&incident.title)
+        .bind(incident.severity.as_str())
+        .bind(incident.response.as_str())
+        .bind(incident.area.map(|area| area.0))
+        .bind(incident.area.map(|area| area.1))
+        .bind(incident.area.map(|area| area.2))
+        .bind(incident.cluster_id)
+        .bind(incident.expires_at.to_rfc3339())
+        .bind(Utc::now().to_rfc3339())
+        .bind(incident.id)
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Records that an article reports on an incident, what changed and whether anyone
+    /// was notified.
+    pub async fn link_article_to_incident(
+        &self,
+        incident_id: i64,
+        article_id: i64,
+        changes: &str,
+        notified: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO incident_articles (incident_id, article_id, changes, notified, linked_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+        )
+        .bind(incident_id)
+        .bind(article_id)
+        .bind(changes)
+        .bind(notified)
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Returns whether an article reports on an incident that hasn't been cleared.
+    pub async fn reports_active_incident(&self, article_id: i64) -> Result<bool, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM incident_articles ia
+            JOIN incidents i ON i.id = ia.incident_id
+            WHERE ia.article_id = ?1 AND i.status = 'active'
+            "#,
+        )
+        .bind(article_id)
+        .fetch_one(self.pool())
+        .await?;
+        Ok(count > 0)
+    }
+
+    /// Returns all incidents that haven't been cleared.
+    pub async fn fetch_active_incidents(&self) -> Result<Vec<Incident>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, title, topic, category, severity, response, latitude, longitude,
+                   radius_km, cluster_id, expires_at
+            FROM incidents
+            WHERE status = 'active'
+            ORDER BY updated_at DESC
+            "#,
+        )
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let latitude: Option<f64> = row.get("latitude");
+                let longitude: Option<f64> = row.get("longitude");
+                let radius_km: Option<f64> = row.get("radius_km");
+                let expires_at: String = row.get("expires_at");
+                Incident {
+                    id: row.get("id"),
+                    title: row.get("title"),
+                    topic: row.get("topic"),
+                    category: ThreatCategory::from_name(row.get("category")),
+                    severity: ThreatSeverity::from_name(row.get("severity")),
+                    response: ThreatResponse::from_name(row.get("response")),
+                    area: latitude
+                        .zip(longitude)
+                        .zip(radius_km)
+                        .map(|((lat, lon), radius)| (lat, lon, radius)),
+                    cluster_id: row.get("cluster_id"),
+                    expires_at: DateTime::parse_from_rfc3339(&expires_at)
+                        .map(|time| time.with_timezone(&Utc))
+                        .unwrap_or_else(|_| Utc::now()),
+                }
+            })
+            .collect())
+    }
+
+    /// Marks an incident as over. Returns false if it was already cleared, so that only
+    /// one worker sends the all-clear.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn clear_incident(&self, incident_id: i64) -> Result<bool, sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        let result = sqlx::query(
+            r#"
+            UPDATE incidents SET status = 'cleared', cleared_at = ?1, updated_at = ?1
+            WHERE id = ?2 AND status = 'active'
+            "#,
+        )
+        .bind(now)
+        .bind(incident_id)
+        .execute(self.pool())
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns the cluster an article was assigned to, if any.
+    pub async fn get_article_cluster_id(
+        &self,
+        article_id: i64,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let cluster_id: Option<Option<i64>> =
+            sqlx::query_scalar("SELECT cluster_id FROM articles WHERE id = ?1")
+                .bind(article_id)
+                .fetch_optional(self.pool())
+                .await?;
+        Ok(cluster_id.flatten())
+    }
+}
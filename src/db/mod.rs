@@ -1,11 +1,24 @@
 // Re-export the Database struct and other public items
+pub mod admin;
+pub mod alerts;
 mod article;
+pub mod article_query;
+pub mod auth;
 pub mod cluster;
 pub mod core;
 mod device;
+pub mod digest;
 pub mod entity;
+pub mod events;
+mod feedback;
+pub mod incident;
+pub mod notifications;
+pub mod places;
 mod queue;
 mod schema;
+pub mod search;
+mod slack;
+pub mod throttle;
 
 // Re-export Database and essential traits
 pub use self::core::Database;
@@ -0,0 +1,47 @@
+use chrono::Utc;
+use tracing::debug;
+
+use super::core::Database;
+use crate::TARGET_DB;
+
+/// The outcome of routing one analysis to one notification backend.
+#[derive(Debug, Clone)]
+pub struct NotificationDelivery<'a> {
+    pub article_url: &'a str,
+    pub topics: &'a [String],
+    pub backend: &'a str,
+    pub delivered: bool,
+    pub attempts: u32,
+    /// What the backend handed back, e.g. the R2 URL of the analysis
+    pub receipt: Option<&'a str>,
+    pub last_error: Option<&'a str>,
+}
+
+impl Database {
+    /// Records a delivery attempt in the notification delivery log.
+    pub async fn log_notification_delivery(
+        &self,
+        delivery: &NotificationDelivery<'_>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO notification_deliveries (
+                article_url, topics, backend, status, attempts, receipt, last_error, created_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+        )
+        .bind(delivery.article_url)
+        .bind(delivery.topics.join(", "))
+        .bind(delivery.backend)
+        .bind(if delivery.delivered { "sent" } else { "failed" })
+        .bind(delivery.attempts as i64)
+        .bind(delivery.receipt)
+        .bind(delivery.last_error)
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool())
+        .await?;
+        debug!(target: TARGET_DB, "Logged {} delivery to {} for {}", if delivery.delivered { "successful" } else { "failed" }, delivery.backend, delivery.article_url);
+        Ok(())
+    }
+}
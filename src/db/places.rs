@@ -0,0 +1,227 @@
+use chrono::Utc;
+use tracing::{debug, instrument};
+
+use super::core::Database;
+use crate::db::Row;
+use crate::TARGET_DB;
+
+/// A place people live, as used by the life safety checks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub id: i64,
+    pub continent: String,
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+/// A person who is alerted about threats near their location.
+#[derive(Debug, Clone)]
+pub struct Person {
+    pub id: i64,
+    pub first_name: String,
+    pub last_name: String,
+    pub timezone: Option<String>,
+    pub slack_id: Option<String>,
+    /// Home coordinates, if more precise than the location's.
+    pub home_latitude: Option<f64>,
+    pub home_longitude: Option<f64>,
+    /// Local time window in which only immediate alerts are delivered, e.g. "22:00-07:00"
+    pub quiet_hours: Option<String>,
+    pub location: Location,
+}
+
+impl Person {
+    /// The person's home coordinates, falling back to their location's.
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        match (self.home_latitude, self.home_longitude) {
+            (Some(lat), Some(lon)) => Some((lat, lon)),
+            _ => match (self.location.latitude, self.location.longitude) {
+                (Some(lat), Some(lon)) => Some((lat, lon)),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl Database {
+    /// Returns the id of a location, creating it if needed. Coordinates, when given,
+    /// replace any stored for the location.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn upsert_location(
+        &self,
+        continent: &str,
+        country: &str,
+        region: &str,
+        city: &str,
+        latitude: Option<f64>,
+        longitude: Option<f64>,
+    ) -> Result<i64, sqlx::Error> {
+        let (id,): (i64,) = sqlx::query_as(
+            r#"
+            INSERT INTO locations (continent, country, region, city, latitude, longitude)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(continent, country, region, city) DO UPDATE SET
+                latitude = COALESCE(excluded.latitude, latitude),
+                longitude = COALESCE(excluded.longitude, longitude)
+            RETURNING id
+            "#,
+        )
+        .bind(continent.trim())
+        .bind(country.trim())
+        .bind(region.trim())
+        .bind(city.trim())
+        .bind(latitude)
+        .bind(longitude)
+        .fetch_one(self.pool())
+        .await?;
+        Ok(id)
+    }
+
+    /// Adds a person at a location, returning their id. Adding the same person at the
+    /// same location again updates their time zone and Slack ID.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn add_person(
+        &self,
+        first_name: &str,
+        last_name: &str,
+        location_id: i64,
+        timezone: Option<&str>,
+        slack_id: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        let (id,): (i64,) = sqlx::query_as(
+            r#"
+            INSERT INTO people (first_name, last_name, location_id, timezone, slack_id, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(first_name, last_name, location_id) DO UPDATE SET
+                timezone = excluded.timezone,
+                slack_id = excluded.slack_id
+            RETURNING id
+            "#,
+        )
+        .bind(first_name.trim())
+        .bind(last_name.trim())
+        .bind(location_id)
+        .bind(timezone)
+        .bind(slack_id)
+        .bind(Utc::now().to_rfc3339())
+        .fetch_one(self.pool())
+        .await?;
+        debug!(target: TARGET_DB, "Added person {} {} with id {}", first_name, last_name, id);
+        Ok(id)
+    }
+
+    /// Moves a person to another location. Returns false if there is no such person.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn move_person(&self, person_id: i64, location_id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE people SET location_id = ?1 WHERE id = ?2")
+            .bind(location_id)
+            .bind(person_id)
+            .execute(self.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Sets or clears a person's home coordinates. Returns false if there is no such person.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn set_person_home(
+        &self,
+        person_id: i64,
+        latitude: Option<f64>,
+        longitude: Option<f64>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE people SET latitude = ?1, longitude = ?2 WHERE id = ?3")
+            .bind(latitude)
+            .bind(longitude)
+            .bind(person_id)
+            .execute(self.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Sets or clears a person's quiet hours. Returns false if there is no such person.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn set_person_quiet_hours(
+        &self,
+        person_id: i64,
+        quiet_hours: Option<&str>,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE people SET quiet_hours = ?1 WHERE id = ?2")
+            .bind(quiet_hours)
+            .bind(person_id)
+            .execute(self.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Removes a person. Returns false if there is no such person.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn remove_person(&self, person_id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM people WHERE id = ?1")
+            .bind(person_id)
+            .execute(self.pool())
+            .await?;
+        // Drop locations nobody lives in any more
+        sqlx::query("DELETE FROM locations WHERE id NOT IN (SELECT location_id FROM people)")
+            .execute(self.pool())
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns everyone in the registry with their location.
+    pub async fn fetch_people(&self) -> Result<Vec<Person>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT p.id, p.first_name, p.last_name, p.timezone, p.slack_id,
+                   p.latitude AS home_latitude, p.longitude AS home_longitude, p.quiet_hours,
+                   l.id AS location_id, l.continent, l.country, l.region, l.city,
+                   l.latitude, l.longitude
+            FROM people p
+            JOIN locations l ON p.location_id = l.id
+            ORDER BY l.continent, l.country, l.region, l.city, p.last_name, p.first_name
+            "#,
+        )
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Person {
+                id: row.get("id"),
+                first_name: row.get("first_name"),
+                last_name: row.get("last_name"),
+                timezone: row.get("timezone"),
+                slack_id: row.get("slack_id"),
+                home_latitude: row.get("home_latitude"),
+                home_longitude: row.get("home_longitude"),
+                quiet_hours: row.get("quiet_hours"),
+                location: Location {
+                    id: row.get("location_id"),
+                    continent: row.get("continent"),
+                    country: row.get("country"),
+                    region: row.get("region"),
+                    city: row.get("city"),
+                    latitude: row.get("latitude"),
+                    longitude: row.get("longitude"),
+                },
+            })
+            .collect())
+    }
+
+    /// Returns the current places revision, which changes whenever people or locations do.
+    pub async fn places_revision(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT revision FROM places_revision WHERE id = 1")
+            .fetch_one(self.pool())
+            .await
+    }
+
+    /// Whether the places registry is empty.
+    pub async fn places_empty(&self) -> Result<bool, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM people")
+            .fetch_one(self.pool())
+            .await?;
+        Ok(count == 0)
+    }
+}
@@ -5,6 +5,7 @@ use url::Url;
 use urlnorm::UrlNormalizer;
 
 use super::core::Database;
+use crate::threat::{ThreatCategory, ThreatDetails, ThreatResponse, ThreatSeverity};
 use crate::TARGET_DB;
 
 impl Database {
@@ -99,7 +100,10 @@ impl Database {
         article_hash: &str,
         title_domain_hash: &str,
         topic_matched: &str,
+        matched_topics: &str,
         pub_date: Option<&str>,
+        language: Option<&str>,
+        original_text: Option<&str>,
     ) -> Result<(), sqlx::Error> {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -111,9 +115,10 @@ impl Database {
             r#"
             INSERT INTO matched_topics_queue (
                 article_text, article_html, article_url, article_title,
-                article_hash, title_domain_hash, topic_matched, timestamp, pub_date
+                article_hash, title_domain_hash, topic_matched, matched_topics, timestamp, pub_date,
+                language, original_text
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
             ON CONFLICT(article_url) DO NOTHING
             "#,
         )
@@ -124,8 +129,11 @@ impl Database {
         .bind(article_hash)
         .bind(title_domain_hash)
         .bind(topic_matched)
+        .bind(matched_topics)
         .bind(timestamp)
         .bind(pub_date) // <-- store pub_date
+        .bind(language)
+        .bind(original_text)
         .execute(self.pool())
         .await;
 
@@ -151,6 +159,7 @@ impl Database {
     pub async fn add_to_life_safety_queue(
         &self,
         threat: &str,
+        details: &ThreatDetails,
         article_url: &str,
         article_title: &str,
         article_text: &str,
@@ -158,6 +167,8 @@ impl Database {
         article_hash: &str,
         title_domain_hash: &str,
         pub_date: Option<&str>,
+        language: Option<&str>,
+        original_text: Option<&str>,
     ) -> Result<(), sqlx::Error> {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -169,9 +180,11 @@ impl Database {
             r#"
             INSERT INTO life_safety_queue (
                 article_url, article_title, article_text, article_html,
-                article_hash, title_domain_hash, threat, timestamp, pub_date
+                article_hash, title_domain_hash, threat, timestamp, pub_date,
+                language, original_text, threat_category, threat_severity,
+                threat_starts_at, threat_ends_at, threat_confidence, threat_response
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
             ON CONFLICT(article_url) DO NOTHING
             "#,
         )
@@ -184,6 +197,14 @@ impl Database {
         .bind(threat)
         .bind(timestamp)
         .bind(pub_date) // <-- store pub_date
+        .bind(language)
+        .bind(original_text)
+        .bind(details.category.as_str())
+        .bind(details.severity.as_str())
+        .bind(&details.starts_at)
+        .bind(&details.ends_at)
+        .bind(details.confidence)
+        .bind(details.response.as_str())
         .execute(self.pool())
         .await;
 
@@ -217,7 +238,10 @@ impl Database {
             String,         // article_hash
             String,         // title_domain_hash
             String,         // threat
+            ThreatDetails,  // threat_category, threat_severity, ...
             Option<String>, // pub_date
+            Option<String>, // language
+            Option<String>, // original_text
         )>,
         sqlx::Error,
     > {
@@ -235,7 +259,15 @@ impl Database {
                 article_hash,
                 title_domain_hash,
                 threat,
-                pub_date
+                pub_date,
+                language,
+                original_text,
+                threat_category,
+                threat_severity,
+                threat_starts_at,
+                threat_ends_at,
+                threat_confidence,
+                threat_response
             FROM life_safety_queue
             ORDER BY timestamp ASC
             LIMIT 1
@@ -254,6 +286,25 @@ impl Database {
             let title_domain_hash: String = row.get("title_domain_hash");
             let threat: String = row.get("threat");
             let pub_date: Option<String> = row.get("pub_date"); // <-- retrieve pub_date
+            let language: Option<String> = row.get("language");
+            let original_text: Option<String> = row.get("original_text");
+            let threat_category: Option<String> = row.get("threat_category");
+            let threat_severity: Option<String> = row.get("threat_severity");
+            let threat_response: Option<String> = row.get("threat_response");
+            let details = ThreatDetails {
+                category: threat_category
+                    .map(|c| ThreatCategory::from_name(&c))
+                    .unwrap_or_default(),
+                severity: threat_severity
+                    .map(|s| ThreatSeverity::from_name(&s))
+                    .unwrap_or_default(),
+                response: threat_response
+                    .map(|r| ThreatResponse::from_name(&r))
+                    .unwrap_or_default(),
+                starts_at: row.get("threat_starts_at"),
+                ends_at: row.get("threat_ends_at"),
+                confidence: row.get("threat_confidence"),
+            };
 
             sqlx::query("DELETE FROM life_safety_queue WHERE id = ?1")
                 .bind(id)
@@ -270,7 +321,10 @@ impl Database {
                 article_hash,
                 title_domain_hash,
                 threat,
+                details,
                 pub_date,
+                language,
+                original_text,
             )))
         } else {
             debug!(target: TARGET_DB, "No new items found in life safety queue");
@@ -361,6 +415,9 @@ impl Database {
             String,
             String,
             Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
         )>,
         sqlx::Error,
     > {
@@ -378,7 +435,10 @@ impl Database {
                 article_hash,
                 title_domain_hash,
                 topic_matched,
-                pub_date
+                matched_topics,
+                pub_date,
+                language,
+                original_text
             FROM matched_topics_queue
             ORDER BY timestamp ASC
             LIMIT 1
@@ -396,7 +456,10 @@ impl Database {
             let article_hash: String = row.get("article_hash");
             let title_domain_hash: String = row.get("title_domain_hash");
             let topic_matched: String = row.get("topic_matched");
+            let matched_topics: Option<String> = row.get("matched_topics");
             let pub_date: Option<String> = row.get("pub_date"); // <-- retrieve pub_date
+            let language: Option<String> = row.get("language");
+            let original_text: Option<String> = row.get("original_text");
 
             sqlx::query("DELETE FROM matched_topics_queue WHERE id = ?1")
                 .bind(id)
@@ -413,7 +476,10 @@ impl Database {
                 article_hash,
                 title_domain_hash,
                 topic_matched,
+                matched_topics,
                 pub_date,
+                language,
+                original_text,
             )))
         } else {
             debug!(target: TARGET_DB, "No new items found in matched topics queue");
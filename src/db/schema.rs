@@ -84,6 +84,21 @@ impl Database {
             );
             CREATE INDEX IF NOT EXISTS idx_article_cluster_members_article_id ON article_cluster_members (article_id);
             CREATE INDEX IF NOT EXISTS idx_article_cluster_members_cluster_id ON article_cluster_members (cluster_id);
+
+            -- Devices following or silencing clusters, by the devices table ID
+            CREATE TABLE IF NOT EXISTS user_cluster_preferences (
+                user_id INTEGER NOT NULL,
+                cluster_id INTEGER NOT NULL,
+                silenced INTEGER NOT NULL DEFAULT 0,
+                followed INTEGER NOT NULL DEFAULT 1,
+                last_seen_version INTEGER NOT NULL DEFAULT 0,
+                last_interaction TEXT,
+                PRIMARY KEY (user_id, cluster_id),
+                FOREIGN KEY (cluster_id) REFERENCES article_clusters (id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_user_cluster_prefs_user_id ON user_cluster_preferences (user_id);
+            CREATE INDEX IF NOT EXISTS idx_user_cluster_prefs_cluster_id ON user_cluster_preferences (cluster_id);
+            CREATE INDEX IF NOT EXISTS idx_user_cluster_prefs_silenced ON user_cluster_preferences (silenced);
             
             -- Entity alias system tables
             CREATE TABLE IF NOT EXISTS entity_aliases (
@@ -194,6 +209,262 @@ impl Database {
             );
             CREATE UNIQUE INDEX IF NOT EXISTS idx_matched_topics_article_url ON matched_topics_queue (article_url);
 
+            -- Every topic an article was confirmed for; articles.category holds the primary one
+            CREATE TABLE IF NOT EXISTS article_topics (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                article_id INTEGER NOT NULL,
+                topic TEXT NOT NULL,
+                confidence REAL NOT NULL DEFAULT 1.0,
+                matched_at TEXT NOT NULL,
+                FOREIGN KEY (article_id) REFERENCES articles (id) ON DELETE CASCADE,
+                UNIQUE(article_id, topic)
+            );
+            CREATE INDEX IF NOT EXISTS idx_article_topics_topic ON article_topics (topic, article_id);
+
+            -- Places people live, for life safety alerts
+            CREATE TABLE IF NOT EXISTS locations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                continent TEXT NOT NULL,
+                country TEXT NOT NULL,
+                region TEXT NOT NULL,
+                city TEXT NOT NULL,
+                latitude REAL,
+                longitude REAL,
+                UNIQUE(continent, country, region, city)
+            );
+
+            CREATE TABLE IF NOT EXISTS people (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                first_name TEXT NOT NULL,
+                last_name TEXT NOT NULL,
+                location_id INTEGER NOT NULL,
+                timezone TEXT,
+                slack_id TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (location_id) REFERENCES locations (id),
+                UNIQUE(first_name, last_name, location_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_people_location ON people (location_id);
+
+            -- Where each person receives life safety alerts: slack, email or webhook
+            CREATE TABLE IF NOT EXISTS person_channels (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                person_id INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                address TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (person_id) REFERENCES people (id) ON DELETE CASCADE,
+                UNIQUE(person_id, kind, address)
+            );
+
+            -- One row per alert per person per channel, tracking delivery and acknowledgement
+            CREATE TABLE IF NOT EXISTS alert_deliveries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                person_id INTEGER NOT NULL,
+                article_id INTEGER,
+                incident_id INTEGER,
+                kind TEXT NOT NULL,
+                address TEXT NOT NULL,
+                urgency TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                body TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                deliver_after TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                ack_token TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL,
+                sent_at TEXT,
+                acknowledged_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_alert_deliveries_due ON alert_deliveries (status, deliver_after);
+            CREATE INDEX IF NOT EXISTS idx_alert_deliveries_incident ON alert_deliveries (incident_id, person_id);
+
+            -- Bumped on every change to people or locations so workers can hot-reload
+            CREATE TABLE IF NOT EXISTS places_revision (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                revision INTEGER NOT NULL
+            );
+            INSERT OR IGNORE INTO places_revision (id, revision) VALUES (1, 0);
+            CREATE TRIGGER IF NOT EXISTS trg_people_insert_revision AFTER INSERT ON people
+            BEGIN UPDATE places_revision SET revision = revision + 1 WHERE id = 1; END;
+            CREATE TRIGGER IF NOT EXISTS trg_people_update_revision AFTER UPDATE ON people
+            BEGIN UPDATE places_revision SET revision = revision + 1 WHERE id = 1; END;
+            CREATE TRIGGER IF NOT EXISTS trg_people_delete_revision AFTER DELETE ON people
+            BEGIN UPDATE places_revision SET revision = revision + 1 WHERE id = 1; END;
+            CREATE TRIGGER IF NOT EXISTS trg_locations_insert_revision AFTER INSERT ON locations
+            BEGIN UPDATE places_revision SET revision = revision + 1 WHERE id = 1; END;
+            CREATE TRIGGER IF NOT EXISTS trg_locations_update_revision AFTER UPDATE ON locations
+            BEGIN UPDATE places_revision SET revision = revision + 1 WHERE id = 1; END;
+            CREATE TRIGGER IF NOT EXISTS trg_locations_delete_revision AFTER DELETE ON locations
+            BEGIN UPDATE places_revision SET revision = revision + 1 WHERE id = 1; END;
+
+            -- LSH bands of each article's MinHash signature, for near-duplicate lookups
+            CREATE TABLE IF NOT EXISTS article_minhash_bands (
+                article_id INTEGER NOT NULL,
+                band INTEGER NOT NULL,
+                band_hash INTEGER NOT NULL,
+                PRIMARY KEY (article_id, band),
+                FOREIGN KEY (article_id) REFERENCES articles (id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_article_minhash_bands_lookup ON article_minhash_bands (band, band_hash);
+
+            CREATE TABLE IF NOT EXISTS incidents (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                title TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                category TEXT NOT NULL,
+                severity TEXT NOT NULL,
+                response TEXT NOT NULL,
+                latitude REAL,
+                longitude REAL,
+                radius_km REAL,
+                cluster_id INTEGER,
+                status TEXT NOT NULL DEFAULT 'active',
+                expires_at TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                cleared_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_incidents_status ON incidents (status, expires_at);
+
+            CREATE TABLE IF NOT EXISTS incident_articles (
+                incident_id INTEGER NOT NULL,
+                article_id INTEGER NOT NULL,
+                changes TEXT,
+                notified BOOLEAN NOT NULL,
+                linked_at TEXT NOT NULL,
+                PRIMARY KEY (incident_id, article_id),
+                FOREIGN KEY (incident_id) REFERENCES incidents (id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_incident_articles_article_id ON incident_articles (article_id);
+
+            -- One row per analysis per notification backend it was routed to
+            CREATE TABLE IF NOT EXISTS notification_deliveries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                article_url TEXT NOT NULL,
+                topics TEXT NOT NULL,
+                backend TEXT NOT NULL,
+                status TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                receipt TEXT,
+                last_error TEXT,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_notification_deliveries_article_url ON notification_deliveries (article_url);
+            CREATE INDEX IF NOT EXISTS idx_notification_deliveries_backend ON notification_deliveries (backend, status);
+
+            -- The first Slack message about each story cluster, per channel
+            CREATE TABLE IF NOT EXISTS slack_threads (
+                cluster_id INTEGER NOT NULL,
+                channel TEXT NOT NULL,
+                ts TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (cluster_id, channel)
+            );
+
+            -- Reader votes on posted analyses, one per reader per article and topic
+            CREATE TABLE IF NOT EXISTS article_feedback (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                article_id INTEGER NOT NULL,
+                topic TEXT NOT NULL,
+                feedback TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (article_id) REFERENCES articles (id) ON DELETE CASCADE,
+                UNIQUE(article_id, topic, user_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_article_feedback_topic ON article_feedback (topic, feedback);
+
+            -- Scheduled digests that were sent, so each run goes out once
+            CREATE TABLE IF NOT EXISTS digest_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                scheduled_for TEXT NOT NULL,
+                stories INTEGER,
+                r2_url TEXT,
+                created_at TEXT NOT NULL,
+                completed_at TEXT,
+                UNIQUE(name, scheduled_for)
+            );
+
+            -- Notifications sent to each device and Slack channel, for rate limits and
+            -- suppressing repeats about the same story cluster
+            CREATE TABLE IF NOT EXISTS notification_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                recipient TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                cluster_id INTEGER,
+                article_url TEXT NOT NULL,
+                sent_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_notification_log_recipient ON notification_log (recipient, sent_at);
+
+            -- Notifications held back by a rate limit until they are sent as one batch
+            CREATE TABLE IF NOT EXISTS notification_batches (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                recipient TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                cluster_id INTEGER,
+                article_url TEXT NOT NULL,
+                title TEXT NOT NULL,
+                held_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_notification_batches_recipient ON notification_batches (recipient, topic);
+
+            -- Full-text index of analyzed articles; the rowid is the article ID
+            CREATE VIRTUAL TABLE IF NOT EXISTS article_search USING fts5(
+                title,
+                summary,
+                tiny_summary,
+                critical_analysis,
+                entities,
+                tokenize = 'porter unicode61'
+            );
+
+            -- The outcome of the latest fetch of each RSS feed, for the admin dashboard
+            CREATE TABLE IF NOT EXISTS feed_status (
+                url TEXT PRIMARY KEY,
+                last_fetched_at INTEGER NOT NULL,
+                last_success_at INTEGER,
+                consecutive_failures INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                last_new_articles INTEGER NOT NULL DEFAULT 0
+            );
+
+            -- App API refresh tokens, stored as SHA-256 hashes; each is bound to a device
+            CREATE TABLE IF NOT EXISTS refresh_tokens (
+                token_hash TEXT PRIMARY KEY,
+                device_id TEXT NOT NULL,
+                issued_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_refresh_tokens_device_id ON refresh_tokens (device_id);
+
+            -- Revoked app API access tokens by JWT ID, kept until they expire
+            CREATE TABLE IF NOT EXISTS revoked_tokens (
+                jti TEXT PRIMARY KEY,
+                device_id TEXT NOT NULL,
+                revoked_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL
+            );
+
+            -- Devices whose access tokens issued before revoked_before are all revoked
+            CREATE TABLE IF NOT EXISTS revoked_devices (
+                device_id TEXT PRIMARY KEY,
+                revoked_before INTEGER NOT NULL
+            );
+
+            -- Events streamed to API clients, kept so they can resume from the last ID seen
+            CREATE TABLE IF NOT EXISTS event_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                topics TEXT, -- JSON array, NULL for events without topics
+                payload TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_event_log_created_at ON event_log (created_at);
+
             -- Upgrade to new life_safety_queue table:
             -- ALTER TABLE life_safety_queue RENAME TO life_safety_queue_old;
             CREATE TABLE IF NOT EXISTS life_safety_queue (
@@ -244,8 +515,69 @@ impl Database {
         )
         .execute(&mut *conn)
         .await?;
+
+        // Columns added after the original tables were created
+        self.ensure_column("matched_topics_queue", "matched_topics", "TEXT")
+            .await?;
+        self.ensure_column("articles", "language", "TEXT").await?;
+        self.ensure_column("articles", "minhash", "BLOB").await?;
+        self.ensure_column("life_safety_queue", "threat_category", "TEXT")
+            .await?;
+        self.ensure_column("life_safety_queue", "threat_severity", "TEXT")
+            .await?;
+        self.ensure_column("life_safety_queue", "threat_response", "TEXT")
+            .await?;
+        self.ensure_column("life_safety_queue", "threat_starts_at", "TEXT")
+            .await?;
+        self.ensure_column("life_safety_queue", "threat_ends_at", "TEXT")
+            .await?;
+        self.ensure_column("life_safety_queue", "threat_confidence", "REAL")
+            .await?;
+        self.ensure_column("people", "latitude", "REAL").await?;
+        self.ensure_column("people", "quiet_hours", "TEXT").await?;
+        self.ensure_column("people", "longitude", "REAL").await?;
+        self.ensure_column("alert_deliveries", "claimed_at", "TEXT")
+            .await?;
+        self.ensure_column("articles", "canonical_article_id", "INTEGER")
+            .await?;
+        self.ensure_column("devices", "platform", "TEXT NOT NULL DEFAULT 'ios'")
+            .await?;
+        // The subscription keys of web push devices, as JSON
+        self.ensure_column("devices", "push_keys", "TEXT").await?;
+        for queue in ["matched_topics_queue", "life_safety_queue"] {
+            // article_text holds the translation when an article was translated
+            self.ensure_column(queue, "language", "TEXT").await?;
+            self.ensure_column(queue, "original_text", "TEXT").await?;
+        }
+
         info!(target: TARGET_DB, "Tables ensured to exist");
 
         Ok(())
     }
+
+    /// Adds a column to an existing table if it is missing, so older databases pick up
+    /// new columns without a separate migration.
+    pub(crate) async fn ensure_column(
+        &self,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<(), sqlx::Error> {
+        let columns: Vec<String> =
+            sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+                .fetch_all(self.pool())
+                .await?;
+
+        if !columns.iter().any(|name| name == column) {
+            info!(target: TARGET_DB, "Adding column {}.{}", table, column);
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))
+            .execute(self.pool())
+            .await?;
+        }
+
+        Ok(())
+    }
 }
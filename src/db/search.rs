@@ -0,0 +1,147 @@
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{debug, instrument};
+
+use super::core::Database;
+use crate::db::Row;
+use crate::TARGET_DB;
+
+/// BM25 weights of the `article_search` columns: title, summary, tiny summary, critical
+/// analysis and entity names.
+const BM25_WEIGHTS: &str = "10.0, 4.0, 5.0, 2.0, 3.0";
+
+/// An article matching a full-text search.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub id: i64,
+    pub url: String,
+    pub title: Option<String>,
+    pub tiny_summary: Option<String>,
+    pub category: Option<String>,
+    pub quality: Option<i64>,
+    pub pub_date: Option<String>,
+    /// BM25 relevance, higher is better
+    pub score: f64,
+    /// The best matching passage, with matches in [brackets]
+    pub snippet: Option<String>,
+}
+
+impl SearchHit {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Self {
+        let analysis: Option<String> = row.get("analysis");
+        let analysis: Value = analysis
+            .and_then(|analysis| serde_json::from_str(&analysis).ok())
+            .unwrap_or_default();
+        SearchHit {
+            id: row.get("id"),
+            url: row.get("url"),
+            title: analysis["title"].as_str().map(str::to_string),
+            tiny_summary: row.get("tiny_summary"),
+            category: row.get("category"),
+            quality: analysis["quality"].as_i64(),
+            pub_date: row.get("pub_date"),
+            score: row.get("score"),
+            snippet: row.get("snippet"),
+        }
+    }
+}
+
+impl Database {
+    /// Adds an article to the full-text index, or refreshes it after its analysis or
+    /// entities changed. Articles without an analysis are removed from the index.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn index_article_for_search(&self, article_id: i64) -> Result<(), sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT a.analysis, a.tiny_summary,
+                   (SELECT GROUP_CONCAT(e.name, ' ')
+                    FROM article_entities ae JOIN entities e ON e.id = ae.entity_id
+                    WHERE ae.article_id = a.id) AS entities
+            FROM articles a
+            WHERE a.id = ?1
+            "#,
+        )
+        .bind(article_id)
+        .fetch_optional(self.pool())
+        .await?;
+
+        let mut transaction = self.pool().begin().await?;
+        sqlx::query("DELETE FROM article_search WHERE rowid = ?1")
+            .bind(article_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        let analysis: Option<Value> = row.as_ref().and_then(|row| {
+            row.get::<Option<String>, _>("analysis")
+                .and_then(|analysis| serde_json::from_str(&analysis).ok())
+        });
+        if let (Some(row), Some(analysis)) = (row, analysis) {
+            let text = |name: &str| analysis[name].as_str().unwrap_or("").to_string();
+            sqlx::query(
+                r#"
+                INSERT INTO article_search (rowid, title, summary, tiny_summary, critical_analysis, entities)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                "#,
+            )
+            .bind(article_id)
+            .bind(text("title"))
+            .bind(text("summary"))
+            .bind(row.get::<Option<String>, _>("tiny_summary"))
+            .bind(text("critical_analysis"))
+            .bind(row.get::<Option<String>, _>("entities"))
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+        debug!(target: TARGET_DB, "Indexed article {} for search", article_id);
+        Ok(())
+    }
+
+    /// Indexes every analyzed article again, e.g. after upgrading a database from before
+    /// the index existed. Returns the number of articles indexed.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn rebuild_search_index(&self) -> Result<usize, sqlx::Error> {
+        let ids: Vec<i64> =
+            sqlx::query_scalar("SELECT id FROM articles WHERE analysis IS NOT NULL ORDER BY id")
+                .fetch_all(self.pool())
+                .await?;
+        for &id in &ids {
+            self.index_article_for_search(id).await?;
+        }
+        Ok(ids.len())
+    }
+
+    /// Finds articles matching an FTS5 query, best match first by BM25, optionally only
+    /// those about `topic`.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn search_articles(
+        &self,
+        fts_query: &str,
+        topic: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<SearchHit>, sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT a.id, a.url, a.category, a.tiny_summary, a.analysis, a.pub_date,
+                   -bm25(article_search, {}) AS score,
+                   snippet(article_search, -1, '[', ']', '…', 16) AS snippet
+            FROM article_search
+            JOIN articles a ON a.id = article_search.rowid
+            WHERE article_search MATCH ?1
+              AND (?2 IS NULL OR a.category = ?2
+                   OR a.id IN (SELECT article_id FROM article_topics WHERE topic = ?2))
+            ORDER BY score DESC
+            LIMIT ?3
+            "#,
+            BM25_WEIGHTS
+        ))
+        .bind(fts_query)
+        .bind(topic)
+        .bind(limit)
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(rows.iter().map(SearchHit::from_row).collect())
+    }
+}
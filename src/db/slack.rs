@@ -0,0 +1,45 @@
+use chrono::Utc;
+use tracing::debug;
+
+use super::core::Database;
+use crate::TARGET_DB;
+
+impl Database {
+    /// Returns the `ts` of the first Slack message posted about a cluster in a channel, if
+    /// there is one.
+    pub async fn get_slack_thread(
+        &self,
+        cluster_id: i64,
+        channel: &str,
+    ) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT ts FROM slack_threads WHERE cluster_id = ?1 AND channel = ?2")
+            .bind(cluster_id)
+            .bind(channel)
+            .fetch_optional(self.pool())
+            .await
+    }
+
+    /// Remembers the message later articles in a cluster are threaded under. The first
+    /// message wins if two are posted at once.
+    pub async fn save_slack_thread(
+        &self,
+        cluster_id: i64,
+        channel: &str,
+        ts: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO slack_threads (cluster_id, channel, ts, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+        )
+        .bind(cluster_id)
+        .bind(channel)
+        .bind(ts)
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool())
+        .await?;
+        debug!(target: TARGET_DB, "Saved Slack thread {} for cluster {} in {}", ts, cluster_id, channel);
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use chrono::Utc;
+use tracing::{debug, instrument};
+
+use super::core::Database;
+use crate::db::Row;
+use crate::TARGET_DB;
+
+/// A notification held back by a rate limit, waiting to be sent in a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeldNotification {
+    pub id: i64,
+    pub recipient: String,
+    pub topic: String,
+    pub cluster_id: Option<i64>,
+    pub article_url: String,
+    pub title: String,
+}
+
+impl Database {
+    /// Counts the notifications sent to a recipient since `since` (unix seconds).
+    pub async fn count_notifications_since(
+        &self,
+        recipient: &str,
+        since: i64,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM notification_log WHERE recipient = ?1 AND sent_at >= ?2",
+        )
+        .bind(recipient)
+        .bind(since)
+        .fetch_one(self.pool())
+        .await
+    }
+
+    /// Returns whether a recipient was notified about a story cluster since `since` (unix
+    /// seconds), or has a notification about it waiting in a batch.
+    pub async fn cluster_notified_since(
+        &self,
+        recipient: &str,
+        cluster_id: i64,
+        since: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT (SELECT COUNT(*) FROM notification_log
+                    WHERE recipient = ?1 AND cluster_id = ?2 AND sent_at >= ?3)
+                 + (SELECT COUNT(*) FROM notification_batches
+                    WHERE recipient = ?1 AND cluster_id = ?2)
+            "#,
+        )
+        .bind(recipient)
+        .bind(cluster_id)
+        .bind(since)
+        .fetch_one(self.pool())
+        .await?;
+        Ok(count > 0)
+    }
+
+    /// Returns whether a recipient was notified about an article since `since` (unix
+    /// seconds), or has a notification about it waiting in a batch.
+    pub async fn article_notified_since(
+        &self,
+        recipient: &str,
+        article_url: &str,
+        since: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT (SELECT COUNT(*) FROM notification_log
+                    WHERE recipient = ?1 AND article_url = ?2 AND sent_at >= ?3)
+                 + (SELECT COUNT(*) FROM notification_batches
+                    WHERE recipient = ?1 AND article_url = ?2)
+            "#,
+        )
+        .bind(recipient)
+        .bind(article_url)
+        .bind(since)
+        .fetch_one(self.pool())
+        .await?;
+        Ok(count > 0)
+    }
+
+    /// Records a notification sent to a recipient.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn log_notification(
+        &self,
+        recipient: &str,
+        topic: &str,
+        cluster_id: Option<i64>,
+        article_url: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO notification_log (recipient, topic, cluster_id, article_url, sent_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+        )
+        .bind(recipient)
+        .bind(topic)
+        .bind(cluster_id)
+        .bind(article_url)
+        .bind(Utc::now().timestamp())
+        .execute(self.pool())
+        .await?;
+        Ok(())
+    }
+
+    /// Holds back a notification for a recipient until it is sent in a batch.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn hold_notification(
+        &self,
+        recipient: &str,
+        topic: &str,
+        cluster_id: Option<i64>,
+        article_url: &str,
+        title: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO notification_batches (recipient, topic, cluster_id, article_url, title, held_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+        )
+        .bind(recipient)
+        .bind(topic)
+        .bind(cluster_id)
+        .bind(article_url)
+        .bind(title)
+        .bind(Utc::now().timestamp())
+        .execute(self.pool())
+        .await?;
+        debug!(target: TARGET_DB, "Held notification about {} for {}", article_url, recipient);
+        Ok(())
+    }
+
+    /// Returns the held notifications of every recipient and topic whose oldest one was
+    /// held before `held_before` (unix seconds). They stay held until released once sent.
+    #[instrument(target = "db", level = "debug", skip(self))]
+    pub async fn fetch_due_batches(
+        &self,
+        held_before: i64,
+    ) -> Result<Vec<HeldNotification>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT b.id, b.recipient, b.topic, b.cluster_id, b.article_url, b.title
+            FROM notification_batches b
+            JOIN (
+                SELECT recipient, topic FROM notification_batches
+                GROUP BY recipient, topic
+                HAVING MIN(held_at) < ?1
+            ) due ON due.recipient = b.recipient AND due.topic = b.topic
+            ORDER BY b.recipient, b.topic, b.held_at
+            "#,
+        )
+        .bind(held_before)
+        .fetch_all(self.pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| HeldNotification {
+                id: row.get("id"),
+                recipient: row.get("recipient"),
+                topic: row.get("topic"),
+                cluster_id: row.get("cluster_id"),
+                article_url: row.get("article_url"),
+                title: row.get("title"),
+            })
+            .collect())
+    }
+
+    /// Deletes held notifications once their batch was sent.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn release_held_notifications(&self, ids: &[i64]) -> Result<(), sqlx::Error> {
+        let mut transaction = self.pool().begin().await?;
+        for id in ids {
+            sqlx::query("DELETE FROM notification_batches WHERE id = ?1")
+                .bind(id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+        transaction.commit().await?;
+        debug!(target: TARGET_DB, "Released {} held notifications", ids.len());
+        Ok(())
+    }
+
+    /// Gives up on held notifications that are still unsent after being held since before
+    /// `before` (unix seconds), because their recipient is gone or keeps failing.
+    #[instrument(target = "db", level = "info", skip(self))]
+    pub async fn prune_held_notifications(&self, before: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM notification_batches WHERE held_at < ?1")
+            .bind(before)
+            .execute(self.pool())
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Forgets notifications sent before `before` (unix seconds), once they no longer count
+    /// towards any limit.
+    pub async fn prune_notification_log(&self, before: i64) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM notification_log WHERE sent_at < ?1")
+            .bind(before)
+            .execute(self.pool())
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,677 @@
+//! Scheduled daily and weekly digests of the most important stories.
+//!
+//! Digests are configured in the JSON file named by `DIGESTS_JSON_PATH`. A topic digest
+//! covers the stories of one topic; a cluster digest covers the top story clusters across
+//! topics. Stories are ranked by the importance of their cluster and the quality of their
+//! best article, introduced by an LLM-written overview, rendered as Markdown and HTML, and
+//! delivered to Slack, email and R2.
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveTime, Utc, Weekday};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use tokio::time::{sleep, Duration as TokioDuration};
+use tracing::{error, info, warn};
+
+use crate::alerts::utc_offset;
+use crate::app::util::upload_to_r2;
+use crate::db::core::Database;
+use crate::db::digest::DigestArticle;
+use crate::email::send_email;
+use crate::llm::generate_llm_response;
+use crate::prompt;
+use crate::slack::send_slack_notice;
+use crate::topics;
+use crate::util::escape_html;
+use crate::{LLMClient, LLMParams, WorkerDetail, TARGET_LLM_REQUEST, TARGET_WEB_REQUEST};
+
+const DIGESTS_JSON_PATH_ENV: &str = "DIGESTS_JSON_PATH";
+
+/// How often the scheduler looks for digests that are due.
+const CHECK_INTERVAL_SECS: u64 = 60;
+
+/// A run that is due more than this many hours ago, e.g. after downtime, is skipped
+/// rather than sent late.
+const MAX_LATENESS_HOURS: i64 = 6;
+
+/// Related articles listed under each story.
+const MAX_RELATED_ARTICLES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Schedule {
+    Daily,
+    Weekly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestKind {
+    /// One digest per topic
+    #[default]
+    Topic,
+    /// One digest of the top story clusters
+    Cluster,
+}
+
+/// A digest in `DIGESTS_JSON_PATH`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DigestConfig {
+    pub name: String,
+    #[serde(default)]
+    pub kind: DigestKind,
+    pub schedule: Schedule,
+    /// Local time the digest is sent, "HH:MM"
+    #[serde(default = "default_at")]
+    pub at: String,
+    /// Day weekly digests are sent, e.g. "monday"
+    #[serde(default)]
+    pub weekday: Option<String>,
+    /// Time zone `at` is read in, e.g. "CET (UTC+1)"; UTC if unset
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// Topics to cover; all configured topics if empty
+    #[serde(default)]
+    pub topics: Vec<String>,
+    /// How far back the digest looks; a day for daily and a week for weekly digests
+    #[serde(default)]
+    pub hours: Option<i64>,
+    /// Maximum number of stories in each digest
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    #[serde(default)]
+    pub slack_channel: Option<String>,
+    #[serde(default)]
+    pub email: Vec<String>,
+    /// Upload the digest to R2
+    #[serde(default)]
+    pub r2: bool,
+}
+
+fn default_at() -> String {
+    "07:00".to_string()
+}
+
+fn default_limit() -> usize {
+    10
+}
+
+impl DigestConfig {
+    fn time(&self) -> Option<NaiveTime> {
+        NaiveTime::parse_from_str(self.at.trim(), "%H:%M").ok()
+    }
+
+    fn weekday(&self) -> Option<Weekday> {
+        match &self.weekday {
+            Some(weekday) => weekday.trim().parse().ok(),
+            None => Some(Weekday::Mon),
+        }
+    }
+
+    fn lookback(&self) -> Duration {
+        match (self.hours, self.schedule) {
+            (Some(hours), _) => Duration::hours(hours),
+            (None, Schedule::Daily) => Duration::days(1),
+            (None, Schedule::Weekly) => Duration::weeks(1),
+        }
+    }
+
+    /// The most recent time the digest was scheduled for, at or before `now`.
+    pub fn last_due(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let offset = self
+            .timezone
+            .as_deref()
+            .and_then(utc_offset)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        let local = now.with_timezone(&offset);
+        let mut due = local.date_naive().and_time(self.time()?);
+        if due > local.naive_local() {
+            due -= Duration::days(1);
+        }
+        if self.schedule == Schedule::Weekly {
+            let weekday = self.weekday()?;
+            while due.weekday() != weekday {
+                due -= Duration::days(1);
+            }
+        }
+        Some((due - offset).and_utc())
+    }
+}
+
+/// The contents of `DIGESTS_JSON_PATH`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DigestsConfig {
+    #[serde(default)]
+    pub digests: Vec<DigestConfig>,
+}
+
+impl DigestsConfig {
+    /// Parses the configuration, checking every schedule and that each digest goes
+    /// somewhere.
+    pub fn parse(json_data: &str) -> Result<Self, String> {
+        let config: DigestsConfig = serde_json::from_str(json_data)
+            .map_err(|err| format!("Failed to parse digests file: {}", err))?;
+        for digest in &config.digests {
+            if digest.time().is_none() {
+                return Err(format!(
+                    "Digest {} has invalid time {}",
+                    digest.name, digest.at
+                ));
+            }
+            if digest.weekday().is_none() {
+                return Err(format!("Digest {} has an invalid weekday", digest.name));
+            }
+            if digest.slack_channel.is_none() && digest.email.is_empty() && !digest.r2 {
+                return Err(format!(
+                    "Digest {} has nowhere to be delivered",
+                    digest.name
+                ));
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// The digests, loaded once from `DIGESTS_JSON_PATH`.
+static DIGESTS: Lazy<Vec<DigestConfig>> = Lazy::new(|| {
+    let Ok(path) = env::var(DIGESTS_JSON_PATH_ENV) else {
+        return Vec::new();
+    };
+    match fs::read_to_string(&path)
+        .map_err(|err| format!("Failed to read the digests file: {}", err))
+        .and_then(|json_data| DigestsConfig::parse(&json_data))
+    {
+        Ok(config) => {
+            info!("Loaded {} digests", config.digests.len());
+            config.digests
+        }
+        Err(err) => {
+            error!("Failed to load digests from {}: {}", path, err);
+            Vec::new()
+        }
+    }
+});
+
+/// One story in a digest: the best article of a cluster, with links to others.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestStory {
+    pub title: String,
+    pub url: String,
+    pub summary: String,
+    pub quality: i64,
+    pub score: f64,
+    /// Title and URL of other articles about the same story
+    pub related: Vec<(String, String)>,
+}
+
+/// Groups articles into stories by cluster and returns the `limit` most important. A
+/// story scores its cluster's importance plus a quarter of its best article's quality.
+/// Cluster digests describe each story by its cluster summary where there is one.
+pub fn rank_stories(
+    articles: &[DigestArticle],
+    use_cluster_summary: bool,
+    limit: usize,
+) -> Vec<DigestStory> {
+    // Articles outside a cluster are stories of their own
+    let mut groups: HashMap<i64, Vec<&DigestArticle>> = HashMap::new();
+    for article in articles {
+        groups
+            .entry(article.cluster_id.unwrap_or(-article.id))
+            .or_default()
+            .push(article);
+    }
+
+    let mut stories: Vec<DigestStory> = groups
+        .into_values()
+        .map(|mut group| {
+            group.sort_by(|a, b| b.quality.cmp(&a.quality).then(b.id.cmp(&a.id)));
+            let best = group[0];
+            let summary = match &best.cluster_summary {
+                Some(summary) if use_cluster_summary && !summary.trim().is_empty() => {
+                    summary.trim().to_string()
+                }
+                _ => best.tiny_summary.trim().to_string(),
+            };
+            DigestStory {
+                title: best.title.clone(),
+                url: best.url.clone(),
+                summary,
+                quality: best.quality,
+                score: best.importance_score + best.quality as f64 / 4.0,
+                related: group[1..]
+                    .iter()
+                    .take(MAX_RELATED_ARTICLES)
+                    .map(|article| (article.title.clone(), article.url.clone()))
+                    .collect(),
+            }
+        })
+        .collect();
+    stories.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.quality.cmp(&a.quality))
+            .then(a.title.cmp(&b.title))
+    });
+    stories.truncate(limit);
+    stories
+}
+
+/// A finished digest.
+#[derive(Debug, Clone)]
+pub struct Digest {
+    pub title: String,
+    /// What the digest covers, e.g. "the last 24 hours"
+    pub period: String,
+    pub overview: Option<String>,
+    pub stories: Vec<DigestStory>,
+}
+
+impl Digest {
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n_Stories from {}_\n\n", self.title, self.period);
+        if let Some(overview) = &self.overview {
+            markdown.push_str(overview.trim());
+            markdown.push_str("\n\n");
+        }
+        markdown.push_str("## Stories\n");
+        for (i, story) in self.stories.iter().enumerate() {
+            markdown.push_str(&format!(
+                "{}. **[{}]({})**\n{}\n",
+                i + 1,
+                story.title,
+                story.url,
+                story.summary
+            ));
+            if !story.related.is_empty() {
+                let related: Vec<String> = story
+                    .related
+                    .iter()
+                    .map(|(title, url)| format!("[{}]({})", title, url))
+                    .collect();
+                markdown.push_str(&format!("Also: {}\n", related.join(", ")));
+            }
+            markdown.push('\n');
+        }
+        markdown
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<h1>{}</h1>\n<p><em>Stories from {}</em></p>\n",
+            escape_html(&self.title),
+            escape_html(&self.period)
+        );
+        if let Some(overview) = &self.overview {
+            for paragraph in overview.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                html.push_str(&format!("<p>{}</p>\n", escape_html(paragraph.trim())));
+            }
+        }
+        html.push_str("<h2>Stories</h2>\n<ol>\n");
+        for story in &self.stories {
+            html.push_str(&format!(
+                "<li><strong><a href=\"{}\">{}</a></strong><p>{}</p>",
+                escape_html(&story.url),
+                escape_html(&story.title),
+                escape_html(&story.summary).replace('\n', "<br>")
+            ));
+            if !story.related.is_empty() {
+                let related: Vec<String> = story
+                    .related
+                    .iter()
+                    .map(|(title, url)| {
+                        format!(
+                            "<a href=\"{}\">{}</a>",
+                            escape_html(url),
+                            escape_html(title)
+                        )
+                    })
+                    .collect();
+                html.push_str(&format!("<p>Also: {}</p>", related.join(", ")));
+            }
+            html.push_str("</li>\n");
+        }
+        html.push_str("</ol>\n");
+        html
+    }
+}
+
+/// The model that writes digest overviews.
+#[derive(Clone)]
+pub struct DigestWriter {
+    pub llm_client: LLMClient,
+    pub model: String,
+}
+
+impl DigestWriter {
+    async fn write_overview(
+        &self,
+        title: &str,
+        period: &str,
+        stories: &[DigestStory],
+    ) -> Option<String> {
+        let stories_text: Vec<String> = stories
+            .iter()
+            .map(|story| format!("- {}: {}", story.title, story.summary.replace('\n', " ")))
+            .collect();
+        let llm_params = LLMParams {
+            llm_client: self.llm_client.clone(),
+            model: self.model.clone(),
+            temperature: 0.2,
+            require_json: None,
+            json_format: None,
+            thinking_config: None,
+            no_think: false,
+        };
+        let worker_detail = WorkerDetail {
+            name: "digest writer".to_string(),
+            id: 0,
+            model: self.model.clone(),
+            connection_info: "digest".to_string(),
+        };
+        generate_llm_response(
+            &prompt::digest_overview_prompt(title, period, &stories_text.join("\n")),
+            &llm_params,
+            &worker_detail,
+            "digest_overview",
+        )
+        .await
+        .map(|overview| overview.trim().to_string())
+        .filter(|overview| !overview.is_empty())
+    }
+}
+
+fn describe_period(lookback: Duration) -> String {
+    match lookback.num_hours() {
+        24 => "the last day".to_string(),
+        168 => "the last week".to_string(),
+        hours if hours % 24 == 0 => format!("the last {} days", hours / 24),
+        hours => format!("the last {} hours", hours),
+    }
+}
+
+/// Builds the digests of one scheduled run: one per topic for topic digests, or one for
+/// cluster digests. Digests without stories are left out.
+pub async fn build_digests(
+    db: &Database,
+    config: &DigestConfig,
+    scheduled_for: DateTime<Utc>,
+    writer: Option<&DigestWriter>,
+) -> Result<Vec<Digest>, sqlx::Error> {
+    let lookback = config.lookback();
+    let period = describe_period(lookback);
+    let articles = db.fetch_digest_articles(scheduled_for - lookback).await?;
+    let covers = |article: &DigestArticle, topics: &[String]| {
+        topics.is_empty() || article.topics.iter().any(|topic| topics.contains(topic))
+    };
+
+    let mut sections: Vec<(String, Vec<DigestStory>)> = Vec::new();
+    match config.kind {
+        DigestKind::Topic => {
+            let topic_names: Vec<String> = if config.topics.is_empty() {
+                topics::all()
+                    .iter()
+                    .map(|topic| topic.name.clone())
+                    .collect()
+            } else {
+                config.topics.clone()
+            };
+            for topic in topic_names {
+                let topic_articles: Vec<DigestArticle> = articles
+                    .iter()
+                    .filter(|article| covers(article, std::slice::from_ref(&topic)))
+                    .cloned()
+                    .collect();
+                let stories = rank_stories(&topic_articles, false, config.limit);
+                sections.push((format!("{}: {}", config.name, topic), stories));
+            }
+        }
+        DigestKind::Cluster => {
+            let cluster_articles: Vec<DigestArticle> = articles
+                .iter()
+                .filter(|article| covers(article, &config.topics))
+                .cloned()
+                .collect();
+            let stories = rank_stories(&cluster_articles, true, config.limit);
+            sections.push((config.name.clone(), stories));
+        }
+    }
+
+    let mut digests = Vec::new();
+    for (title, stories) in sections {
+        if stories.is_empty() {
+            info!("Digest {} has no stories from {}, skipping", title, period);
+            continue;
+        }
+        let overview = match writer {
+            Some(writer) => writer.write_overview(&title, &period, &stories).await,
+            None => None,
+        };
+        digests.push(Digest {
+            title,
+            period: period.clone(),
+            overview,
+            stories,
+        });
+    }
+    Ok(digests)
+}
+
+/// Sends a digest to each destination of its configuration. Returns the R2 URL if it was
+/// uploaded.
+async fn deliver_digest(
+    config: &DigestConfig,
+    digest: &Digest,
+    slack_token: &str,
+) -> Option<String> {
+    let markdown = digest.to_markdown();
+
+    if let Some(channel) = &config.slack_channel {
+        if !send_slack_notice(&markdown, slack_token, channel).await {
+            error!(target: TARGET_WEB_REQUEST, "Failed to send digest {} to Slack", digest.title);
+        }
+    }
+
+    for to in &config.email {
+        if let Err(e) = send_email(to, &digest.title, &markdown).await {
+            error!(target: TARGET_WEB_REQUEST, "Failed to email digest {} to {}: {}", digest.title, to, e);
+        }
+    }
+
+    if !config.r2 {
+        return None;
+    }
+    let stories: Vec<_> = digest
+        .stories
+        .iter()
+        .map(|story| {
+            json!({
+                "title": story.title,
+                "url": story.url,
+                "summary": story.summary,
+                "quality": story.quality,
+                "score": story.score,
+                "related": story
+                    .related
+                    .iter()
+                    .map(|(title, url)| json!({ "title": title, "url": url }))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    let r2_url = upload_to_r2(&json!({
+        "type": "digest",
+        "title": digest.title,
+        "period": digest.period,
+        "overview": digest.overview,
+        "stories": stories,
+        "markdown": markdown,
+        "html": digest.to_html(),
+    }))
+    .await;
+    if r2_url.is_none() {
+        error!(target: TARGET_WEB_REQUEST, "Failed to upload digest {} to R2", digest.title);
+    }
+    r2_url
+}
+
+/// Builds and delivers every digest that is due and hasn't been sent yet.
+pub async fn send_due_digests(
+    db: &Database,
+    now: DateTime<Utc>,
+    writer: Option<&DigestWriter>,
+    slack_token: &str,
+) {
+    for config in DIGESTS.iter() {
+        let Some(scheduled_for) = config.last_due(now) else {
+            continue;
+        };
+        if now - scheduled_for > Duration::hours(MAX_LATENESS_HOURS) {
+            continue;
+        }
+        match db.claim_digest_run(&config.name, scheduled_for).await {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                error!("Failed to claim digest run {}: {:?}", config.name, e);
+                continue;
+            }
+        }
+
+        info!(target: TARGET_LLM_REQUEST, "Building digest {} scheduled for {}", config.name, scheduled_for);
+        let digests = match build_digests(db, config, scheduled_for, writer).await {
+            Ok(digests) => digests,
+            Err(e) => {
+                error!("Failed to build digest {}: {:?}", config.name, e);
+                continue;
+            }
+        };
+        let mut stories = 0;
+        let mut r2_urls = Vec::new();
+        for digest in &digests {
+            stories += digest.stories.len();
+            r2_urls.extend(deliver_digest(config, digest, slack_token).await);
+        }
+        let r2_url = (!r2_urls.is_empty()).then(|| r2_urls.join(" "));
+        if let Err(e) = db
+            .complete_digest_run(&config.name, scheduled_for, stories, r2_url.as_deref())
+            .await
+        {
+            warn!("Failed to record digest run {}: {:?}", config.name, e);
+        }
+        info!(
+            "Sent {} digests with {} stories for {}",
+            digests.len(),
+            stories,
+            config.name
+        );
+    }
+}
+
+/// Checks every minute for digests that are due. Returns immediately if none are configured.
+pub async fn digest_loop(writer: Option<DigestWriter>, slack_token: String) -> anyhow::Result<()> {
+    if DIGESTS.is_empty() {
+        info!("No digests configured");
+        return Ok(());
+    }
+    let db = Database::instance().await;
+    loop {
+        send_due_digests(db, Utc::now(), writer.as_ref(), &slack_token).await;
+        sleep(TokioDuration::from_secs(CHECK_INTERVAL_SECS)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn config(json: &str) -> DigestConfig {
+        DigestsConfig::parse(&format!(r#"{{"digests": [{}]}}"#, json))
+            .unwrap()
+            .digests
+            .remove(0)
+    }
+
+    #[test]
+    fn test_last_due() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 12, 6, 30, 0).unwrap(); // a Wednesday
+        let daily = config(r#"{"name": "d", "schedule": "daily", "at": "07:00", "r2": true}"#);
+        assert_eq!(
+            daily.last_due(now),
+            Some(Utc.with_ymd_and_hms(2025, 3, 11, 7, 0, 0).unwrap())
+        );
+        let cet = config(
+            r#"{"name": "d", "schedule": "daily", "at": "07:00", "timezone": "CET (UTC+1)", "r2": true}"#,
+        );
+        assert_eq!(
+            cet.last_due(now),
+            Some(Utc.with_ymd_and_hms(2025, 3, 12, 6, 0, 0).unwrap())
+        );
+        let weekly = config(
+            r#"{"name": "w", "schedule": "weekly", "weekday": "monday", "at": "08:00", "r2": true}"#,
+        );
+        assert_eq!(
+            weekly.last_due(now),
+            Some(Utc.with_ymd_and_hms(2025, 3, 10, 8, 0, 0).unwrap())
+        );
+        assert_eq!(weekly.lookback(), Duration::weeks(1));
+
+        assert!(
+            DigestsConfig::parse(r#"{"digests": [{"name": "x", "schedule": "daily"}]}"#).is_err()
+        );
+        assert!(DigestsConfig::parse(
+            r#"{"digests": [{"name": "x", "schedule": "daily", "at": "25:00", "r2": true}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_rank_stories() {
+        let article = |id, quality, cluster_id, importance_score| DigestArticle {
+            id,
+            url: format!("https://example.com/{}", id),
+            title: format!("Article {}", id),
+            tiny_summary: format!("Summary {}", id),
+            quality,
+            topics: vec!["Rust".to_string()],
+            cluster_id,
+            cluster_summary: cluster_id.map(|id| format!("Cluster {}", id)),
+            importance_score,
+        };
+        let articles = vec![
+            article(1, 2, Some(10), 1.5),
+            article(2, 4, Some(10), 1.5),
+            article(3, 4, None, 0.0),
+            article(4, -2, Some(20), 2.0),
+        ];
+
+        let stories = rank_stories(&articles, true, 10);
+        assert_eq!(stories.len(), 3);
+        assert_eq!(stories[0].title, "Article 2");
+        assert_eq!(stories[0].summary, "Cluster 10");
+        assert_eq!(
+            stories[0].related,
+            vec![("Article 1".to_string(), "https://example.com/1".to_string())]
+        );
+        assert_eq!(stories[1].title, "Article 4");
+        assert_eq!(stories[2].title, "Article 3");
+
+        let stories = rank_stories(&articles, false, 1);
+        assert_eq!(stories.len(), 1);
+        assert_eq!(stories[0].summary, "Summary 2");
+
+        let digest = Digest {
+            title: "Daily: Rust".to_string(),
+            period: "the last day".to_string(),
+            overview: Some("A <busy> day.".to_string()),
+            stories,
+        };
+        assert!(digest
+            .to_markdown()
+            .contains("1. **[Article 2](https://example.com/2)**\nSummary 2\nAlso: [Article 1](https://example.com/1)"));
+        assert!(digest.to_html().contains("<p>A &lt;busy&gt; day.</p>"));
+    }
+}
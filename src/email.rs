@@ -0,0 +1,60 @@
+//! Outgoing email, formatted with `emailmessage` and handed to the local sendmail.
+
+use emailmessage::{Mailbox, Message};
+use std::env;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+const EMAIL_FROM_ENV: &str = "EMAIL_FROM";
+const SENDMAIL_PATH_ENV: &str = "SENDMAIL_PATH";
+
+/// Sends a plain text email from `EMAIL_FROM` through the sendmail binary at
+/// `SENDMAIL_PATH` (default `/usr/sbin/sendmail`).
+pub async fn send_email(to: &str, subject: &str, body: &str) -> Result<(), String> {
+    let from: Mailbox = env::var(EMAIL_FROM_ENV)
+        .map_err(|_| format!("{} is not set", EMAIL_FROM_ENV))?
+        .parse()
+        .map_err(|e| format!("Invalid {}: {:?}", EMAIL_FROM_ENV, e))?;
+    let to: Mailbox = to
+        .parse()
+        .map_err(|e| format!("Invalid email address {}: {:?}", to, e))?;
+
+    let message: Message<&str> = Message::builder()
+        .from(from)
+        .to(to)
+        .subject(subject)
+        .date_now()
+        .body(body);
+
+    let sendmail = env::var(SENDMAIL_PATH_ENV).unwrap_or_else(|_| "/usr/sbin/sendmail".to_string());
+    let mut child = Command::new(&sendmail)
+        .args(["-t", "-i"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", sendmail, e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(message.to_string().as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write to {}: {}", sendmail, e))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Failed to wait for {}: {}", sendmail, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} exited with {}: {}",
+            sendmail,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
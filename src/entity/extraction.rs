@@ -24,7 +24,14 @@ pub async fn extract_entities(
     llm_params.json_format = Some(crate::JsonSchemaType::EntityExtraction);
 
     // Get LLM response
-    let response = match generate_llm_response(&entity_prompt, llm_params, worker_detail).await {
+    let response = match generate_llm_response(
+        &entity_prompt,
+        llm_params,
+        worker_detail,
+        "entity_extraction",
+    )
+    .await
+    {
         Some(response) => response,
         None => {
             error!(target: TARGET_ENTITY, "Failed to generate entity extraction response");
@@ -3,7 +3,6 @@ use std::collections::{HashMap, HashSet};
 use strsim::{jaro_winkler, levenshtein};
 use tracing::debug;
 use unicode_normalization::UnicodeNormalization;
-use whatlang::detect as detect_language;
 
 use super::aliases::{AliasCache, COMMON_VARIATIONS};
 use super::types::EntityType;
@@ -566,11 +565,6 @@ impl EntityNormalizer {
             .join(" ") // Join with single spaces
             .to_string()
     }
-
-    /// Detect language of text to apply language-specific normalization
-    fn _detect_language(&self, text: &str) -> Option<String> {
-        detect_language(text).map(|info| info.lang().code().to_string())
-    }
 }
 
 #[cfg(test)]
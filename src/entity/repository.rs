@@ -41,6 +41,14 @@ pub async fn store_entities(
         "Stored {} entities for article {}", entity_ids.len(), article_id
     );
 
+    // Entity names are searchable, so refresh the article's full-text entry
+    if let Err(e) = db.index_article_for_search(article_id).await {
+        error!(
+            target: TARGET_ENTITY,
+            "Failed to index entities of article {} for search: {}", article_id, e
+        );
+    }
+
     Ok(entity_ids)
 }
 
@@ -0,0 +1,271 @@
+//! Typed events for API clients: newly analyzed articles, life safety alerts and story
+//! cluster updates.
+//!
+//! Events are appended to the `event_log` table and streamed from there, so a client
+//! that reconnects with the last event ID it saw gets everything it missed. A watch
+//! channel holding the latest event ID wakes up open streams when something is logged.
+
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration};
+use tracing::{error, warn};
+
+use crate::db::core::Database;
+use crate::db::events::{EventFilter, LoggedEvent};
+use crate::TARGET_DB;
+
+/// Events older than this are pruned from the log.
+const EVENT_RETENTION_DAYS: i64 = 7;
+/// The log is pruned whenever an event ID is a multiple of this.
+const PRUNE_EVERY: i64 = 100;
+/// Events read from the log at a time while catching a stream up.
+const STREAM_BATCH: i64 = 100;
+
+/// The kinds of events streamed to clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// An article was analyzed and stored
+    Analysis,
+    /// A threat to life or safety is being alerted
+    LifeSafetyAlert,
+    /// A story cluster's summary was rewritten
+    ClusterUpdate,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 3] = [
+        EventKind::Analysis,
+        EventKind::LifeSafetyAlert,
+        EventKind::ClusterUpdate,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Analysis => "analysis",
+            EventKind::LifeSafetyAlert => "life_safety_alert",
+            EventKind::ClusterUpdate => "cluster_update",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        EventKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == name.trim())
+    }
+}
+
+/// An event as sent to clients.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamEvent {
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub topics: Vec<String>,
+    pub created_at: String,
+    pub data: Value,
+}
+
+impl From<LoggedEvent> for StreamEvent {
+    fn from(event: LoggedEvent) -> Self {
+        StreamEvent {
+            id: event.id,
+            kind: event.kind,
+            topics: event.topics,
+            created_at: DateTime::from_timestamp(event.created_at, 0)
+                .unwrap_or_default()
+                .to_rfc3339(),
+            data: serde_json::from_str(&event.payload).unwrap_or(Value::Null),
+        }
+    }
+}
+
+static LATEST_EVENT: Lazy<watch::Sender<i64>> = Lazy::new(|| watch::channel(0).0);
+
+/// The parts of an analysis that go into analysis and life safety alert events; clients
+/// fetch the full analysis from `/articles/{id}`.
+pub fn article_payload(response_json: &Value) -> Value {
+    let mut payload = json!({});
+    for key in [
+        "id",
+        "url",
+        "title",
+        "tiny_title",
+        "tiny_summary",
+        "pub_date",
+        "language",
+        "quality",
+        "threat",
+        "incident",
+    ] {
+        if !response_json[key].is_null() {
+            payload[key] = response_json[key].clone();
+        }
+    }
+    payload
+}
+
+/// Logs an event and wakes up the open streams. Failures are logged, never returned, so
+/// the stream can't hold up the pipeline.
+pub async fn publish(db: &Database, kind: EventKind, topics: &[String], data: &Value) {
+    match db
+        .append_event(kind.as_str(), topics, &data.to_string())
+        .await
+    {
+        Ok(id) => {
+            LATEST_EVENT.send_replace(id);
+            if id % PRUNE_EVERY == 0 {
+                let before =
+                    (Utc::now() - chrono::Duration::days(EVENT_RETENTION_DAYS)).timestamp();
+                if let Err(e) = db.prune_events(before).await {
+                    warn!(target: TARGET_DB, "Failed to prune the event log: {:?}", e);
+                }
+            }
+        }
+        Err(e) => {
+            error!(target: TARGET_DB, "Failed to log {} event: {:?}", kind.as_str(), e);
+        }
+    }
+}
+
+/// Streams the events after `after_id` that match the filter: first those already
+/// logged, then new ones as they are published.
+pub fn stream(
+    db: &'static Database,
+    after_id: i64,
+    filter: EventFilter,
+) -> impl Stream<Item = StreamEvent> {
+    let state = (
+        LATEST_EVENT.subscribe(),
+        after_id,
+        VecDeque::<LoggedEvent>::new(),
+    );
+    stream::unfold(state, move |(mut latest, mut last_id, mut pending)| {
+        let filter = filter.clone();
+        async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    last_id = event.id;
+                    return Some((event.into(), (latest, last_id, pending)));
+                }
+
+                // Everything up to the latest ID published so far is in the log
+                let published = *latest.borrow_and_update();
+                match db.events_after(last_id, &filter, STREAM_BATCH).await {
+                    Ok(events) if !events.is_empty() => {
+                        pending.extend(events);
+                        continue;
+                    }
+                    Ok(_) => last_id = last_id.max(published),
+                    Err(e) => {
+                        error!(target: TARGET_DB, "Failed to read the event log: {:?}", e);
+                        sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+                }
+
+                if latest.changed().await.is_err() {
+                    return None;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use tokio::time::timeout;
+
+    #[test]
+    fn test_event_kinds() {
+        for kind in EventKind::ALL {
+            assert_eq!(EventKind::from_name(kind.as_str()), Some(kind));
+        }
+        assert_eq!(
+            EventKind::from_name(" cluster_update"),
+            Some(EventKind::ClusterUpdate)
+        );
+        assert_eq!(EventKind::from_name("analyses"), None);
+    }
+
+    #[tokio::test]
+    async fn test_stream_catches_up_then_follows() {
+        let db: &'static Database = Box::leak(Box::new(Database::in_memory().await));
+        let rust = vec!["Rust".to_string()];
+        publish(db, EventKind::Analysis, &rust, &json!({"id": 1})).await;
+        publish(
+            db,
+            EventKind::Analysis,
+            &["Go".to_string()],
+            &json!({"id": 2}),
+        )
+        .await;
+        publish(db, EventKind::LifeSafetyAlert, &rust, &json!({"id": 3})).await;
+
+        let filter = EventFilter {
+            kinds: Vec::new(),
+            topics: rust.clone(),
+        };
+        let events = stream(db, 0, filter);
+        futures::pin_mut!(events);
+
+        // Logged events first
+        let first = timeout(Duration::from_secs(5), events.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (first.kind.as_str(), first.data["id"].as_i64()),
+            ("analysis", Some(1))
+        );
+        let second = timeout(Duration::from_secs(5), events.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.kind, "life_safety_alert");
+        assert_eq!(second.topics, rust);
+
+        // Then events as they are published, skipping those that don't match
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(50)).await;
+            publish(
+                db,
+                EventKind::Analysis,
+                &["Go".to_string()],
+                &json!({"id": 4}),
+            )
+            .await;
+            publish(db, EventKind::ClusterUpdate, &[], &json!({"cluster_id": 7})).await;
+        });
+        let live = timeout(Duration::from_secs(5), events.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(live.kind, "cluster_update");
+        assert_eq!(live.data["cluster_id"], 7);
+        assert!(live.id > second.id + 1);
+    }
+
+    #[test]
+    fn test_article_payload() {
+        let response_json = json!({
+            "id": 7,
+            "url": "https://example.com/a",
+            "title": "A",
+            "tiny_summary": "Short",
+            "article_body": "Long text",
+            "quality": 2,
+            "incident": null,
+        });
+        assert_eq!(
+            article_payload(&response_json),
+            json!({"id": 7, "url": "https://example.com/a", "title": "A", "tiny_summary": "Short", "quality": 2})
+        );
+    }
+}
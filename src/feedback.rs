@@ -0,0 +1,135 @@
+//! Reader feedback on posted analyses, collected from Slack buttons.
+//!
+//! Each vote is stored against the article and topic it was posted under, so it can be
+//! exported as labelled data for the prompt regression set and for tuning per-topic
+//! thresholds.
+
+use serde::{Deserialize, Serialize};
+
+/// What a reader said about an analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackKind {
+    /// The article should not have matched the topic
+    NotRelevant,
+    /// The article is relevant, but to a different topic
+    WrongTopic,
+    /// A good match worth more like it
+    Great,
+}
+
+impl FeedbackKind {
+    /// In order of precedence when votes are tied: a disputed match is not a good one.
+    pub const ALL: [FeedbackKind; 3] = [
+        FeedbackKind::NotRelevant,
+        FeedbackKind::WrongTopic,
+        FeedbackKind::Great,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeedbackKind::NotRelevant => "not_relevant",
+            FeedbackKind::WrongTopic => "wrong_topic",
+            FeedbackKind::Great => "great",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "not_relevant" => Some(FeedbackKind::NotRelevant),
+            "wrong_topic" => Some(FeedbackKind::WrongTopic),
+            "great" => Some(FeedbackKind::Great),
+            _ => None,
+        }
+    }
+
+    /// Button label shown in Slack.
+    pub fn label(&self) -> &'static str {
+        match self {
+            FeedbackKind::NotRelevant => "Not relevant",
+            FeedbackKind::WrongTopic => "Wrong topic",
+            FeedbackKind::Great => "Great",
+        }
+    }
+
+    /// Whether the vote confirms the article belongs to the topic.
+    pub fn is_positive(&self) -> bool {
+        matches!(self, FeedbackKind::Great)
+    }
+}
+
+/// The votes for one article under one topic.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FeedbackVotes {
+    pub not_relevant: u32,
+    pub wrong_topic: u32,
+    pub great: u32,
+}
+
+impl FeedbackVotes {
+    pub fn add(&mut self, kind: FeedbackKind, count: u32) {
+        match kind {
+            FeedbackKind::NotRelevant => self.not_relevant += count,
+            FeedbackKind::WrongTopic => self.wrong_topic += count,
+            FeedbackKind::Great => self.great += count,
+        }
+    }
+
+    pub fn count(&self, kind: FeedbackKind) -> u32 {
+        match kind {
+            FeedbackKind::NotRelevant => self.not_relevant,
+            FeedbackKind::WrongTopic => self.wrong_topic,
+            FeedbackKind::Great => self.great,
+        }
+    }
+
+    /// The label with the most votes, if there are any.
+    pub fn label(&self) -> Option<FeedbackKind> {
+        FeedbackKind::ALL
+            .into_iter()
+            .filter(|kind| self.count(*kind) > 0)
+            .fold(None, |best: Option<FeedbackKind>, kind| match best {
+                Some(best) if self.count(best) >= self.count(kind) => Some(best),
+                _ => Some(kind),
+            })
+    }
+}
+
+/// A labelled article, as exported for the regression set and threshold tuning.
+#[derive(Debug, Clone, Serialize)]
+pub struct LabelledArticle {
+    pub article_id: i64,
+    pub url: String,
+    pub topic: String,
+    pub label: FeedbackKind,
+    pub votes: FeedbackVotes,
+    /// Embedding similarity of the article to the topic when it was matched
+    pub confidence: Option<f64>,
+    pub quality: Option<i64>,
+    pub title: Option<String>,
+    pub tiny_summary: Option<String>,
+    pub article_body: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feedback_label() {
+        let mut votes = FeedbackVotes::default();
+        assert_eq!(votes.label(), None);
+        votes.add(FeedbackKind::Great, 2);
+        assert_eq!(votes.label(), Some(FeedbackKind::Great));
+        votes.add(FeedbackKind::WrongTopic, 2);
+        assert_eq!(votes.label(), Some(FeedbackKind::WrongTopic));
+        votes.add(FeedbackKind::NotRelevant, 1);
+        assert_eq!(votes.label(), Some(FeedbackKind::WrongTopic));
+        votes.add(FeedbackKind::Great, 1);
+        assert_eq!(votes.label(), Some(FeedbackKind::Great));
+
+        for kind in FeedbackKind::ALL {
+            assert_eq!(FeedbackKind::from_name(kind.as_str()), Some(kind));
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! MinHash fingerprints for near-duplicate detection.
+//!
+//! Wire-service stories are republished with small differences in boilerplate, so the
+//! exact SHA-256 of the text misses them. A MinHash signature over word shingles estimates
+//! the Jaccard similarity of two articles. For the LSH lookup the signature is split into
+//! bands; articles sharing any band are candidates and are then compared on the full
+//! signature.
+
+use std::env;
+
+const NEAR_DUPLICATE_THRESHOLD_ENV: &str = "NEAR_DUPLICATE_THRESHOLD";
+
+/// Number of consecutive words in a shingle.
+const SHINGLE_SIZE: usize = 3;
+
+/// Articles with fewer shingles than this are too short to fingerprint reliably.
+const MIN_SHINGLES: usize = 20;
+
+/// Number of hash functions in a signature.
+pub const NUM_HASHES: usize = 64;
+
+/// Number of LSH bands; each band covers `NUM_HASHES / NUM_BANDS` rows. With 16 bands of
+/// 4 rows, articles with a Jaccard similarity of 0.8 become candidates 99.9% of the time.
+pub const NUM_BANDS: usize = 16;
+
+const ROWS_PER_BAND: usize = NUM_HASHES / NUM_BANDS;
+
+/// A MinHash signature.
+pub type Signature = [u32; NUM_HASHES];
+
+/// Estimated Jaccard similarity above which articles are near-duplicates, unless
+/// overridden.
+const DEFAULT_NEAR_DUPLICATE_THRESHOLD: f64 = 0.8;
+
+/// Minimum estimated Jaccard similarity at which two articles are near-duplicates
+/// (`NEAR_DUPLICATE_THRESHOLD`, default 0.8).
+pub fn threshold() -> f64 {
+    env::var(NEAR_DUPLICATE_THRESHOLD_ENV)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_NEAR_DUPLICATE_THRESHOLD)
+}
+
+/// Computes the MinHash signature of an article, ignoring case, punctuation and the
+/// `Title:`/`Body:` labels added at extraction. Returns `None` if the text is too short.
+pub fn minhash(text: &str) -> Option<Signature> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .filter(|word| *word != "Title:" && *word != "Body:")
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    if words.len() < SHINGLE_SIZE + MIN_SHINGLES - 1 {
+        return None;
+    }
+
+    let mut signature = [u32::MAX; NUM_HASHES];
+    for shingle in words.windows(SHINGLE_SIZE) {
+        let base = fnv1a(shingle.join(" ").as_bytes());
+        for (i, min) in signature.iter_mut().enumerate() {
+            let hash = splitmix64(base ^ (i as u64).wrapping_mul(0x9e3779b97f4a7c15)) as u32;
+            if hash < *min {
+                *min = hash;
+            }
+        }
+    }
+    Some(signature)
+}
+
+/// Estimated Jaccard similarity of the shingle sets behind two signatures.
+pub fn similarity(a: &Signature, b: &Signature) -> f64 {
+    let equal = a.iter().zip(b.iter()).filter(|(x, y)| x == y).count();
+    equal as f64 / NUM_HASHES as f64
+}
+
+/// Hashes each band of a signature for the LSH lookup.
+pub fn band_hashes(signature: &Signature) -> [i64; NUM_BANDS] {
+    let mut bands = [0i64; NUM_BANDS];
+    for (band, rows) in bands.iter_mut().zip(signature.chunks(ROWS_PER_BAND)) {
+        let bytes: Vec<u8> = rows.iter().flat_map(|row| row.to_le_bytes()).collect();
+        *band = fnv1a(&bytes) as i64;
+    }
+    bands
+}
+
+/// Serializes a signature for storage.
+pub fn to_bytes(signature: &Signature) -> Vec<u8> {
+    signature
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Deserializes a stored signature, returning `None` if it has the wrong length.
+pub fn from_bytes(bytes: &[u8]) -> Option<Signature> {
+    if bytes.len() != NUM_HASHES * 4 {
+        return None;
+    }
+    let mut signature = [0u32; NUM_HASHES];
+    for (value, chunk) in signature.iter_mut().zip(bytes.chunks_exact(4)) {
+        *value = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    Some(signature)
+}
+
+/// 64-bit FNV-1a, used instead of the std hasher because signatures are persisted and
+/// must not change between Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// SplitMix64 finalizer, used to derive independent hash functions from one base hash.
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STORY: &str = "Title: Storm hits coast\nBody: A powerful storm made landfall on the southern coast early on Tuesday, \
+        bringing winds of up to 150 kilometers per hour and heavy rain to several towns. Authorities ordered the \
+        evacuation of low-lying areas and opened emergency shelters in schools and sports halls. Power was cut to \
+        tens of thousands of homes, and officials warned that flooding could continue through the rest of the week \
+        as rivers rise. Emergency services said they had rescued dozens of people trapped by rising water overnight.";
+
+    #[test]
+    fn test_syndicated_copy_is_near_duplicate() {
+        let copy = format!(
+            "{} Copyright 2025 The Associated Press. All rights reserved.",
+            STORY.replace("Title: Storm hits coast", "Title: Storm slams coast")
+        );
+        let original = minhash(STORY).unwrap();
+        let syndicated = minhash(&copy).unwrap();
+        let score = similarity(&original, &syndicated);
+        assert!(
+            score >= DEFAULT_NEAR_DUPLICATE_THRESHOLD,
+            "syndicated copy scored {}",
+            score
+        );
+
+        let unrelated = minhash(
+            "Title: Chip maker earnings\nBody: The company reported record quarterly revenue on Wednesday as demand \
+             for data center processors continued to grow, and raised its forecast for the full year. Shares rose in \
+             after-hours trading while analysts said supply constraints could limit growth into next year, citing \
+             longer lead times for advanced packaging and memory components from suppliers in Asia.",
+        )
+        .unwrap();
+        assert!(similarity(&original, &unrelated) < 0.2);
+        assert_eq!(from_bytes(&to_bytes(&original)), Some(original));
+    }
+
+    #[test]
+    fn test_short_text_is_not_fingerprinted() {
+        assert_eq!(
+            minhash("Title: Short\nBody: Too short to fingerprint."),
+            None
+        );
+    }
+}
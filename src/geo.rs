@@ -0,0 +1,216 @@
+//! Distances, impact radius bands and the offline gazetteer used to place threats and
+//! people on the map.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use tracing::{info, warn};
+
+const GAZETTEER_PATH_ENV: &str = "GAZETTEER_PATH";
+const THREAT_NEAR_RADIUS_FACTOR_ENV: &str = "THREAT_NEAR_RADIUS_FACTOR";
+const THREAT_NEAR_MIN_BUFFER_KM_ENV: &str = "THREAT_NEAR_MIN_BUFFER_KM";
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Great-circle distance between two points in kilometers.
+pub fn haversine_km(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// How a threat affects someone, by distance from its center.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Impact {
+    /// Within the threat's impact radius.
+    Direct,
+    /// Outside the impact radius but within the near band.
+    Near,
+}
+
+/// Radius bands of a threat: direct up to `radius_km`, near up to `near_radius_km`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RadiusBands {
+    pub radius_km: f64,
+    pub near_radius_km: f64,
+}
+
+impl RadiusBands {
+    /// Derives the near band from the impact radius: `THREAT_NEAR_RADIUS_FACTOR` times the
+    /// radius (default 3), but at least `THREAT_NEAR_MIN_BUFFER_KM` beyond it (default 50).
+    pub fn for_radius(radius_km: f64) -> Self {
+        let factor = env::var(THREAT_NEAR_RADIUS_FACTOR_ENV)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3.0);
+        let min_buffer_km = env::var(THREAT_NEAR_MIN_BUFFER_KM_ENV)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(50.0);
+        Self::with(radius_km, factor, min_buffer_km)
+    }
+
+    fn with(radius_km: f64, factor: f64, min_buffer_km: f64) -> Self {
+        let radius_km = radius_km.max(0.0);
+        RadiusBands {
+            radius_km,
+            near_radius_km: (radius_km * factor).max(radius_km + min_buffer_km),
+        }
+    }
+
+    /// Classifies a distance from the threat center.
+    pub fn classify(&self, distance_km: f64) -> Option<Impact> {
+        if distance_km <= self.radius_km {
+            Some(Impact::Direct)
+        } else if distance_km <= self.near_radius_km {
+            Some(Impact::Near)
+        } else {
+            None
+        }
+    }
+}
+
+/// A place from the gazetteer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GazetteerEntry {
+    pub name: String,
+    pub country_code: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub population: u64,
+}
+
+/// Offline place name lookup, loaded from a GeoNames dump such as `cities15000.txt`.
+#[derive(Debug, Default)]
+pub struct Gazetteer {
+    by_name: HashMap<String, Vec<GazetteerEntry>>,
+}
+
+impl Gazetteer {
+    /// Parses the tab-separated GeoNames format: name in column 2, ASCII name in column 3,
+    /// alternate names in column 4, latitude and longitude in columns 5 and 6, the country
+    /// code in column 9 and the population in column 15.
+    pub fn parse(data: &str) -> Self {
+        let mut by_name: HashMap<String, Vec<GazetteerEntry>> = HashMap::new();
+        for line in data.lines() {
+            let columns: Vec<&str> = line.split('\t').collect();
+            if columns.len() < 15 {
+                continue;
+            }
+            let (Ok(latitude), Ok(longitude)) = (columns[4].parse(), columns[5].parse()) else {
+                continue;
+            };
+            let entry = GazetteerEntry {
+                name: columns[1].to_string(),
+                country_code: columns[8].to_uppercase(),
+                latitude,
+                longitude,
+                population: columns[14].parse().unwrap_or(0),
+            };
+
+            let mut names: Vec<String> = [columns[1], columns[2]]
+                .into_iter()
+                .chain(columns[3].split(','))
+                .map(normalize_name)
+                .filter(|name| !name.is_empty())
+                .collect();
+            names.sort();
+            names.dedup();
+            for name in names {
+                by_name.entry(name).or_default().push(entry.clone());
+            }
+        }
+        Gazetteer { by_name }
+    }
+
+    /// Looks up a place by name, preferring matches in `country_code` (ISO 3166-1 alpha-2)
+    /// and then the most populous.
+    pub fn lookup(&self, name: &str, country_code: Option<&str>) -> Option<&GazetteerEntry> {
+        let candidates = self.by_name.get(&normalize_name(name))?;
+        let in_country = country_code.and_then(|code| {
+            candidates
+                .iter()
+                .filter(|entry| entry.country_code.eq_ignore_ascii_case(code))
+                .max_by_key(|entry| entry.population)
+        });
+        in_country.or_else(|| candidates.iter().max_by_key(|entry| entry.population))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// The gazetteer named by `GAZETTEER_PATH`, loaded on first use. Empty if unset or unreadable.
+pub fn gazetteer() -> &'static Gazetteer {
+    static GAZETTEER: Lazy<Gazetteer> = Lazy::new(|| {
+        let Ok(path) = env::var(GAZETTEER_PATH_ENV) else {
+            return Gazetteer::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(data) => {
+                let gazetteer = Gazetteer::parse(&data);
+                info!(
+                    "Loaded gazetteer {} with {} names",
+                    path,
+                    gazetteer.by_name.len()
+                );
+                gazetteer
+            }
+            Err(e) => {
+                warn!("Failed to read gazetteer {}: {}", path, e);
+                Gazetteer::default()
+            }
+        }
+    });
+    &GAZETTEER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_haversine_and_radius_bands() {
+        let paris = (48.8566, 2.3522);
+        let london = (51.5074, -0.1278);
+        let distance = haversine_km(paris, london);
+        assert!((distance - 344.0).abs() < 5.0);
+
+        let bands = RadiusBands::with(100.0, 3.0, 50.0);
+        assert_eq!(bands.classify(80.0), Some(Impact::Direct));
+        assert_eq!(bands.classify(250.0), Some(Impact::Near));
+        assert_eq!(bands.classify(distance), None);
+        assert_eq!(RadiusBands::with(5.0, 3.0, 50.0).near_radius_km, 55.0);
+    }
+
+    #[test]
+    fn test_gazetteer_lookup_prefers_country_then_population() {
+        let data = "3176959\tFlorence\tFlorence\tFirenze,Florenz\t43.77925\t11.24626\tP\tPPLA\tIT\t\t16\tFI\t\t\t382258\n\
+                    4061234\tFlorence\tFlorence\t\t34.79981\t-87.67725\tP\tPPLA2\tUS\t\tAL\t077\t\t\t40184\n";
+        let gazetteer = Gazetteer::parse(data);
+        assert_eq!(
+            gazetteer.lookup("Firenze", None).unwrap().country_code,
+            "IT"
+        );
+        assert_eq!(
+            gazetteer.lookup("florence", None).unwrap().country_code,
+            "IT"
+        );
+        assert_eq!(
+            gazetteer
+                .lookup("Florence", Some("us"))
+                .unwrap()
+                .country_code,
+            "US"
+        );
+        assert!(gazetteer.lookup("Atlantis", None).is_none());
+    }
+}
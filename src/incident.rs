@@ -0,0 +1,393 @@
+//! Incident tracking for life safety alerts. Articles about the same emergency are linked
+//! to one incident, follow-up articles only notify people when the severity, area or
+//! advice changes, and an all-clear is sent when the incident ends.
+
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicI64, Ordering};
+use tracing::{error, info};
+
+use crate::alerts;
+use crate::app::util::send_to_app;
+use crate::db::core::Database;
+use crate::db::incident::Incident;
+use crate::geo::{haversine_km, RadiusBands};
+use crate::slack::send_slack_notice;
+use crate::threat::{ThreatResponse, ThreatSeverity};
+use crate::topics;
+use crate::TARGET_DB;
+
+/// Incidents without a known end are cleared after this many hours without news.
+pub const INCIDENT_QUIET_HOURS: i64 = 72;
+
+/// How often workers look for expired incidents.
+const EXPIRY_CHECK_INTERVAL_SECS: i64 = 60;
+
+/// A radius change by more than this factor counts as the area changing.
+const AREA_CHANGE_FACTOR: f64 = 1.25;
+
+/// A change in an incident that is worth telling people about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IncidentChange {
+    Escalated {
+        from: ThreatSeverity,
+        to: ThreatSeverity,
+    },
+    Deescalated {
+        from: ThreatSeverity,
+        to: ThreatSeverity,
+    },
+    AreaGrew,
+    AreaShrank,
+    AreaMoved,
+    AdviceChanged {
+        from: ThreatResponse,
+        to: ThreatResponse,
+    },
+    AllClear,
+}
+
+impl IncidentChange {
+    pub fn describe(&self) -> String {
+        match self {
+            IncidentChange::Escalated { from, to } => {
+                format!("severity raised from {} to {}", from.as_str(), to.as_str())
+            }
+            IncidentChange::Deescalated { from, to } => {
+                format!("severity lowered from {} to {}", from.as_str(), to.as_str())
+            }
+            IncidentChange::AreaGrew => "affected area grew".to_string(),
+            IncidentChange::AreaShrank => "affected area shrank".to_string(),
+            IncidentChange::AreaMoved => "affected area moved".to_string(),
+            IncidentChange::AdviceChanged { from, to } => format!(
+                "advice changed from {} to {}",
+                from.as_str(),
+                to.as_str().replace('_', " ")
+            ),
+            IncidentChange::AllClear => "all clear".to_string(),
+        }
+    }
+}
+
+/// What tracking an article did to its incident.
+#[derive(Debug, Clone)]
+pub struct IncidentUpdate {
+    pub incident: Incident,
+    pub is_new: bool,
+    pub changes: Vec<IncidentChange>,
+}
+
+impl IncidentUpdate {
+    /// New incidents and material changes are notified; repeated reports are not.
+    pub fn should_notify(&self) -> bool {
+        self.is_new || !self.changes.is_empty()
+    }
+
+    pub fn is_all_clear(&self) -> bool {
+        self.changes.contains(&IncidentChange::AllClear)
+    }
+
+    pub fn describe_changes(&self) -> String {
+        self.changes
+            .iter()
+            .map(IncidentChange::describe)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "id": self.incident.id,
+            "status": if self.is_all_clear() { "cleared" } else { "active" },
+            "is_new": self.is_new,
+            "changes": self.changes.iter().map(IncidentChange::describe).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Whether an observed threat is the same emergency as an existing incident: the articles
+/// were clustered together, or the threats are of the same kind and their near bands
+/// overlap.
+pub fn same_incident(incident: &Incident, observed: &Incident) -> bool {
+    if let (Some(a), Some(b)) = (incident.cluster_id, observed.cluster_id) {
+        if a == b {
+            return true;
+        }
+    }
+    if incident.category != observed.category {
+        return false;
+    }
+    match (incident.area, observed.area) {
+        (Some((lat1, lon1, r1)), Some((lat2, lon2, r2))) => {
+            let reach = RadiusBands::for_radius(r1)
+                .near_radius_km
+                .max(RadiusBands::for_radius(r2).near_radius_km);
+            haversine_km((lat1, lon1), (lat2, lon2)) <= reach
+        }
+        _ => false,
+    }
+}
+
+/// The material changes from an incident's current state to what a new article reports.
+/// Details the article doesn't give (unknown severity, no advice, no area) don't count as
+/// changes.
+pub fn incident_changes(current: &Incident, observed: &Incident) -> Vec<IncidentChange> {
+    if observed.response == ThreatResponse::AllClear {
+        return vec![IncidentChange::AllClear];
+    }
+
+    let mut changes = Vec::new();
+    let (from, to) = (current.severity, observed.severity);
+    if to != ThreatSeverity::Unknown {
+        if to.rank() > from.rank() {
+            changes.push(IncidentChange::Escalated { from, to });
+        } else if to.rank() < from.rank() {
+            changes.push(IncidentChange::Deescalated { from, to });
+        }
+    }
+
+    if let (Some((lat1, lon1, r1)), Some((lat2, lon2, r2))) = (current.area, observed.area) {
+        if r2 > r1 * AREA_CHANGE_FACTOR {
+            changes.push(IncidentChange::AreaGrew);
+        } else if r2 * AREA_CHANGE_FACTOR < r1 {
+            changes.push(IncidentChange::AreaShrank);
+        }
+        if haversine_km((lat1, lon1), (lat2, lon2)) > (r1 / 2.0).max(5.0) {
+            changes.push(IncidentChange::AreaMoved);
+        }
+    }
+
+    let (from, to) = (current.response, observed.response);
+    if to != ThreatResponse::None && to != from {
+        changes.push(IncidentChange::AdviceChanged { from, to });
+    }
+
+    changes
+}
+
+/// Links a life safety article to the incident it reports on, opening a new incident if
+/// none matches, and works out whether people should hear about it.
+///
+/// Returns `None` for an all-clear that matches no active incident, as there is nothing
+/// to clear.
+pub async fn track_incident(
+    db: &Database,
+    article_id: i64,
+    mut observed: Incident,
+) -> Result<Option<IncidentUpdate>, sqlx::Error> {
+    observed.cluster_id = db.get_article_cluster_id(article_id).await?;
+
+    let active = db.fetch_active_incidents().await?;
+    let update = match active
+        .into_iter()
+        .find(|incident| same_incident(incident, &observed))
+    {
+        Some(current) => {
+            let changes = incident_changes(&current, &observed);
+            let incident = Incident {
+                id: current.id,
+                title: observed.title,
+                topic: current.topic.clone(),
+                category: current.category,
+                severity: if observed.severity == ThreatSeverity::Unknown {
+                    current.severity
+                } else {
+                    observed.severity
+                },
+                response: if observed.response == ThreatResponse::None {
+                    current.response
+                } else {
+                    observed.response
+                },
+                area: observed.area.or(current.area),
+                cluster_id: observed.cluster_id.or(current.cluster_id),
+                expires_at: observed.expires_at,
+            };
+            if changes.contains(&IncidentChange::AllClear) {
+                // Another worker may have cleared it already, in which case it has sent
+                // the all-clear
+                if !db.clear_incident(incident.id).await? {
+                    return Ok(None);
+                }
+            } else {
+                db.update_incident(&incident).await?;
+            }
+            IncidentUpdate {
+                incident,
+                is_new: false,
+                changes,
+            }
+        }
+        None if observed.response == ThreatResponse::AllClear => return Ok(None),
+        None => {
+            observed.id = db.create_incident(&observed).await?;
+            IncidentUpdate {
+                incident: observed,
+                is_new: true,
+                changes: Vec::new(),
+            }
+        }
+    };
+
+    db.link_article_to_incident(
+        update.incident.id,
+        article_id,
+        &update.describe_changes(),
+        update.should_notify(),
+    )
+    .await?;
+    info!(target: TARGET_DB, "Linked article {} to incident {} (new: {}, changes: {:?})", article_id, update.incident.id, update.is_new, update.changes);
+
+    Ok(Some(update))
+}
+
+/// Tells Slack and app subscribers that an incident is over.
+pub async fn send_all_clear(
+    db: &Database,
+    incident: &Incident,
+    reason: &str,
+    slack_token: &str,
+    default_channel: &str,
+) {
+    let channel = topics::get(&incident.topic)
+        .and_then(|topic| topic.slack_channel.as_deref())
+        .unwrap_or(default_channel);
+    send_slack_notice(
+        &format!(
+            ":white_check_mark: *All clear:* {}\n{}",
+            incident.title, reason
+        ),
+        slack_token,
+        channel,
+    )
+    .await;
+
+    let notice = json!({
+        "topic": incident.topic,
+        "title": incident.title,
+        "tiny_title": format!("All clear: {}", incident.title),
+        "tiny_summary": reason,
+        "pub_date": Utc::now().format("%Y-%m-%d").to_string(),
+        "threat": { "urgency": "routine" },
+        "incident": { "id": incident.id, "status": "cleared" },
+    });
+    if send_to_app(&notice, None).await.is_none() {
+        error!(target: TARGET_DB, "Failed to send all-clear for incident {} to app", incident.id);
+    }
+
+    alerts::queue_all_clear(
+        db,
+        incident.id,
+        &format!("All clear: {}", incident.title),
+        reason,
+    )
+    .await;
+}
+
+/// Clears incidents that have passed their expected end without further news, sending
+/// an all-clear for each. Checks at most once a minute however often it is called.
+pub async fn clear_expired_incidents(db: &Database, slack_token: &str, slack_channel: &str) {
+    static LAST_CHECK: AtomicI64 = AtomicI64::new(0);
+    let now = Utc::now();
+    let last_check = LAST_CHECK.load(Ordering::Relaxed);
+    if now.timestamp() - last_check < EXPIRY_CHECK_INTERVAL_SECS
+        || LAST_CHECK
+            .compare_exchange(
+                last_check,
+                now.timestamp(),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .is_err()
+    {
+        return;
+    }
+
+    let incidents = match db.fetch_active_incidents().await {
+        Ok(incidents) => incidents,
+        Err(e) => {
+            error!(target: TARGET_DB, "Failed to fetch active incidents: {:?}", e);
+            return;
+        }
+    };
+    for incident in incidents.iter().filter(|i| i.expires_at < now) {
+        match db.clear_incident(incident.id).await {
+            Ok(true) => {
+                info!(target: TARGET_DB, "Incident {} expired at {}, sending all-clear", incident.id, incident.expires_at);
+                send_all_clear(
+                    db,
+                    incident,
+                    "There have been no new reports and the threat is expected to have passed.",
+                    slack_token,
+                    slack_channel,
+                )
+                .await;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                error!(target: TARGET_DB, "Failed to clear incident {}: {:?}", incident.id, e)
+            }
+        }
+    }
+}
+
+/// When an incident should be considered over if nothing more is heard.
+pub fn default_expiry() -> chrono::DateTime<Utc> {
+    Utc::now() + Duration::hours(INCIDENT_QUIET_HOURS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::threat::ThreatCategory;
+
+    fn incident(severity: ThreatSeverity, response: ThreatResponse, radius_km: f64) -> Incident {
+        Incident {
+            id: 1,
+            title: "Chemical plant fire".to_string(),
+            topic: "Alert: Direct".to_string(),
+            category: ThreatCategory::Hazmat,
+            severity,
+            response,
+            area: Some((45.76, 4.84, radius_km)),
+            cluster_id: None,
+            expires_at: default_expiry(),
+        }
+    }
+
+    #[test]
+    fn test_incident_matching_and_changes() {
+        let current = incident(ThreatSeverity::Severe, ThreatResponse::Shelter, 5.0);
+
+        // A repeat report of the same emergency changes nothing
+        let repeat = incident(ThreatSeverity::Unknown, ThreatResponse::None, 5.5);
+        assert!(same_incident(&current, &repeat));
+        assert!(incident_changes(&current, &repeat).is_empty());
+
+        let mut elsewhere = repeat.clone();
+        elsewhere.area = Some((48.85, 2.35, 5.0));
+        assert!(!same_incident(&current, &elsewhere));
+
+        let evacuate = incident(ThreatSeverity::Extreme, ThreatResponse::Evacuate, 20.0);
+        assert_eq!(
+            incident_changes(&current, &evacuate),
+            vec![
+                IncidentChange::Escalated {
+                    from: ThreatSeverity::Severe,
+                    to: ThreatSeverity::Extreme
+                },
+                IncidentChange::AreaGrew,
+                IncidentChange::AdviceChanged {
+                    from: ThreatResponse::Shelter,
+                    to: ThreatResponse::Evacuate
+                },
+            ]
+        );
+
+        let all_clear = incident(ThreatSeverity::Minor, ThreatResponse::AllClear, 5.0);
+        assert_eq!(
+            incident_changes(&current, &all_clear),
+            vec![IncidentChange::AllClear]
+        );
+    }
+}
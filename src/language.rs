@@ -0,0 +1,142 @@
+use std::env;
+use whatlang::{detect, Lang};
+
+const TRANSLATE_ARTICLES_ENV: &str = "TRANSLATE_ARTICLES";
+const OUTPUT_LANGUAGE_ENV: &str = "OUTPUT_LANGUAGE";
+
+/// The language decision prompts are written in, and that articles are translated into.
+pub const WORKING_LANGUAGE: &str = "en";
+
+/// Detects the language of `text`, returning an ISO 639-1 code such as `"en"` or `"fr"`.
+///
+/// Returns `None` when whatlang is not confident or the language has no two-letter code
+/// in our mapping; callers should treat that as "unknown" rather than "not English".
+pub fn detect_language(text: &str) -> Option<String> {
+    let info = detect(text)?;
+    if !info.is_reliable() {
+        return None;
+    }
+    iso_639_1(info.lang()).map(str::to_string)
+}
+
+/// Returns true if `code` is one of `languages`, or if `languages` is empty.
+///
+/// An unknown language (`None`) is accepted so that detection failures never hide an
+/// article from a topic.
+pub fn language_allowed(languages: &[String], code: Option<&str>) -> bool {
+    match code {
+        Some(code) if !languages.is_empty() => {
+            languages.iter().any(|l| l.eq_ignore_ascii_case(code))
+        }
+        _ => true,
+    }
+}
+
+/// Whether non-English articles are translated before the decision prompts
+/// (`TRANSLATE_ARTICLES=true`).
+pub fn translation_enabled() -> bool {
+    env::var(TRANSLATE_ARTICLES_ENV)
+        .map(|v| v.to_lowercase() == "true")
+        .unwrap_or(false)
+}
+
+/// Whether an article in language `code` should be translated.
+pub fn needs_translation(code: Option<&str>) -> bool {
+    translation_enabled() && code.is_some_and(|code| !code.eq_ignore_ascii_case(WORKING_LANGUAGE))
+}
+
+/// The ISO 639-1 code generated text should be written in (`OUTPUT_LANGUAGE`, default `en`).
+pub fn output_language() -> String {
+    env::var(OUTPUT_LANGUAGE_ENV)
+        .ok()
+        .map(|code| code.trim().to_lowercase())
+        .filter(|code| !code.is_empty())
+        .unwrap_or_else(|| WORKING_LANGUAGE.to_string())
+}
+
+/// Returns the English name of a language code for use in prompts, or the code itself
+/// if it is not in our mapping.
+pub fn language_name(code: &str) -> String {
+    let name = match code.to_lowercase().as_str() {
+        "en" => "English",
+        "fr" => "French",
+        "de" => "German",
+        "es" => "Spanish",
+        "it" => "Italian",
+        "pt" => "Portuguese",
+        "nl" => "Dutch",
+        "sv" => "Swedish",
+        "da" => "Danish",
+        "no" => "Norwegian",
+        "fi" => "Finnish",
+        "pl" => "Polish",
+        "cs" => "Czech",
+        "ro" => "Romanian",
+        "hu" => "Hungarian",
+        "el" => "Greek",
+        "tr" => "Turkish",
+        "ru" => "Russian",
+        "uk" => "Ukrainian",
+        "ar" => "Arabic",
+        "he" => "Hebrew",
+        "hi" => "Hindi",
+        "zh" => "Chinese",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        _ => return code.to_string(),
+    };
+    name.to_string()
+}
+
+/// Maps a whatlang language to its ISO 639-1 code.
+fn iso_639_1(lang: Lang) -> Option<&'static str> {
+    let code = match lang {
+        Lang::Eng => "en",
+        Lang::Fra => "fr",
+        Lang::Deu => "de",
+        Lang::Spa => "es",
+        Lang::Ita => "it",
+        Lang::Por => "pt",
+        Lang::Nld => "nl",
+        Lang::Swe => "sv",
+        Lang::Dan => "da",
+        Lang::Nob => "no",
+        Lang::Fin => "fi",
+        Lang::Pol => "pl",
+        Lang::Ces => "cs",
+        Lang::Ron => "ro",
+        Lang::Hun => "hu",
+        Lang::Ell => "el",
+        Lang::Tur => "tr",
+        Lang::Rus => "ru",
+        Lang::Ukr => "uk",
+        Lang::Ara => "ar",
+        Lang::Heb => "he",
+        Lang::Hin => "hi",
+        Lang::Cmn => "zh",
+        Lang::Jpn => "ja",
+        Lang::Kor => "ko",
+        _ => return None,
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language("Le gouvernement a annoncé mardi une nouvelle réforme des retraites qui sera présentée au Parlement la semaine prochaine.").as_deref(),
+            Some("fr")
+        );
+        assert_eq!(
+            detect_language("Die Bundesregierung hat am Dienstag ein neues Gesetz zur Förderung erneuerbarer Energien beschlossen.").as_deref(),
+            Some("de")
+        );
+        assert!(language_allowed(&["en".to_string()], None));
+        assert!(!language_allowed(&["en".to_string()], Some("es")));
+        assert_eq!(language_name("es"), "Spanish");
+    }
+}
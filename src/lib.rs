@@ -5,16 +5,30 @@ pub use workers::analysis::worker_loop as analysis_worker;
 pub use workers::decision::worker_loop as decision_worker;
 
 pub mod app {
+    pub mod admin;
     pub mod api;
+    pub mod auth;
+    pub mod push;
     pub mod util;
 }
+pub mod alerts;
 pub mod clustering;
 pub mod db; // Now uses the directory module structure
+pub mod digest;
+pub mod email;
 pub mod entity;
 pub mod environment;
+pub mod events;
+pub mod feedback;
+pub mod fingerprint;
+pub mod geo;
+pub mod incident;
+pub mod language;
 pub mod llm;
 pub mod logging;
 pub mod metrics;
+pub mod notifier;
+pub mod places;
 pub mod prompt; // Now uses the directory module structure (replacing prompts.rs)
                 // Import the modular RSS structure
 pub mod rss;
@@ -23,7 +37,11 @@ pub mod rss;
 pub use rss::process_rss_urls;
 pub use rss::rss_loop;
 pub use rss::test_rss_feed;
+pub mod search;
 pub mod slack;
+pub mod threat;
+pub mod throttle;
+pub mod topics;
 pub mod util;
 pub mod vector;
 
@@ -58,6 +76,9 @@ pub enum JsonSchemaType {
     /// For threat location: returns impacted_regions array
     ThreatLocation,
 
+    /// For threat geolocation: returns threat_areas array with coordinates and radius
+    ThreatGeolocation,
+
     /// Generic JSON response without schema enforcement
     Generic,
 }
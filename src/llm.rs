@@ -7,11 +7,13 @@ use ollama_rs::generation::{
 use regex::Regex;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::{sleep, timeout};
 use tracing::{debug, error, info, warn};
 use unicode_segmentation::UnicodeSegmentation;
 
+use crate::metrics;
+use crate::threat::ThreatDetails;
 use crate::TARGET_LLM_REQUEST;
 use crate::{JsonSchemaType, LLMClient, LLMParams, WorkerDetail};
 
@@ -21,6 +23,8 @@ const CONTEXT_WINDOW: u32 = 8192;
 #[derive(Serialize, Deserialize, Debug, JsonSchema)]
 pub struct ThreatLocationResponse {
     pub impacted_regions: Vec<ImpactedRegion>,
+    #[serde(flatten)]
+    pub details: ThreatDetails,
 }
 
 #[derive(Serialize, Deserialize, Debug, JsonSchema)]
@@ -30,6 +34,24 @@ pub struct ImpactedRegion {
     pub region: Option<String>,
 }
 
+// Response schema for threat geolocation
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+pub struct ThreatGeolocationResponse {
+    pub threat_areas: Vec<ThreatArea>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+pub struct ThreatArea {
+    /// Most specific place name at the center of the threat, e.g. a city
+    pub place: String,
+    /// ISO 3166-1 alpha-2 country code
+    pub country_code: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Distance from the center within which people are directly at risk
+    pub radius_km: f64,
+}
+
 // Response schema for entity extraction
 #[derive(Serialize, Deserialize, Debug, JsonSchema)]
 pub struct EntityExtractionResponse {
@@ -99,11 +121,15 @@ fn estimate_token_count(text: &str) -> u32 {
         .unwrap()
 }
 
+/// Sends a prompt to the LLM, retrying with exponential backoff. `kind` names the prompt
+/// in the latency metrics, e.g. `summary`.
 pub async fn generate_llm_response(
     prompt: &str,
     params: &LLMParams,
     worker_detail: &WorkerDetail,
+    kind: &str,
 ) -> Option<String> {
+    let started = Instant::now();
     let max_retries = 5;
     let mut response_text = String::new();
     let mut backoff = 2;
@@ -163,6 +189,13 @@ pub async fn generate_llm_response(
                                 >(
                                 )));
                         }
+                        JsonSchemaType::ThreatGeolocation => {
+                            request.format =
+                                Some(FormatType::StructuredJson(JsonStructure::new::<
+                                    ThreatGeolocationResponse,
+                                >(
+                                )));
+                        }
                         JsonSchemaType::Generic => {
                             request.format = Some(FormatType::Json);
                         }
@@ -461,6 +494,7 @@ pub async fn generate_llm_response(
         }
     }
 
+    metrics::record_llm_request(&params.model, kind, started, !response_text.is_empty());
     if response_text.is_empty() {
         error!(
             target: TARGET_LLM_REQUEST,
@@ -21,9 +21,12 @@ const USE_REASONING_MODELS_ENV: &str = "USE_REASONING_MODELS";
 use argus::analysis_worker;
 use argus::app::api;
 use argus::decision_worker;
+use argus::digest::{self, DigestWriter};
 use argus::environment;
 use argus::logging;
+use argus::notifier;
 use argus::rss;
+use argus::throttle;
 use argus::{
     FallbackConfig, LLMClient, ThinkingModelConfig, START_TIME, TARGET_LLM_REQUEST,
     TARGET_WEB_REQUEST,
@@ -269,7 +272,8 @@ async fn main() -> Result<()> {
             .count();
 
     let urls = get_env_var_as_vec("URLS", ';');
-    let topics = get_env_var_as_vec("TOPICS", ';');
+    let topics = argus::topics::all().to_vec();
+    notifier::init_router();
     let slack_token = env::var(SLACK_TOKEN_ENV).expect("SLACK_TOKEN environment variable required");
     let slack_channel =
         env::var(SLACK_CHANNEL_ENV).expect("SLACK_CHANNEL environment variable required");
@@ -316,6 +320,47 @@ async fn main() -> Result<()> {
         }
     });
 
+    // Spawn the digest scheduler, writing overviews with the first analysis worker's model
+    let digest_writer = analysis_workers.first().map(|worker| DigestWriter {
+        llm_client: worker.llm_client.clone(),
+        model: worker.model.clone(),
+    });
+    let digest_slack_token = slack_token.clone();
+    let digest_notify = Arc::clone(&panic_notify);
+    let digest_handle = tokio::spawn(async move {
+        let thread_name = "Digest Scheduler".to_string();
+        info!("{}: Starting digest scheduler (digest_loop).", thread_name);
+        match digest::digest_loop(digest_writer, digest_slack_token).await {
+            Ok(_) => {
+                info!("{}: digest_loop completed successfully.", thread_name)
+            }
+            Err(e) => {
+                error!("{}: digest_loop failed: {}", thread_name, e);
+                digest_notify.notify_one();
+            }
+        }
+    });
+
+    // Spawn the sender of notifications held back by rate limits
+    let batch_slack_token = slack_token.clone();
+    let batch_notify = Arc::clone(&panic_notify);
+    let batch_handle = tokio::spawn(async move {
+        let thread_name = "Notification Batcher".to_string();
+        info!(
+            "{}: Starting notification batcher (batch_loop).",
+            thread_name
+        );
+        match throttle::batch_loop(batch_slack_token).await {
+            Ok(_) => {
+                info!("{}: batch_loop completed successfully.", thread_name)
+            }
+            Err(e) => {
+                error!("{}: batch_loop failed: {}", thread_name, e);
+                batch_notify.notify_one();
+            }
+        }
+    });
+
     // Launch DECISION workers
     let mut decision_handles = Vec::new();
     for (decision_id, llm_client, decision_model, no_think) in
@@ -461,5 +506,15 @@ async fn main() -> Result<()> {
         error!(target: TARGET_WEB_REQUEST, "RSS task (rss_loop) encountered an error: {}", e);
     }
 
+    // Await digest_loop completion
+    if let Err(e) = digest_handle.await {
+        error!("Digest task (digest_loop) encountered an error: {}", e);
+    }
+
+    // Await batch_loop completion
+    if let Err(e) = batch_handle.await {
+        error!("Batch task (batch_loop) encountered an error: {}", e);
+    }
+
     Ok(())
 }
@@ -1,7 +1,16 @@
+use crate::db::core::Database;
 use crate::START_TIME;
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, Encoder, Histogram, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec,
+    TextEncoder,
+};
+use std::future::Future;
 use std::sync::atomic::Ordering;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System}; // Updated imports
+use tracing::warn;
 
 pub struct SystemInfo {
     pub memory_usage: u64,
@@ -48,3 +57,234 @@ impl SystemInfo {
         }
     }
 }
+
+/// LLM requests take seconds to minutes, including retries.
+const LLM_BUCKETS: &[f64] = &[
+    0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0, 600.0,
+];
+
+static ARTICLES_FETCHED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "argus_articles_fetched_total",
+        "New articles queued from RSS feeds, by feed",
+        &["feed"]
+    )
+    .expect("Failed to register argus_articles_fetched_total")
+});
+
+static ARTICLES_DECIDED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "argus_articles_decided_total",
+        "Articles decided on, by primary topic: life safety, a matched topic or none",
+        &["topic"]
+    )
+    .expect("Failed to register argus_articles_decided_total")
+});
+
+static ARTICLES_ANALYZED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "argus_articles_analyzed_total",
+        "Articles analyzed and stored, by topic",
+        &["topic"]
+    )
+    .expect("Failed to register argus_articles_analyzed_total")
+});
+
+static LLM_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "argus_llm_requests_total",
+        "LLM prompts by model, prompt kind and outcome after retries",
+        &["model", "kind", "outcome"]
+    )
+    .expect("Failed to register argus_llm_requests_total")
+});
+
+static LLM_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "argus_llm_request_duration_seconds",
+        "Time to answer an LLM prompt, including retries",
+        &["model", "kind"],
+        LLM_BUCKETS.to_vec()
+    )
+    .expect("Failed to register argus_llm_request_duration_seconds")
+});
+
+static QDRANT_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "argus_qdrant_request_duration_seconds",
+        "Time taken by Qdrant requests, by operation",
+        &["operation"]
+    )
+    .expect("Failed to register argus_qdrant_request_duration_seconds")
+});
+
+static EMBEDDING_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "argus_embedding_duration_seconds",
+        "Time taken to compute an E5 embedding"
+    )
+    .expect("Failed to register argus_embedding_duration_seconds")
+});
+
+static NOTIFICATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "argus_notifications_total",
+        "Notifications by backend and outcome after retries",
+        &["backend", "outcome"]
+    )
+    .expect("Failed to register argus_notifications_total")
+});
+
+static QUEUE_DEPTH: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "argus_queue_depth",
+        "Entries waiting in each queue",
+        &["queue"]
+    )
+    .expect("Failed to register argus_queue_depth")
+});
+
+static CLUSTERS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!("argus_clusters", "Story clusters by status", &["status"])
+        .expect("Failed to register argus_clusters")
+});
+
+static UPTIME: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("argus_uptime_seconds", "Seconds since Argus started")
+        .expect("Failed to register argus_uptime_seconds")
+});
+
+static MEMORY_USED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("argus_memory_used_kb", "Memory used on the host in KB")
+        .expect("Failed to register argus_memory_used_kb")
+});
+
+fn outcome(success: bool) -> &'static str {
+    if success {
+        "success"
+    } else {
+        "failure"
+    }
+}
+
+/// Counts new articles queued from a feed.
+pub fn record_articles_fetched(feed: &str, count: u64) {
+    ARTICLES_FETCHED.with_label_values(&[feed]).inc_by(count);
+}
+
+/// Counts an article decided on, by its primary topic.
+pub fn record_article_decided(topic: &str) {
+    ARTICLES_DECIDED.with_label_values(&[topic]).inc();
+}
+
+/// Counts an article analyzed and stored.
+pub fn record_article_analyzed(topic: &str) {
+    ARTICLES_ANALYZED.with_label_values(&[topic]).inc();
+}
+
+/// Records how long an LLM prompt took and whether it was answered.
+pub fn record_llm_request(model: &str, kind: &str, started: Instant, success: bool) {
+    LLM_DURATION
+        .with_label_values(&[model, kind])
+        .observe(started.elapsed().as_secs_f64());
+    LLM_REQUESTS
+        .with_label_values(&[model, kind, outcome(success)])
+        .inc();
+}
+
+/// Counts a notification that a backend accepted or that was given up on.
+pub fn record_notification(backend: &str, delivered: bool) {
+    NOTIFICATIONS
+        .with_label_values(&[backend, outcome(delivered)])
+        .inc();
+}
+
+/// Awaits a Qdrant request, recording how long it took.
+pub async fn time_qdrant<F: Future>(operation: &str, request: F) -> F::Output {
+    let started = Instant::now();
+    let output = request.await;
+    QDRANT_DURATION
+        .with_label_values(&[operation])
+        .observe(started.elapsed().as_secs_f64());
+    output
+}
+
+/// Awaits an embedding, recording how long it took.
+pub async fn time_embedding<F: Future>(embedding: F) -> F::Output {
+    let started = Instant::now();
+    let output = embedding.await;
+    EMBEDDING_DURATION.observe(started.elapsed().as_secs_f64());
+    output
+}
+
+/// Refreshes the queue, cluster and process gauges from the database and the system.
+async fn update_gauges(db: &Database) {
+    match db.queue_depths().await {
+        Ok(queues) => {
+            for (queue, depth) in [
+                ("rss", queues.rss_queue),
+                ("matched_topics", queues.matched_topics_queue),
+                ("life_safety", queues.life_safety_queue),
+                ("notification_batches", queues.notification_batches),
+                ("pending_alert_deliveries", queues.pending_alert_deliveries),
+            ] {
+                QUEUE_DEPTH.with_label_values(&[queue]).set(depth);
+            }
+        }
+        Err(e) => warn!("Failed to count queues for metrics: {:?}", e),
+    }
+
+    match db.cluster_counts().await {
+        Ok(counts) => {
+            CLUSTERS.reset();
+            for (status, count) in counts {
+                CLUSTERS.with_label_values(&[&status]).set(count);
+            }
+        }
+        Err(e) => warn!("Failed to count clusters for metrics: {:?}", e),
+    }
+
+    let sys_info = SystemInfo::collect();
+    UPTIME.set(sys_info.uptime as i64);
+    MEMORY_USED.set(sys_info.memory_usage as i64);
+}
+
+/// Encodes every registered metric in the Prometheus text format.
+fn encode() -> String {
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        warn!("Failed to encode metrics: {:?}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Returns the current metrics in the Prometheus text format, for `/metrics`.
+pub async fn render(db: &Database) -> String {
+    update_gauges(db).await;
+    encode()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode() {
+        record_article_decided("Rust");
+        record_llm_request("llama3", "summary", Instant::now(), true);
+        record_notification("slack", false);
+        assert_eq!(time_qdrant("search", async { 42 }).await, 42);
+
+        let text = encode();
+        assert!(text.contains("# TYPE argus_articles_decided_total counter"));
+        assert!(text.contains("argus_articles_decided_total{topic=\"Rust\"}"));
+        assert!(text.contains(
+            "argus_llm_request_duration_seconds_count{kind=\"summary\",model=\"llama3\"} "
+        ));
+        assert!(text.contains(
+            "argus_llm_requests_total{kind=\"summary\",model=\"llama3\",outcome=\"success\"}"
+        ));
+        assert!(text.contains("argus_notifications_total{backend=\"slack\",outcome=\"failure\"}"));
+        assert!(text.contains("argus_qdrant_request_duration_seconds_bucket{operation=\"search\""));
+    }
+}
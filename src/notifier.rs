@@ -0,0 +1,638 @@
+//! Routing of finished analyses to notification backends.
+//!
+//! Each backend implements [`Notifier`]. Which backends an analysis goes to is decided by
+//! the routes in the JSON file named by `NOTIFIERS_JSON_PATH`, matched on topic and threat
+//! severity. Without a configuration, or when no route matches, analyses go to the app and
+//! Slack. Every backend retries on its own terms and every outcome is logged in the
+//! `notification_deliveries` table.
+
+use chrono::Utc;
+use futures::future::{join_all, BoxFuture};
+use once_cell::sync::Lazy;
+use ring::hmac;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::future::Future;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info, warn};
+use url::Url;
+use uuid::Uuid;
+
+use crate::app::push::Pusher;
+use crate::app::util::{push_to_app, upload_to_r2};
+use crate::db::core::Database;
+use crate::db::notifications::NotificationDelivery;
+use crate::email::send_email;
+use crate::metrics;
+use crate::slack::send_to_slack;
+use crate::threat::ThreatSeverity;
+use crate::util::escape_html;
+use crate::TARGET_WEB_REQUEST;
+
+const NOTIFIERS_JSON_PATH_ENV: &str = "NOTIFIERS_JSON_PATH";
+
+/// Name of the built-in backend that uploads the analysis to R2 and pushes it to devices.
+pub const APP_BACKEND: &str = "app";
+/// Name of the built-in backend that posts to each topic's Slack channel.
+pub const SLACK_BACKEND: &str = "slack";
+
+/// A finished analysis ready to be sent out.
+#[derive(Debug, Clone)]
+pub struct Notification<'a> {
+    pub topics: Vec<String>,
+    /// Threat severity for life safety alerts, unknown for everything else
+    pub severity: ThreatSeverity,
+    pub article_url: &'a str,
+    /// The story cluster the article was assigned to, if any
+    pub cluster_id: Option<i64>,
+    /// Slack header line, e.g. "*<url|title>*"
+    pub headline: String,
+    /// The full analysis JSON
+    pub analysis: &'a Value,
+}
+
+impl Notification<'_> {
+    fn field(&self, name: &str) -> &str {
+        self.analysis[name].as_str().unwrap_or("")
+    }
+}
+
+/// What a backend hands back on success, e.g. the R2 URL of the analysis.
+pub type Receipt = Option<String>;
+
+/// How many times a backend is tried and how long it waits between tries. The wait
+/// doubles after each failure.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 3,
+            initial_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+/// A place analyses can be delivered to.
+pub trait Notifier: Send + Sync {
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    fn send<'a>(
+        &'a self,
+        notification: &'a Notification<'a>,
+    ) -> BoxFuture<'a, Result<Receipt, String>>;
+}
+
+/// Uploads the analysis to R2, records its R2 URL with the article, and pushes it to
+/// subscribed devices if push notifications are configured.
+pub struct AppNotifier;
+
+impl AppNotifier {
+    /// How often the push step is tried. Devices are all looked up before any is pushed
+    /// to, so a retry doesn't push to a device twice.
+    const PUSH_RETRY_POLICY: RetryPolicy = RetryPolicy {
+        attempts: 2,
+        initial_backoff: Duration::from_secs(2),
+    };
+}
+
+impl Notifier for AppNotifier {
+    /// Only the push step is retried, so the analysis is uploaded once.
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn send<'a>(
+        &'a self,
+        notification: &'a Notification<'a>,
+    ) -> BoxFuture<'a, Result<Receipt, String>> {
+        Box::pin(async move {
+            let r2_url = upload_to_r2(notification.analysis)
+                .await
+                .ok_or_else(|| "Failed to upload the analysis to R2".to_string())?;
+            let db = Database::instance().await;
+            if let Err(e) = db
+                .update_article_with_r2_details(notification.article_url, &r2_url)
+                .await
+            {
+                error!(target: TARGET_WEB_REQUEST, "Failed to record R2 URL of {}: {:?}", notification.article_url, e);
+            }
+
+            let Some(pusher) = Pusher::shared() else {
+                return Ok(Some(r2_url));
+            };
+            let (result, _) = retry("app push", Self::PUSH_RETRY_POLICY, || {
+                push_to_app(
+                    db,
+                    pusher,
+                    notification.analysis,
+                    &r2_url,
+                    notification.cluster_id,
+                )
+            })
+            .await;
+            result.map(|()| Some(r2_url))
+        })
+    }
+}
+
+/// Posts the analysis to each topic's Slack channel, or `channel` for topics without one,
+/// threaded under the first post about the same story cluster.
+pub struct SlackNotifier {
+    pub token: String,
+    pub channel: String,
+}
+
+impl Notifier for SlackNotifier {
+    /// Slack messages are already retried one by one, so the whole post is tried once.
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn send<'a>(
+        &'a self,
+        notification: &'a Notification<'a>,
+    ) -> BoxFuture<'a, Result<Receipt, String>> {
+        Box::pin(async move {
+            let db = Database::instance().await;
+            if send_to_slack(
+                db,
+                &notification.headline,
+                notification.analysis,
+                notification.cluster_id,
+                &self.token,
+                &self.channel,
+            )
+            .await
+            {
+                Ok(None)
+            } else {
+                Err("Slack did not accept the message".to_string())
+            }
+        })
+    }
+}
+
+/// Emails the summary to a fixed list of recipients.
+pub struct EmailNotifier {
+    pub to: Vec<String>,
+}
+
+impl Notifier for EmailNotifier {
+    fn send<'a>(
+        &'a self,
+        notification: &'a Notification<'a>,
+    ) -> BoxFuture<'a, Result<Receipt, String>> {
+        Box::pin(async move {
+            let subject = format!(
+                "[{}] {}",
+                notification.topics.join(", "),
+                notification.field("tiny_title")
+            );
+            let body = format!(
+                "{}\n\n{}\n\n{}",
+                notification.field("tiny_summary"),
+                notification.field("summary"),
+                notification.article_url
+            );
+            for to in &self.to {
+                send_email(to, &subject, &body).await?;
+            }
+            Ok(None)
+        })
+    }
+}
+
+/// POSTs the analysis as JSON, signed with HMAC-SHA256 if a secret is set.
+///
+/// The signature covers `{timestamp}.{body}` and is sent as `X-Argus-Signature:
+/// sha256=<hex>`, with the timestamp in `X-Argus-Timestamp`.
+pub struct WebhookNotifier {
+    pub url: String,
+    pub secret: Option<String>,
+}
+
+impl Notifier for WebhookNotifier {
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            attempts: 4,
+            initial_backoff: Duration::from_secs(1),
+        }
+    }
+
+    fn send<'a>(
+        &'a self,
+        notification: &'a Notification<'a>,
+    ) -> BoxFuture<'a, Result<Receipt, String>> {
+        Box::pin(async move {
+            let body = json!({
+                "event": "analysis",
+                "topics": notification.topics,
+                "severity": notification.severity.as_str(),
+                "url": notification.article_url,
+                "analysis": notification.analysis,
+            })
+            .to_string();
+            let timestamp = Utc::now().timestamp().to_string();
+
+            let mut request = reqwest::Client::new()
+                .post(&self.url)
+                .header("Content-Type", "application/json")
+                .header("X-Argus-Timestamp", &timestamp)
+                .timeout(Duration::from_secs(10));
+            if let Some(secret) = &self.secret {
+                request = request.header(
+                    "X-Argus-Signature",
+                    format!("sha256={}", sign_webhook(secret, &timestamp, &body)),
+                );
+            }
+            let response = request.body(body).send().await.map_err(|e| e.to_string())?;
+            if response.status().is_success() {
+                Ok(None)
+            } else {
+                Err(format!("Webhook returned {}", response.status()))
+            }
+        })
+    }
+}
+
+/// Hex HMAC-SHA256 of `{timestamp}.{body}`, for receivers to verify webhooks with.
+pub fn sign_webhook(secret: &str, timestamp: &str, body: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = hmac::sign(&key, format!("{}.{}", timestamp, body).as_bytes());
+    tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Sends a message to a Matrix room through the client-server API.
+pub struct MatrixNotifier {
+    pub homeserver: String,
+    pub access_token: String,
+    pub room_id: String,
+}
+
+impl Notifier for MatrixNotifier {
+    fn send<'a>(
+        &'a self,
+        notification: &'a Notification<'a>,
+    ) -> BoxFuture<'a, Result<Receipt, String>> {
+        Box::pin(async move {
+            let mut url = Url::parse(&self.homeserver).map_err(|e| e.to_string())?;
+            url.path_segments_mut()
+                .map_err(|_| format!("Invalid homeserver URL {}", self.homeserver))?
+                .pop_if_empty()
+                .extend([
+                    "_matrix",
+                    "client",
+                    "v3",
+                    "rooms",
+                    &self.room_id,
+                    "send",
+                    "m.room.message",
+                    &Uuid::new_v4().to_string(),
+                ]);
+
+            let title = notification.field("tiny_title");
+            let summary = notification.field("tiny_summary");
+            let message = json!({
+                "msgtype": "m.text",
+                "body": format!("{}\n{}\n{}", title, summary, notification.article_url),
+                "format": "org.matrix.custom.html",
+                "formatted_body": format!(
+                    "<b>{}</b><br>{}<br><a href=\"{}\">{}</a>",
+                    escape_html(title),
+                    escape_html(summary),
+                    escape_html(notification.article_url),
+                    escape_html(notification.article_url)
+                ),
+            });
+            let response = reqwest::Client::new()
+                .put(url)
+                .bearer_auth(&self.access_token)
+                .json(&message)
+                .timeout(Duration::from_secs(10))
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("Matrix returned {}", response.status()));
+            }
+            let response: Value = response.json().await.map_err(|e| e.to_string())?;
+            Ok(response["event_id"].as_str().map(str::to_string))
+        })
+    }
+}
+
+/// A configured backend in `NOTIFIERS_JSON_PATH`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BackendConfig {
+    /// Slack, with its own channel for topics that don't set one
+    Slack {
+        channel: String,
+    },
+    Email {
+        to: Vec<String>,
+    },
+    Webhook {
+        url: String,
+        secret: Option<String>,
+    },
+    Matrix {
+        homeserver: String,
+        access_token: String,
+        room_id: String,
+    },
+}
+
+/// Sends analyses about any of `topics` (all topics if empty) with at least
+/// `min_severity` to `backends`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Route {
+    #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub min_severity: Option<ThreatSeverity>,
+    pub backends: Vec<String>,
+}
+
+impl Route {
+    fn matches(&self, notification: &Notification) -> bool {
+        (self.topics.is_empty() || notification.topics.iter().any(|t| self.topics.contains(t)))
+            && self
+                .min_severity
+                .is_none_or(|min| notification.severity.rank() >= min.rank())
+    }
+}
+
+/// The contents of `NOTIFIERS_JSON_PATH`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RouterConfig {
+    #[serde(default)]
+    pub backends: HashMap<String, BackendConfig>,
+    #[serde(default)]
+    pub routes: Vec<Route>,
+}
+
+impl RouterConfig {
+    /// Parses the configuration, checking that every route names a known backend.
+    pub fn parse(json_data: &str) -> Result<Self, String> {
+        let config: RouterConfig = serde_json::from_str(json_data)
+            .map_err(|err| format!("Failed to parse notifiers file: {}", err))?;
+        for route in &config.routes {
+            for backend in &route.backends {
+                if backend != APP_BACKEND
+                    && backend != SLACK_BACKEND
+                    && !config.backends.contains_key(backend)
+                {
+                    return Err(format!("Route uses unknown backend {}", backend));
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Reads and parses the configuration file at `path`.
+    pub fn load(path: &str) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read the notifiers file: {}", err))
+            .and_then(|json_data| RouterConfig::parse(&json_data))
+    }
+
+    /// Names of the backends a notification goes to, in route order without repeats.
+    pub fn backends_for(&self, notification: &Notification) -> Vec<&str> {
+        let mut backends: Vec<&str> = Vec::new();
+        for route in self
+            .routes
+            .iter()
+            .filter(|route| route.matches(notification))
+        {
+            for backend in &route.backends {
+                if !backends.contains(&backend.as_str()) {
+                    backends.push(backend);
+                }
+            }
+        }
+        if backends.is_empty() {
+            backends = vec![APP_BACKEND, SLACK_BACKEND];
+        }
+        backends
+    }
+}
+
+/// The router configuration, loaded once from `NOTIFIERS_JSON_PATH`.
+static ROUTER: Lazy<RouterConfig> = Lazy::new(|| {
+    let Ok(path) = env::var(NOTIFIERS_JSON_PATH_ENV) else {
+        return RouterConfig::default();
+    };
+    match RouterConfig::load(&path) {
+        Ok(config) => {
+            info!(
+                "Loaded {} notification backends and {} routes",
+                config.backends.len(),
+                config.routes.len()
+            );
+            config
+        }
+        Err(err) => panic!("Failed to load notifiers from {}: {}", path, err),
+    }
+});
+
+/// Loads the router configuration, so an unreadable or invalid notifiers file stops Argus
+/// at startup rather than falling back to the default routes.
+pub fn init_router() {
+    Lazy::force(&ROUTER);
+}
+
+fn build_notifier(
+    name: &str,
+    config: &RouterConfig,
+    slack_token: &str,
+    slack_channel: &str,
+) -> Option<Box<dyn Notifier>> {
+    let notifier: Box<dyn Notifier> = match name {
+        APP_BACKEND => Box::new(AppNotifier),
+        SLACK_BACKEND => Box::new(SlackNotifier {
+            token: slack_token.to_string(),
+            channel: slack_channel.to_string(),
+        }),
+        _ => match config.backends.get(name)?.clone() {
+            BackendConfig::Slack { channel } => Box::new(SlackNotifier {
+                token: slack_token.to_string(),
+                channel,
+            }),
+            BackendConfig::Email { to } => Box::new(EmailNotifier { to }),
+            BackendConfig::Webhook { url, secret } => Box::new(WebhookNotifier { url, secret }),
+            BackendConfig::Matrix {
+                homeserver,
+                access_token,
+                room_id,
+            } => Box::new(MatrixNotifier {
+                homeserver,
+                access_token,
+                room_id,
+            }),
+        },
+    };
+    Some(notifier)
+}
+
+/// Tries `attempt` until it succeeds or the policy's attempts run out. Returns the last
+/// result and how many attempts were made.
+async fn retry<T, F, Fut>(
+    name: &str,
+    policy: RetryPolicy,
+    mut attempt: F,
+) -> (Result<T, String>, u32)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let mut backoff = policy.initial_backoff;
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match attempt().await {
+            Ok(value) => return (Ok(value), attempts),
+            Err(e) if attempts < policy.attempts => {
+                warn!(target: TARGET_WEB_REQUEST, "Failed to notify {} (attempt {}), retrying in {:?}: {}", name, attempts, backoff, e);
+                sleep(backoff).await;
+                backoff *= 2;
+            }
+            Err(e) => return (Err(e), attempts),
+        }
+    }
+}
+
+/// Sends a notification through one backend, retrying per its policy, and logs the outcome.
+async fn deliver(
+    db: &Database,
+    name: &str,
+    notifier: &dyn Notifier,
+    notification: &Notification<'_>,
+) -> (String, Option<Receipt>) {
+    let (result, attempts) = retry(name, notifier.retry_policy(), || {
+        notifier.send(notification)
+    })
+    .await;
+
+    let delivery = NotificationDelivery {
+        article_url: notification.article_url,
+        topics: &notification.topics,
+        backend: name,
+        delivered: result.is_ok(),
+        attempts,
+        receipt: result.as_ref().ok().and_then(|receipt| receipt.as_deref()),
+        last_error: result.as_ref().err().map(String::as_str),
+    };
+    metrics::record_notification(name, result.is_ok());
+    if let Err(e) = db.log_notification_delivery(&delivery).await {
+        error!(target: TARGET_WEB_REQUEST, "Failed to log delivery to {}: {:?}", name, e);
+    }
+
+    match result {
+        Ok(receipt) => {
+            info!(target: TARGET_WEB_REQUEST, "Notified {} of {}", name, notification.article_url);
+            (name.to_string(), Some(receipt))
+        }
+        Err(e) => {
+            error!(target: TARGET_WEB_REQUEST, "Giving up notifying {} of {} after {} attempts: {}", name, notification.article_url, attempts, e);
+            (name.to_string(), None)
+        }
+    }
+}
+
+/// Sends a notification to every backend its routes name, all at once. Returns the
+/// receipt from each backend that accepted it.
+pub async fn dispatch(
+    db: &Database,
+    notification: &Notification<'_>,
+    slack_token: &str,
+    slack_channel: &str,
+) -> HashMap<String, Receipt> {
+    let notifiers: Vec<(&str, Box<dyn Notifier>)> = ROUTER
+        .backends_for(notification)
+        .into_iter()
+        .filter_map(|name| {
+            build_notifier(name, &ROUTER, slack_token, slack_channel)
+                .map(|notifier| (name, notifier))
+        })
+        .collect();
+
+    join_all(
+        notifiers
+            .iter()
+            .map(|(name, notifier)| deliver(db, name, notifier.as_ref(), notification)),
+    )
+    .await
+    .into_iter()
+    .filter_map(|(name, receipt)| receipt.map(|receipt| (name, receipt)))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes_by_topic_and_severity() {
+        let config = RouterConfig::parse(
+            r#"{
+                "backends": {
+                    "dashboard": { "type": "webhook", "url": "https://example.com/hook", "secret": "s3cret" },
+                    "ops": { "type": "matrix", "homeserver": "https://matrix.example.com", "access_token": "t", "room_id": "!ops:example.com" }
+                },
+                "routes": [
+                    { "backends": ["app", "slack", "dashboard"] },
+                    { "topics": ["Alert: Direct"], "min_severity": "severe", "backends": ["ops", "dashboard"] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let analysis = json!({});
+        let mut notification = Notification {
+            topics: vec!["Alert: Direct".to_string()],
+            severity: ThreatSeverity::Moderate,
+            article_url: "https://example.com/a",
+            cluster_id: None,
+            headline: String::new(),
+            analysis: &analysis,
+        };
+        assert_eq!(
+            config.backends_for(&notification),
+            vec!["app", "slack", "dashboard"]
+        );
+        notification.severity = ThreatSeverity::Extreme;
+        assert_eq!(
+            config.backends_for(&notification),
+            vec!["app", "slack", "dashboard", "ops"]
+        );
+
+        assert_eq!(
+            RouterConfig::default().backends_for(&notification),
+            vec![APP_BACKEND, SLACK_BACKEND]
+        );
+        assert!(RouterConfig::parse(r#"{"routes": [{"backends": ["pager"]}]}"#).is_err());
+        assert!(RouterConfig::parse(r#"{"routes": {"backends": ["app"]}}"#).is_err());
+        assert!(RouterConfig::load("/nonexistent/notifiers.json").is_err());
+
+        assert_eq!(
+            sign_webhook("key", "1700000000", r#"{"event":"analysis"}"#),
+            "933b5c40be863d0405f5ea071c6f023682f0d4ac595b9bdd0894489a68cbc9fb"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::sync::{Arc, RwLock};
+use tokio::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::db::core::Database;
+use crate::db::places::Person;
+use crate::geo::gazetteer;
+use crate::TARGET_DB;
+
+const PLACES_JSON_PATH_ENV: &str = "PLACES_JSON_PATH";
+
+/// How often workers check whether the places registry changed.
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Continent → country → regions, as used by the threat location prompt.
+pub type PlacesHierarchy = BTreeMap<String, BTreeMap<String, Vec<String>>>;
+
+/// Continent → country → region → city → person entries, as used by the life safety checks.
+pub type PlacesDetailed =
+    BTreeMap<String, BTreeMap<String, BTreeMap<String, BTreeMap<String, Vec<String>>>>>;
+
+/// A snapshot of the places registry.
+#[derive(Debug, Default)]
+pub struct Places {
+    pub people: Vec<Person>,
+    /// Coordinates of each person in `people`, from their home, their location or the
+    /// gazetteer, in that order.
+    pub coordinates: Vec<Option<(f64, f64)>>,
+    pub hierarchical: PlacesHierarchy,
+    pub detailed: PlacesDetailed,
+    /// Like `detailed`, but only people without coordinates, who can't be matched by
+    /// distance.
+    pub detailed_unlocated: PlacesDetailed,
+}
+
+impl Places {
+    /// Builds the lookup structures from the people in the registry.
+    pub fn from_people(people: Vec<Person>) -> Self {
+        let mut hierarchical = PlacesHierarchy::new();
+        let mut detailed = PlacesDetailed::new();
+        let mut detailed_unlocated = PlacesDetailed::new();
+        let mut coordinates = Vec::with_capacity(people.len());
+
+        for person in &people {
+            let person_coordinates = person.coordinates().or_else(|| {
+                gazetteer()
+                    .lookup(&person.location.city, None)
+                    .map(|entry| (entry.latitude, entry.longitude))
+            });
+            if person_coordinates.is_none() {
+                add_detailed(&mut detailed_unlocated, person);
+            }
+            coordinates.push(person_coordinates);
+
+            let location = &person.location;
+            let regions = hierarchical
+                .entry(location.continent.clone())
+                .or_default()
+                .entry(location.country.clone())
+                .or_default();
+            if !regions.contains(&location.region) {
+                regions.push(location.region.clone());
+            }
+
+            add_detailed(&mut detailed, person);
+        }
+
+        Places {
+            people,
+            coordinates,
+            hierarchical,
+            detailed,
+            detailed_unlocated,
+        }
+    }
+}
+
+fn add_detailed(detailed: &mut PlacesDetailed, person: &Person) {
+    let location = &person.location;
+    detailed
+        .entry(location.continent.clone())
+        .or_default()
+        .entry(location.country.clone())
+        .or_default()
+        .entry(location.region.clone())
+        .or_default()
+        .entry(location.city.clone())
+        .or_default()
+        .push(person_entry(person));
+}
+
+/// Formats a person the way life safety alerts refer to them:
+/// "First, Last, City, Country, Time Zone, Slack ID".
+pub fn person_entry(person: &Person) -> String {
+    format!(
+        "{}, {}, {}, {}, {}, {}",
+        person.first_name,
+        person.last_name,
+        person.location.city,
+        person.location.country,
+        person.timezone.as_deref().unwrap_or(""),
+        person.slack_id.as_deref().unwrap_or("")
+    )
+}
+
+struct CachedPlaces {
+    revision: i64,
+    checked_at: Instant,
+    places: Arc<Places>,
+}
+
+static CACHE: Lazy<RwLock<Option<CachedPlaces>>> = Lazy::new(|| RwLock::new(None));
+
+/// Returns the current places snapshot, reloading it if the registry changed.
+///
+/// The revision is checked at most every 15 seconds. If reloading fails the previous
+/// snapshot is kept, so a database hiccup never stops the workers.
+pub async fn current(db: &Database) -> Arc<Places> {
+    let cached = {
+        let cache = CACHE.read().unwrap_or_else(|e| e.into_inner());
+        cache.as_ref().map(|cached| {
+            (
+                cached.revision,
+                cached.checked_at.elapsed() < RELOAD_CHECK_INTERVAL,
+                cached.places.clone(),
+            )
+        })
+    };
+
+    if let Some((_, true, places)) = &cached {
+        return places.clone();
+    }
+    if cached.is_none() {
+        import_places_json_if_empty(db).await;
+    }
+
+    let revision = match db.places_revision().await {
+        Ok(revision) => revision,
+        Err(e) => {
+            error!(target: TARGET_DB, "Failed to check places revision: {:?}", e);
+            return cached.map(|(_, _, places)| places).unwrap_or_default();
+        }
+    };
+
+    if let Some((cached_revision, _, places)) = &cached {
+        if *cached_revision == revision {
+            if let Some(cache) = CACHE.write().unwrap_or_else(|e| e.into_inner()).as_mut() {
+                cache.checked_at = Instant::now();
+            }
+            return places.clone();
+        }
+    }
+
+    match db.fetch_people().await {
+        Ok(people) => {
+            let places = Arc::new(Places::from_people(people));
+            info!(target: TARGET_DB, "Loaded places revision {}: {} people in {} continents", revision, places.people.len(), places.hierarchical.len());
+            *CACHE.write().unwrap_or_else(|e| e.into_inner()) = Some(CachedPlaces {
+                revision,
+                checked_at: Instant::now(),
+                places: places.clone(),
+            });
+            places
+        }
+        Err(e) => {
+            error!(target: TARGET_DB, "Failed to load places, keeping previous snapshot: {:?}", e);
+            cached.map(|(_, _, places)| places).unwrap_or_default()
+        }
+    }
+}
+
+/// Imports the legacy `PLACES_JSON_PATH` file the first time the registry is used, if the
+/// registry is still empty.
+async fn import_places_json_if_empty(db: &Database) {
+    let Ok(json_path) = env::var(PLACES_JSON_PATH_ENV) else {
+        return;
+    };
+    match db.places_empty().await {
+        Ok(true) => match import_places_json(db, &json_path).await {
+            Ok(count) => {
+                info!(target: TARGET_DB, "Imported {} people from {} into the places registry", count, json_path)
+            }
+            Err(e) => warn!(target: TARGET_DB, "Failed to import {}: {}", json_path, e),
+        },
+        Ok(false) => {}
+        Err(e) => error!(target: TARGET_DB, "Failed to check places registry: {:?}", e),
+    }
+}
+
+/// Imports people from a places.json file, returning how many were imported.
+pub async fn import_places_json(db: &Database, json_path: &str) -> Result<usize, String> {
+    let json_data = fs::read_to_string(json_path)
+        .map_err(|err| format!("Failed to read the places.json file: {}", err))?;
+    let entries = parse_places_json(&json_data)?;
+
+    for entry in &entries {
+        let location_id = db
+            .upsert_location(
+                &entry.continent,
+                &entry.country,
+                &entry.region,
+                &entry.city,
+                None,
+                None,
+            )
+            .await
+            .map_err(|err| format!("Failed to add location: {}", err))?;
+        db.add_person(
+            &entry.first_name,
+            &entry.last_name,
+            location_id,
+            entry.timezone.as_deref(),
+            entry.slack_id.as_deref(),
+        )
+        .await
+        .map_err(|err| format!("Failed to add person: {}", err))?;
+    }
+
+    Ok(entries.len())
+}
+
+/// One person parsed from places.json.
+#[derive(Debug, PartialEq)]
+pub struct PlacesJsonEntry {
+    pub continent: String,
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub timezone: Option<String>,
+    pub slack_id: Option<String>,
+}
+
+/// Parses places.json (continent → country → region → list of
+/// "First, Last, City, Country, Time Zone, Slack ID" entries). Malformed entries are
+/// skipped with a warning.
+pub fn parse_places_json(json_data: &str) -> Result<Vec<PlacesJsonEntry>, String> {
+    let places_data: Value = serde_json::from_str(json_data)
+        .map_err(|err| format!("Failed to parse places.json: {}", err))?;
+
+    let mut entries = Vec::new();
+    let Value::Object(continents) = places_data else {
+        return Err("places.json must contain an object of continents".to_string());
+    };
+    for (continent, countries) in continents {
+        let Value::Object(countries) = countries else {
+            continue;
+        };
+        for (country, regions) in countries {
+            let Value::Object(regions) = regions else {
+                continue;
+            };
+            for (region, people) in regions {
+                let Value::Array(people) = people else {
+                    continue;
+                };
+                for person in people.iter().filter_map(|p| p.as_str()) {
+                    let parts: Vec<&str> = person.split(',').map(str::trim).collect();
+                    if parts.len() < 3 || parts[0].is_empty() || parts[2].is_empty() {
+                        warn!(target: TARGET_DB, "Skipping malformed places.json entry: {}", person);
+                        continue;
+                    }
+                    let optional = |i: usize| {
+                        parts
+                            .get(i)
+                            .filter(|part| !part.is_empty())
+                            .map(|part| part.to_string())
+                    };
+                    entries.push(PlacesJsonEntry {
+                        continent: continent.clone(),
+                        country: country.clone(),
+                        region: region.clone(),
+                        city: parts[2].to_string(),
+                        first_name: parts[0].to_string(),
+                        last_name: parts[1].to_string(),
+                        timezone: optional(4),
+                        slack_id: optional(5),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::places::Location;
+
+    #[test]
+    fn test_parse_places_json_and_build_snapshot() {
+        let entries = parse_places_json(
+            r#"{"Europe": {"Italy": {"Tuscany": [
+                "Maria, Rossi, Florence, Italy, CET (UTC+1), mrossi",
+                "Broken entry"
+            ]}}}"#,
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].region, "Tuscany");
+        assert_eq!(entries[0].slack_id.as_deref(), Some("mrossi"));
+
+        let entry = &entries[0];
+        let places = Places::from_people(vec![Person {
+            id: 1,
+            first_name: entry.first_name.clone(),
+            last_name: entry.last_name.clone(),
+            timezone: entry.timezone.clone(),
+            slack_id: entry.slack_id.clone(),
+            home_latitude: None,
+            home_longitude: None,
+            quiet_hours: None,
+            location: Location {
+                id: 1,
+                continent: entry.continent.clone(),
+                country: entry.country.clone(),
+                region: entry.region.clone(),
+                city: entry.city.clone(),
+                latitude: None,
+                longitude: None,
+            },
+        }]);
+        assert_eq!(places.hierarchical["Europe"]["Italy"], vec!["Tuscany"]);
+        assert_eq!(places.coordinates, vec![None]);
+        assert!(places.detailed_unlocated["Europe"]["Italy"]["Tuscany"].contains_key("Florence"));
+        assert_eq!(
+            places.detailed["Europe"]["Italy"]["Tuscany"]["Florence"],
+            vec!["Maria, Rossi, Florence, Italy, CET (UTC+1), mrossi"]
+        );
+    }
+}
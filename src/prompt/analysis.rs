@@ -1,4 +1,4 @@
-use crate::prompt::common::{global_context, DONT_TELL_ME, WRITE_IN_CLEAR_ENGLISH};
+use crate::prompt::common::{global_context, output_language_instructions, DONT_TELL_ME};
 
 /// Generate a prompt for critical analysis of an article
 pub fn critical_analysis_prompt(article_text: &str, pub_date: Option<&str>) -> String {
@@ -134,7 +134,7 @@ Now, perform the analysis with these guidelines:
 {dont_tell_me}"#,
         context = global_context(pub_date),
         article = article_text,
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME
     )
 }
@@ -233,7 +233,7 @@ Now, perform the analysis with these guidelines:
 {dont_tell_me}"#,
         context = global_context(pub_date),
         article = article_text,
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME
     )
 }
@@ -297,7 +297,7 @@ Now analyze the publication source using these rules:
         article = article_html,
         source_url = source_url,
         context = global_context(pub_date),
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME
     )
 }
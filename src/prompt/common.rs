@@ -1,4 +1,7 @@
 use chrono::Local;
+use once_cell::sync::Lazy;
+
+use crate::language::{language_name, output_language, WORKING_LANGUAGE};
 
 // Common text blocks for all prompts
 pub const DONT_TELL_ME: &str = r#"
@@ -106,6 +109,41 @@ Language Standards for Output:
    - Citations: American format
 "#;
 
+/// Returns the language instructions for generated text: `WRITE_IN_CLEAR_ENGLISH` unless
+/// `OUTPUT_LANGUAGE` names another language.
+pub fn output_language_instructions() -> &'static str {
+    static INSTRUCTIONS: Lazy<String> = Lazy::new(|| {
+        let code = output_language();
+        if code == WORKING_LANGUAGE {
+            return WRITE_IN_CLEAR_ENGLISH.to_string();
+        }
+        let language = language_name(&code);
+        format!(
+            r#"
+Language Standards for Output:
+1. Write all content in clear {language}, using standard {language} spelling and grammar.
+2. For content in any other language:
+   - ALWAYS include both original text and translation
+   - Format as: "original text (translation)"
+   - For titles: Keep original, add translation in parentheses
+   - For names: Do not translate as they are names
+   - Never translate if the translation is the same as the original.
+3. Units and Measurements:
+   - Use the units customary for {language} readers
+4. Writing Style:
+   - Use clear, accessible {language}
+   - Avoid region-specific idioms
+   - Define specialized terms
+   - Use active voice when possible
+5. Formatting:
+   - Original quotes: Use quotation marks
+   - Translations: Always in parentheses
+"#
+        )
+    });
+    &INSTRUCTIONS
+}
+
 pub const CONTEXT: &str = "
 In Q1 2024, BRICS expanded, shifting global economic power, while record temperatures highlighted climate concerns. Japan's 7.6 earthquake and U.S. winter storms exposed vulnerabilities. France enshrined abortion rights, Sweden joined NATO, and the U.S. Supreme Court ruled on key legal precedents. Major wildfires and geopolitical tensions added to global challenges.
 In Q2 2024, a solar eclipse captivated North America as record heatwaves and severe floods underscored climate urgency. Trump's trial and free speech protests stirred U.S. discourse. Putin's fifth term, Xi's European visit, and G7's $50B Ukraine aid shaped geopolitics. Apple's AI integration marked tech innovation.
@@ -0,0 +1,30 @@
+use crate::prompt::common::{current_date, output_language_instructions, DONT_TELL_ME};
+
+/// Generate a prompt for the overview at the top of a scheduled digest
+pub fn digest_overview_prompt(digest_title: &str, period: &str, stories: &str) -> String {
+    format!(
+        r#"
+Today is {date}.
+
+==========
+{stories}
+----------
+
+The stories above are the most important ones from {period} for the digest "{title}", most important first.
+Write a short overview for a busy reader who will only read this digest.
+Instructions:
+1. Write 2 to 4 short paragraphs of plain prose, with no headings, lists or links.
+2. Lead with the most important developments and explain why they matter.
+3. Connect related stories where they share a cause, an actor or a consequence.
+4. Only use facts from the stories above, and keep their level of certainty: rumors stay rumors.
+5. Do not repeat every story; the reader will see the full list below the overview.
+{language}
+{dont_tell_me}"#,
+        date = current_date(),
+        stories = stories,
+        period = period,
+        title = digest_title,
+        language = output_language_instructions(),
+        dont_tell_me = DONT_TELL_ME
+    )
+}
@@ -1,4 +1,4 @@
-use crate::prompt::common::{global_context, DONT_TELL_ME, WRITE_IN_CLEAR_ENGLISH};
+use crate::prompt::common::{global_context, output_language_instructions, DONT_TELL_ME};
 
 /// Generate a prompt for creating action recommendations based on an article
 pub fn action_recommendations_prompt(article_text: &str, pub_date: Option<&str>) -> String {
@@ -69,7 +69,7 @@ Now create 3-5 specific, actionable recommendations based on this article:
 {dont_tell_me}"#,
         context = global_context(pub_date),
         article = article_text,
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME
     )
 }
@@ -140,7 +140,7 @@ Now create 3-5 engaging talking points based on this article:
 {dont_tell_me}"#,
         context = global_context(pub_date),
         article = article_text,
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME
     )
 }
@@ -366,7 +366,7 @@ Example Devil's Advocate points:
 {dont_tell_me}"#,
         context = global_context(pub_date),
         article = article_text,
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME
     )
 }
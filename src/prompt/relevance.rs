@@ -1,6 +1,7 @@
 use crate::prompt::common::{
-    global_context, DONT_TELL_ME, FORMAT_INSTRUCTIONS, WRITE_IN_CLEAR_ENGLISH,
+    global_context, output_language_instructions, DONT_TELL_ME, FORMAT_INSTRUCTIONS,
 };
+use chrono::Utc;
 use std::collections::BTreeMap;
 
 /// Generate a prompt for analyzing an article's relation to a specific topic
@@ -61,7 +62,7 @@ Now explain how the article relates to **{topic}** using these rules:
         context = global_context(pub_date),
         article = article_text,
         topic = topic_prompt,
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME
     )
 }
@@ -126,7 +127,7 @@ Now analyze the impact on {places} using these rules:
 {format_instructions}"#,
         article = article_text,
         places = affected_places,
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME,
         format_instructions = FORMAT_INSTRUCTIONS
     )
@@ -193,7 +194,7 @@ Now explain why there is no impact on {places} using these rules:
 {format_instructions}"#,
         article = article_text,
         places = non_affected_places,
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME,
         format_instructions = FORMAT_INSTRUCTIONS
     )
@@ -212,8 +213,17 @@ pub fn threat_locations(
     );
     prompt.push_str("For each impacted region, provide the continent, country, and region name. ");
     prompt.push_str("If a region is not mentioned in or directly impacted by the text of the article, do not include it in the output. ");
+    prompt.push_str("Also classify the threat:\n");
+    prompt.push_str("- \"category\": one of weather, geophysical, fire, health, conflict, infrastructure, hazmat or other\n");
+    prompt.push_str("- \"severity\": minor, moderate, severe or extreme (a watch or advisory is usually minor or moderate; an evacuation order or active attack is severe or extreme)\n");
+    prompt.push_str("- \"response\": what people in the area are advised to do: monitor, prepare, avoid, shelter, evacuate, all_clear (the threat is over) or none\n");
+    prompt.push_str(&format!(
+        "- \"starts_at\" and \"ends_at\": when the threat starts and is expected to end, as ISO 8601 dates or times, or null if the article does not say. Today is {}.\n",
+        Utc::now().format("%Y-%m-%d")
+    ));
+    prompt.push_str("- \"confidence\": from 0 to 1, how sure you are that this is a real and current threat\n\n");
     prompt.push_str("The JSON format should be:\n\n");
-    prompt.push_str("{\n  \"impacted_regions\": [\n    {\n      \"continent\": \"<continent_name>\",\n      \"country\": \"<country_name>\",\n      \"region\": \"<region_name>\"\n    },\n    ...\n  ]\n}\n\n");
+    prompt.push_str("{\n  \"impacted_regions\": [\n    {\n      \"continent\": \"<continent_name>\",\n      \"country\": \"<country_name>\",\n      \"region\": \"<region_name>\"\n    },\n    ...\n  ],\n  \"category\": \"<category>\",\n  \"severity\": \"<severity>\",\n  \"response\": \"<response>\",\n  \"starts_at\": \"<date or null>\",\n  \"ends_at\": \"<date or null>\",\n  \"confidence\": <number>\n}\n\n");
 
     // Add the hierarchical data for reference
     prompt.push_str("Here is the list of geographical regions for reference:\n\n");
@@ -237,3 +247,42 @@ pub fn threat_locations(
 
     prompt
 }
+
+/// Generates a prompt asking where a threat is centered and how far it reaches, so that
+/// affected people can be found by distance.
+pub fn threat_geolocation_prompt(article: &str) -> String {
+    format!(
+        r#"You are analyzing a news article about a threat to life or safety to determine where it is on the map.
+
+For each distinct area threatened by the events described, give:
+- "place": the most specific place at the center of the threat (a city, town or landmark; a region only if nothing more specific is given)
+- "country_code": the ISO 3166-1 alpha-2 code of the country, e.g. "FR"
+- "latitude" and "longitude": your best estimate of the center in decimal degrees, or null if unknown
+- "radius_km": how far from the center people are directly at risk, in kilometers
+
+Guidance for radius_km:
+- A building fire, shooting or local gas leak: 1 to 5
+- A chemical spill, flood or wildfire affecting a town: 10 to 50
+- A hurricane, major earthquake or regional wildfire: 100 to 300
+- A pandemic or nationwide emergency: the approximate size of the affected country
+
+Only include areas where the threat is current or imminent. Respond with JSON in this format:
+
+{{
+  "threat_areas": [
+    {{ "place": "<place>", "country_code": "<code>", "latitude": <number or null>, "longitude": <number or null>, "radius_km": <number> }}
+  ]
+}}
+
+---
+
+Here is the article:
+
+{article}
+
+---
+
+Based on the article, list the threatened areas in the JSON format specified above."#,
+        article = article
+    )
+}
@@ -1,5 +1,5 @@
 use crate::prompt::common::{
-    current_date, global_context, DONT_TELL_ME, FORMAT_INSTRUCTIONS, WRITE_IN_CLEAR_ENGLISH,
+    current_date, global_context, output_language_instructions, DONT_TELL_ME, FORMAT_INSTRUCTIONS,
 };
 
 /// Generate a prompt for summarizing an article into a bullet-point summary
@@ -106,7 +106,7 @@ Now summarize the article text above using these rules:
 {format_instructions}"#,
         context = global_context(pub_date),
         article = article_text,
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME,
         format_instructions = FORMAT_INSTRUCTIONS
     )
@@ -271,7 +271,7 @@ For multi-topic articles:
 {dont_tell_me}"#,
         summary = summary_response,
         date = current_date(),
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME
     )
 }
@@ -415,7 +415,7 @@ IMPORTANT: Return ONLY one title, even if the article covers multiple topics or
 {write_in_clear_english}
 {dont_tell_me}"#,
         summary = summary_response,
-        write_in_clear_english = WRITE_IN_CLEAR_ENGLISH,
+        write_in_clear_english = output_language_instructions(),
         dont_tell_me = DONT_TELL_ME
     )
 }
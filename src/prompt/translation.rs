@@ -0,0 +1,20 @@
+/// Generate a prompt to translate a non-English article into English before the decision prompts
+pub fn translation_prompt(article_text: &str, source_language: &str) -> String {
+    format!(
+        r#"
+==========
+{article}
+----------
+
+Translate the {language} news article above into English.
+Instructions:
+1. Translate the full article faithfully, do not summarize, shorten or add commentary.
+2. Keep the "Title:" and "Body:" labels exactly as they appear.
+3. Do not translate the names of people, organizations or products.
+4. Keep place names recognizable, using the common English name where one exists (e.g., "Toscana" becomes "Tuscany").
+5. Keep numbers, dates and units as written.
+6. Output only the translated article."#,
+        article = article_text,
+        language = source_language
+    )
+}
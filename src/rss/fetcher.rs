@@ -11,6 +11,7 @@ use super::parser::process_feed;
 use super::types::{MAX_RETRIES, RETRY_DELAY};
 use super::util::{is_valid_url, try_decompressions};
 use crate::db::core::Database;
+use crate::metrics;
 use crate::TARGET_WEB_REQUEST;
 
 /// Main RSS fetching loop - periodically fetches all configured RSS feeds
@@ -56,6 +57,7 @@ pub async fn process_rss_urls(rss_urls: &Vec<String>, db: &Database) -> Result<(
 
         let mut attempts = 0;
         let mut new_articles_count = 0;
+        let mut last_error = None;
 
         debug!(target: TARGET_WEB_REQUEST, "Starting to process RSS URL: {}", rss_url);
 
@@ -64,6 +66,7 @@ pub async fn process_rss_urls(rss_urls: &Vec<String>, db: &Database) -> Result<(
                 error!(target: TARGET_WEB_REQUEST, "Max retries reached for URL: {}, moving on", rss_url);
                 break;
             }
+            last_error = None;
 
             debug!(target: TARGET_WEB_REQUEST, "Loading RSS feed from {}", rss_url);
 
@@ -98,6 +101,7 @@ pub async fn process_rss_urls(rss_urls: &Vec<String>, db: &Database) -> Result<(
                             Err(err) => {
                                 error!(target: TARGET_WEB_REQUEST,
                                        "Failed to read response bytes from {}: {}", rss_url, err);
+                                last_error = Some(format!("Failed to read response: {}", err));
                                 attempts += 1;
                                 sleep(RETRY_DELAY).await;
                                 continue;
@@ -151,6 +155,7 @@ pub async fn process_rss_urls(rss_urls: &Vec<String>, db: &Database) -> Result<(
                                     }
                                     Err(e) => {
                                         error!(target: TARGET_WEB_REQUEST, "Error processing feed {}: {}", rss_url, e);
+                                        last_error = Some(format!("Failed to process feed: {}", e));
                                         attempts += 1;
                                         sleep(RETRY_DELAY).await;
                                         continue;
@@ -159,6 +164,7 @@ pub async fn process_rss_urls(rss_urls: &Vec<String>, db: &Database) -> Result<(
                             }
                             Err(_) => {
                                 error!(target: TARGET_WEB_REQUEST, "Failed to decode content as UTF-8 from {}", rss_url);
+                                last_error = Some("Content is not UTF-8".to_string());
                                 attempts += 1;
                                 sleep(RETRY_DELAY).await;
                                 continue;
@@ -166,6 +172,7 @@ pub async fn process_rss_urls(rss_urls: &Vec<String>, db: &Database) -> Result<(
                         }
                     } else {
                         warn!(target: TARGET_WEB_REQUEST, "Non-success status {} from {}", response.status(), rss_url);
+                        last_error = Some(format!("HTTP {}", response.status()));
                         attempts += 1;
                         sleep(RETRY_DELAY).await;
                         continue;
@@ -173,6 +180,7 @@ pub async fn process_rss_urls(rss_urls: &Vec<String>, db: &Database) -> Result<(
                 }
                 Err(err) => {
                     error!(target: TARGET_WEB_REQUEST, "Request to {} failed: {}", rss_url, err);
+                    last_error = Some(format!("Request failed: {}", err));
                     attempts += 1;
                     sleep(RETRY_DELAY).await;
                     continue;
@@ -180,7 +188,15 @@ pub async fn process_rss_urls(rss_urls: &Vec<String>, db: &Database) -> Result<(
             }
         }
 
+        if let Err(e) = db
+            .record_feed_fetch(rss_url, new_articles_count as i64, last_error.as_deref())
+            .await
+        {
+            warn!(target: TARGET_WEB_REQUEST, "Failed to record fetch of {}: {}", rss_url, e);
+        }
+
         // Log the total number of new articles
+        metrics::record_articles_fetched(rss_url, new_articles_count as u64);
         if new_articles_count > 0 {
             info!(target: TARGET_WEB_REQUEST, "Total new articles added from {}: {}", rss_url, new_articles_count);
         }
@@ -311,6 +311,7 @@ async fn process_article_entry(
                 None,
                 pub_date,
                 None,
+                None, // language
             )
             .await
         {
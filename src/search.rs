@@ -0,0 +1,372 @@
+//! Keyword and semantic search over analyzed articles.
+//!
+//! Articles are indexed in the `article_search` FTS5 table by title, summary, tiny summary,
+//! critical analysis and entity names, and ranked by BM25. A hybrid search also asks
+//! Qdrant for the articles closest in meaning to the query and merges both rankings. A
+//! semantic search asks Qdrant alone and groups the matches by story cluster.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::db::cluster::get_cluster_info;
+use crate::db::core::Database;
+use crate::vector::search::{search_articles_by_query, search_articles_by_text, SemanticFilter};
+use crate::vector::{ArticleMatch, TARGET_VECTOR};
+
+/// Default share of the semantic similarity in a hybrid score.
+pub const DEFAULT_SEMANTIC_WEIGHT: f64 = 0.5;
+
+/// How a search is run.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// Only articles about this topic
+    pub topic: Option<String>,
+    pub limit: usize,
+    /// Merge with Qdrant similarity, giving it this share of the score (0.0 to 1.0)
+    pub semantic_weight: Option<f64>,
+    /// Pass the query to FTS5 as is, e.g. `"rust async" NOT tokio`
+    pub raw: bool,
+}
+
+/// An article found by a search.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub id: i64,
+    pub url: String,
+    pub title: Option<String>,
+    pub tiny_summary: Option<String>,
+    pub category: Option<String>,
+    pub quality: Option<i64>,
+    pub pub_date: Option<String>,
+    pub snippet: Option<String>,
+    /// BM25 relevance, if the article matched the keywords
+    pub bm25: Option<f64>,
+    /// Similarity to the query, if it was among the semantic matches of a hybrid search
+    pub similarity: Option<f32>,
+    /// The score results are ordered by: BM25 for keyword searches, the merged score
+    /// (0.0 to 1.0) for hybrid searches
+    pub score: f64,
+}
+
+/// Turns free text into an FTS5 query that matches articles containing every word.
+/// Words are quoted so punctuation and FTS5 keywords are taken literally; a trailing `*`
+/// keeps its prefix meaning. Returns None if no words are left.
+pub fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .filter_map(|word| {
+            let (word, prefix) = match word.strip_suffix('*') {
+                Some(word) => (word, "*"),
+                None => (word, ""),
+            };
+            let word = word.replace('"', "");
+            (!word.is_empty()).then(|| format!("\"{}\"{}", word, prefix))
+        })
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Merges keyword and semantic rankings. BM25 scores are scaled so the best keyword match
+/// scores 1.0, then combined with the similarity as
+/// `(1 - semantic_weight) * bm25 + semantic_weight * similarity`. Returns article IDs
+/// with their merged score, best first.
+pub fn merge_hybrid(
+    keyword: &[(i64, f64)],
+    semantic: &[(i64, f32)],
+    semantic_weight: f64,
+) -> Vec<(i64, f64)> {
+    let semantic_weight = semantic_weight.clamp(0.0, 1.0);
+    let max_bm25 = keyword.iter().map(|(_, score)| *score).fold(0.0, f64::max);
+    let mut scores: HashMap<i64, f64> = HashMap::new();
+    for (id, score) in keyword {
+        if max_bm25 > 0.0 {
+            *scores.entry(*id).or_default() += (1.0 - semantic_weight) * score / max_bm25;
+        }
+    }
+    for (id, similarity) in semantic {
+        *scores.entry(*id).or_default() += semantic_weight * *similarity as f64;
+    }
+    let mut merged: Vec<(i64, f64)> = scores.into_iter().collect();
+    merged.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.0.cmp(&a.0)));
+    merged
+}
+
+/// Searches analyzed articles by keyword, and by meaning too if `semantic_weight` is set.
+/// If Qdrant can't be reached, a hybrid search falls back to keywords alone.
+pub async fn search(
+    db: &Database,
+    query: &str,
+    options: &SearchOptions,
+) -> Result<Vec<SearchResult>> {
+    let fts_query = if options.raw {
+        query.trim().to_string()
+    } else {
+        fts_query(query).ok_or_else(|| anyhow!("Search query has no words"))?
+    };
+    let limit = options.limit.max(1);
+    // Fetch more candidates than needed when the rankings are merged
+    let candidates = if options.semantic_weight.is_some() {
+        limit * 2
+    } else {
+        limit
+    };
+
+    let hits = db
+        .search_articles(&fts_query, options.topic.as_deref(), candidates as i64)
+        .await?;
+    let mut results: Vec<SearchResult> = hits
+        .into_iter()
+        .map(|hit| SearchResult {
+            id: hit.id,
+            url: hit.url,
+            title: hit.title,
+            tiny_summary: hit.tiny_summary,
+            category: hit.category,
+            quality: hit.quality,
+            pub_date: hit.pub_date,
+            snippet: hit.snippet,
+            bm25: Some(hit.score),
+            similarity: None,
+            score: hit.score,
+        })
+        .collect();
+
+    let Some(semantic_weight) = options.semantic_weight else {
+        results.truncate(limit);
+        return Ok(results);
+    };
+    let semantic = match search_articles_by_text(query, candidates as u64).await {
+        Ok(semantic) => semantic,
+        Err(e) => {
+            warn!(target: TARGET_VECTOR, "Semantic search failed, using keywords only: {:?}", e);
+            results.truncate(limit);
+            return Ok(results);
+        }
+    };
+
+    let keyword: Vec<(i64, f64)> = results
+        .iter()
+        .filter_map(|result| result.bm25.map(|bm25| (result.id, bm25)))
+        .collect();
+    let similarities: HashMap<i64, f32> = semantic.iter().copied().collect();
+    let mut by_id: HashMap<i64, SearchResult> = results
+        .into_iter()
+        .map(|result| (result.id, result))
+        .collect();
+
+    let mut merged = Vec::new();
+    for (id, score) in merge_hybrid(&keyword, &semantic, semantic_weight) {
+        if merged.len() == limit {
+            break;
+        }
+        let result = match by_id.remove(&id) {
+            Some(result) => result,
+            // Semantic matches without the keywords are looked up, and still have to be
+            // about the topic
+            None => match db.fetch_article(id).await? {
+                Some(article)
+                    if options
+                        .topic
+                        .as_ref()
+                        .is_none_or(|topic| article.topics.contains(topic)) =>
+                {
+                    SearchResult {
+                        id,
+                        url: article.url,
+                        title: article.title,
+                        tiny_summary: article.tiny_summary,
+                        category: article.category,
+                        quality: article.quality,
+                        pub_date: article.pub_date,
+                        snippet: None,
+                        bm25: None,
+                        similarity: None,
+                        score,
+                    }
+                }
+                _ => continue,
+            },
+        };
+        merged.push(SearchResult {
+            similarity: similarities.get(&id).copied(),
+            score,
+            ..result
+        });
+    }
+    Ok(merged)
+}
+
+/// An article found by a semantic search, with the score breakdown of its match.
+#[derive(Debug, Serialize)]
+pub struct SemanticHit {
+    #[serde(flatten)]
+    pub article: ArticleMatch,
+    pub url: String,
+    pub title: Option<String>,
+    pub tiny_summary: Option<String>,
+    pub cluster_id: Option<i64>,
+}
+
+/// The matches of a semantic search in one story cluster.
+#[derive(Debug, Serialize)]
+pub struct ClusterHits {
+    pub cluster_id: i64,
+    pub summary: Option<String>,
+    pub importance_score: f64,
+    /// All articles in the cluster, not only the matching ones
+    pub article_count: i32,
+    /// Score of the best matching article
+    pub best_score: f32,
+    pub articles: Vec<SemanticHit>,
+}
+
+/// The matches of a semantic search, grouped by story cluster.
+#[derive(Debug, Serialize)]
+pub struct SemanticResults {
+    /// Clusters with matching articles, best match first
+    pub clusters: Vec<ClusterHits>,
+    /// Matching articles that aren't in a cluster, best first
+    pub unclustered: Vec<SemanticHit>,
+}
+
+/// Groups matches by cluster, keeping the order of the best match of each cluster and
+/// of the articles within it. Returns the clusters and the articles without one.
+pub fn group_by_cluster(
+    hits: Vec<SemanticHit>,
+) -> (Vec<(i64, Vec<SemanticHit>)>, Vec<SemanticHit>) {
+    let mut clusters: Vec<(i64, Vec<SemanticHit>)> = Vec::new();
+    let mut unclustered = Vec::new();
+    for hit in hits {
+        match hit.cluster_id {
+            Some(cluster_id) => match clusters.iter_mut().find(|(id, _)| *id == cluster_id) {
+                Some((_, cluster_hits)) => cluster_hits.push(hit),
+                None => clusters.push((cluster_id, vec![hit])),
+            },
+            None => unclustered.push(hit),
+        }
+    }
+    (clusters, unclustered)
+}
+
+/// Finds the articles closest in meaning to a query, grouped by story cluster.
+pub async fn semantic_search(
+    db: &Database,
+    query: &str,
+    filter: &SemanticFilter,
+    limit: usize,
+    min_score: Option<f32>,
+) -> Result<SemanticResults> {
+    if query.trim().is_empty() {
+        return Err(anyhow!("Search query has no words"));
+    }
+    let matches = search_articles_by_query(query, filter, limit.max(1) as u64, min_score).await?;
+
+    let mut hits = Vec::with_capacity(matches.len());
+    for article in matches {
+        // Vectors can outlive their article
+        let Some(record) = db.fetch_article(article.id).await? else {
+            continue;
+        };
+        hits.push(SemanticHit {
+            article,
+            url: record.url,
+            title: record.title,
+            tiny_summary: record.tiny_summary,
+            cluster_id: record.cluster_id,
+        });
+    }
+
+    let (grouped, unclustered) = group_by_cluster(hits);
+    let mut clusters = Vec::with_capacity(grouped.len());
+    for (cluster_id, articles) in grouped {
+        let info = get_cluster_info(db, cluster_id).await?;
+        clusters.push(ClusterHits {
+            cluster_id,
+            summary: info.as_ref().and_then(|info| info.summary.clone()),
+            importance_score: info.as_ref().map_or(0.0, |info| info.importance_score),
+            article_count: info.as_ref().map_or(0, |info| info.article_count),
+            best_score: articles[0].article.score,
+            articles,
+        });
+    }
+    Ok(SemanticResults {
+        clusters,
+        unclustered,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query_and_merge() {
+        assert_eq!(
+            fts_query("rust  async*"),
+            Some("\"rust\" \"async\"*".to_string())
+        );
+        assert_eq!(
+            fts_query("\"NOT\" AND c++"),
+            Some("\"NOT\" \"AND\" \"c++\"".to_string())
+        );
+        assert_eq!(fts_query(" \" * "), None);
+
+        let merged = merge_hybrid(&[(1, 8.0), (2, 4.0)], &[(2, 0.9), (3, 0.8)], 0.5);
+        let ids: Vec<i64> = merged.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![2, 1, 3]);
+        assert!((merged[0].1 - 0.7).abs() < 1e-6);
+        assert!((merged[1].1 - 0.5).abs() < 1e-6);
+
+        let keyword_only = merge_hybrid(&[(1, 8.0), (2, 4.0)], &[(3, 0.99)], 0.0);
+        assert_eq!(keyword_only[0], (1, 1.0));
+    }
+
+    #[test]
+    fn test_group_by_cluster() {
+        let hit = |id, score, cluster_id| SemanticHit {
+            article: ArticleMatch {
+                id,
+                published_date: String::new(),
+                category: "Rust".to_string(),
+                quality_score: 2,
+                score,
+                vector_score: Some(score),
+                vector_active_dimensions: None,
+                vector_magnitude: None,
+                entity_overlap_count: None,
+                primary_overlap_count: None,
+                person_overlap: None,
+                org_overlap: None,
+                location_overlap: None,
+                event_overlap: None,
+                temporal_proximity: None,
+                similarity_formula: None,
+            },
+            url: format!("https://example.com/{}", id),
+            title: None,
+            tiny_summary: None,
+            cluster_id,
+        };
+        let (clusters, unclustered) = group_by_cluster(vec![
+            hit(1, 0.9, Some(20)),
+            hit(2, 0.8, None),
+            hit(3, 0.7, Some(10)),
+            hit(4, 0.6, Some(20)),
+        ]);
+        let grouped: Vec<(i64, Vec<i64>)> = clusters
+            .iter()
+            .map(|(cluster_id, hits)| {
+                (*cluster_id, hits.iter().map(|hit| hit.article.id).collect())
+            })
+            .collect();
+        assert_eq!(grouped, vec![(20, vec![1, 4]), (10, vec![3])]);
+        assert_eq!(unclustered.len(), 1);
+        assert_eq!(unclustered[0].article.id, 2);
+
+        let value = serde_json::to_value(&clusters[0].1[0]).unwrap();
+        assert_eq!(value["vector_score"].as_f64().unwrap() as f32, 0.9);
+        assert_eq!(value["cluster_id"], 20);
+    }
+}
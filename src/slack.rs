@@ -1,10 +1,16 @@
 use regex::Regex;
 use reqwest::{header::HeaderValue, Client};
-use serde_json::json;
-use std::collections::HashMap;
+use ring::hmac;
+use serde_json::{json, Value};
 use tokio::time::{timeout, Duration};
 use tracing::{debug, error, info, warn};
+use url::form_urlencoded;
 
+use crate::db::cluster;
+use crate::db::core::Database;
+use crate::feedback::FeedbackKind;
+use crate::throttle::{self, Candidate, Recipient, Verdict};
+use crate::topics;
 use crate::TARGET_WEB_REQUEST;
 
 /// Converts standard Markdown to Slack-compatible formatting.
@@ -55,163 +61,421 @@ fn deduplicate_markdown(text: &str) -> String {
     output
 }
 
-/// Sends the formatted article to the Slack channel.
+/// Slack rejects section text longer than this.
+const MAX_SECTION_CHARS: usize = 3000;
+/// Slack rejects header text longer than this.
+const MAX_HEADER_CHARS: usize = 150;
+/// Slack rejects messages with more blocks than this.
+const MAX_BLOCKS: usize = 50;
+
+/// Splits text into pieces of at most `max_chars` characters, preferring to break at
+/// paragraph and line ends.
+fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+    while rest.chars().count() > max_chars {
+        let limit = rest
+            .char_indices()
+            .nth(max_chars)
+            .map_or(rest.len(), |(i, _)| i);
+        let split = rest[..limit]
+            .rfind("\n\n")
+            .or_else(|| rest[..limit].rfind('\n'))
+            .filter(|&i| i > 0)
+            .unwrap_or(limit);
+        chunks.push(rest[..split].trim_end().to_string());
+        rest = rest[split..].trim_start();
+    }
+    if !rest.is_empty() {
+        chunks.push(rest.to_string());
+    }
+    chunks
+}
+
+/// Context badges summarizing the quality ratings of an analysis.
+fn quality_badges(response_json: &Value) -> Vec<String> {
+    let mut badges = Vec::new();
+    if let Some(quality) = response_json["quality"].as_i64() {
+        badges.push(match quality {
+            3.. => format!(":large_green_circle: *High quality* ({})", quality),
+            1..=2 => format!(":large_yellow_circle: *Fair quality* ({})", quality),
+            _ => format!(":red_circle: *Low quality* ({})", quality),
+        });
+    }
+    if let Some(sources) = response_json["sources_quality"].as_u64() {
+        badges.push(format!("Sources {}/3", sources));
+    }
+    if let Some(argument) = response_json["argument_quality"].as_u64() {
+        badges.push(format!("Arguments {}/3", argument));
+    }
+    match response_json["source_type"].as_str() {
+        Some(source_type) if !source_type.is_empty() && source_type != "none" => {
+            badges.push(format!("Source: {}", source_type))
+        }
+        _ => {}
+    }
+    let topics = topics::topics_in_response(response_json);
+    if !topics.is_empty() {
+        badges.push(format!("Topics: {}", topics.join(", ")));
+    }
+    badges
+}
+
+/// Builds the Block Kit message for an analysis: a header, the headline and short summary,
+/// quality badges, then one section per part of the analysis. Slack collapses long sections
+/// behind "Show more", so the message stays short until a reader expands it.
+fn article_blocks(headline: &str, response_json: &Value) -> Vec<Value> {
+    let field =
+        |name: &str| deduplicate_markdown(response_json[name].as_str().unwrap_or("").trim());
+
+    let mut blocks = Vec::new();
+    let tiny_title = response_json["tiny_title"].as_str().unwrap_or("").trim();
+    if !tiny_title.is_empty() {
+        let title: String = tiny_title.chars().take(MAX_HEADER_CHARS).collect();
+        blocks.push(json!({
+            "type": "header",
+            "text": { "type": "plain_text", "text": title, "emoji": true }
+        }));
+    }
+    let lead = format!("{}\n{}", headline, field("tiny_summary"));
+    for chunk in chunk_text(&lead, MAX_SECTION_CHARS) {
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": chunk }
+        }));
+    }
+    let badges = quality_badges(response_json);
+    if !badges.is_empty() {
+        blocks.push(json!({
+            "type": "context",
+            "elements": badges
+                .into_iter()
+                .map(|badge| json!({ "type": "mrkdwn", "text": badge }))
+                .collect::<Vec<_>>()
+        }));
+    }
+
+    let sections = [
+        ("Relevance", "relation_to_topic"),
+        ("Summary", "summary"),
+        ("Critical Analysis", "critical_analysis"),
+        ("Logical Fallacies", "logical_fallacies"),
+        ("Source Analysis", "source_analysis"),
+        ("Argus Speaks", "additional_insights"),
+    ];
+    for (title, name) in sections {
+        let content = field(name);
+        if content.is_empty() {
+            continue;
+        }
+        blocks.push(json!({ "type": "divider" }));
+        let chunks = chunk_text(&content, MAX_SECTION_CHARS - title.len() - 16);
+        let parts = chunks.len();
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            let heading = if parts > 1 {
+                format!("*{} (Part {})*", title, i + 1)
+            } else {
+                format!("*{}*", title)
+            };
+            blocks.push(json!({
+                "type": "section",
+                "text": { "type": "mrkdwn", "text": format!("{}\n{}", heading, chunk) }
+            }));
+        }
+    }
+
+    // Keep room for the footer and the feedback buttons
+    blocks.truncate(MAX_BLOCKS - 2);
+    blocks.push(json!({
+        "type": "context",
+        "elements": [{
+            "type": "mrkdwn",
+            "text": format!(
+                "_Generated using model {} in {:.2} seconds._",
+                response_json["model"].as_str().unwrap_or("Unknown model"),
+                response_json["elapsed_time"].as_f64().unwrap_or(0.0)
+            )
+        }]
+    }));
+    blocks
+}
+
+/// Sends the analysis of an article to the Slack channel of each topic it matched, as a
+/// single Block Kit message. The first article in a story cluster starts a thread; later
+/// articles in the same cluster are posted as replies to it. New threads are subject to
+/// the channel's rate limit; articles held back by it count as posted. Returns whether it
+/// was posted to every channel.
 pub async fn send_to_slack(
-    article: &str,
-    response: &str,
+    db: &Database,
+    headline: &str,
+    response_json: &Value,
+    cluster_id: Option<i64>,
     slack_token: &str,
     default_channel: &str,
-) {
-    let topics = std::env::var("TOPICS")
-        .unwrap_or_default()
-        .replace('\n', "");
-    let topic_mappings: HashMap<&str, (&str, Option<&str>)> = topics
-        .split(';')
-        .filter_map(|entry| {
-            let mut parts = entry.trim().splitn(3, ':');
-            let topic_name = parts.next()?.trim();
-            let topic_prompt = parts.next()?.trim();
-            let channel = parts.next().map(|ch| ch.trim());
-            Some((topic_name, (topic_prompt, channel)))
-        })
-        .collect();
-
+) -> bool {
     let client = Client::new();
     let worker_id = format!("{:?}", std::thread::current().id());
 
-    let response_json: serde_json::Value = match serde_json::from_str(response) {
-        Ok(json) => json,
-        Err(err) => {
-            error!(target: TARGET_WEB_REQUEST, "Worker {}: Failed to parse response JSON: {:?}", worker_id, err);
-            return;
+    // Post once to each distinct channel of the topics the article matched
+    let mut channels: Vec<(&str, Vec<String>)> = Vec::new();
+    for topic in topics::topics_in_response(response_json) {
+        let channel = topics::get(&topic)
+            .and_then(|topic| topic.slack_channel.as_deref())
+            .unwrap_or(default_channel);
+        match channels.iter_mut().find(|(name, _)| *name == channel) {
+            Some((_, channel_topics)) => channel_topics.push(topic),
+            None => channels.push((channel, vec![topic])),
         }
-    };
+    }
+    if channels.is_empty() {
+        let topic = response_json["topic"].as_str().unwrap_or("").to_string();
+        channels.push((default_channel, vec![topic]));
+    }
 
-    let topic = response_json["topic"]
-        .as_str()
-        .unwrap_or("No topic available")
-        .trim();
-    let channel = topic_mappings
-        .get(topic)
-        .and_then(|(_, channel)| *channel)
-        .unwrap_or(default_channel);
-
-    let tiny_title = deduplicate_markdown(response_json["tiny_title"].as_str().unwrap_or(""));
-    let tiny_summary = deduplicate_markdown(response_json["tiny_summary"].as_str().unwrap_or(""));
-    let summary = deduplicate_markdown(response_json["summary"].as_str().unwrap_or(""));
-    let critical_analysis =
-        deduplicate_markdown(response_json["critical_analysis"].as_str().unwrap_or(""));
-    let logical_fallacies =
-        deduplicate_markdown(response_json["logical_fallacies"].as_str().unwrap_or(""));
-    let relation_to_topic =
-        deduplicate_markdown(response_json["relation_to_topic"].as_str().unwrap_or(""));
-    let source_analysis =
-        deduplicate_markdown(response_json["source_analysis"].as_str().unwrap_or(""));
-    let additional_insights = deduplicate_markdown(
-        response_json["additional_insights"]
-            .as_str()
-            .unwrap_or("")
-            .trim(),
-    );
-    let model = response_json["model"]
+    let article_id = response_json["id"].as_i64();
+    let fallback_text = response_json["tiny_title"]
         .as_str()
-        .unwrap_or("Unknown model")
-        .to_string();
-    let elapsed_time = response_json["elapsed_time"].as_f64().unwrap_or(0.0);
+        .filter(|title| !title.is_empty())
+        .map_or_else(|| deduplicate_markdown(headline), str::to_string);
 
-    // **Step 1: Send the initial message**
-    let first_payload = json!({
-        "channel": channel,
-        "blocks": [
-            {
-                "type": "section",
-                "text": {
-                    "type": "mrkdwn",
-                    "text": format!("*{}*\n{}", tiny_title, tiny_summary),
-                }
-            },
-        ],
-        "unfurl_links": false,
-        "unfurl_media": false,
-    });
+    let article_url = response_json["url"].as_str().unwrap_or("");
+    let is_alert = response_json.get("threat").is_some();
 
-    if let Some(slack_response_json) =
-        send_slack_message(&client, slack_token, &first_payload, &worker_id).await
-    {
-        let ts = slack_response_json["ts"].as_str().unwrap_or("").to_string();
-        if ts.is_empty() {
-            error!(target: TARGET_WEB_REQUEST, "Worker {}: Invalid ts returned from Slack", worker_id);
-            return;
-        }
+    let mut posted = true;
+    for (channel, channel_topics) in channels {
+        let thread_ts = match cluster_id {
+            Some(cluster_id) => cluster_thread(db, cluster_id, channel).await,
+            None => None,
+        };
 
-        // **Step 2: Send remaining content block by block in the thread**
-        let sections = vec![
-            ("*Article*", article.to_string()),
-            ("*Relevance*", relation_to_topic),
-            ("*Summary*", summary),
-            ("*Critical Analysis*", critical_analysis),
-            ("*Logical Fallacies*", logical_fallacies),
-            ("*Source Analysis*", source_analysis),
-            ("*Argus Speaks*", additional_insights),
-        ];
-
-        // In the sections loop, add chunking for long content:
-        for (title, content) in sections {
-            if !content.is_empty() {
-                // Split long content into chunks of approximately 3000 characters
-                // (Slack's limit is around 4000, but we'll be conservative)
-                let chunks: Vec<&str> = content
-                    .as_bytes()
-                    .chunks(3000)
-                    .map(|chunk| std::str::from_utf8(chunk).unwrap_or(""))
-                    .collect();
-
-                for (i, chunk) in chunks.iter().enumerate() {
-                    let section_title = if chunks.len() > 1 {
-                        format!("{} (Part {})", title, i + 1)
-                    } else {
-                        title.to_string()
-                    };
+        // Replies in a cluster's thread don't notify the channel, so only new threads
+        // count towards its rate limit. Life safety alerts are always posted.
+        let throttled = thread_ts.is_none() && !is_alert;
+        let recipient = Recipient::Channel(channel);
+        let candidate = Candidate {
+            topic: &channel_topics[0],
+            cluster_id,
+            article_url,
+            title: &fallback_text,
+        };
+        if throttled && throttle::admit(db, &recipient, &candidate).await != Verdict::Send {
+            continue;
+        }
 
-                    let thread_payload = json!({
-                        "channel": channel,
-                        "thread_ts": ts,
-                        "blocks": [
-                            { "type": "divider" },
-                            {
-                                "type": "section",
-                                "text": { "type": "mrkdwn", "text": format!("{}\n{}", section_title, chunk) }
-                            }
-                        ],
-                        "unfurl_links": false,
-                        "unfurl_media": false,
-                    });
-                    send_slack_message(&client, slack_token, &thread_payload, &worker_id).await;
-                    tokio::time::sleep(Duration::from_secs(1)).await;
-                }
-            }
+        // Readers rate the article under the topics it was posted to this channel for
+        let mut blocks = article_blocks(headline, response_json);
+        if let Some(article_id) = article_id {
+            blocks.push(feedback_block(article_id, &channel_topics));
         }
 
-        // **Step 3: Send final block with model details**
-        let final_payload = json!({
+        let mut payload = json!({
             "channel": channel,
-            "thread_ts": ts,
-            "blocks": [
-                { "type": "divider" },
-                {
-                    "type": "section",
-                    "text": {
-                        "type": "mrkdwn",
-                        "text": format!("_Generated using model _{} in {:.2} seconds._", model, elapsed_time)
-                    }
-                }
-            ],
+            "text": fallback_text,
+            "blocks": blocks,
             "unfurl_links": false,
             "unfurl_media": false,
         });
+        if let Some(thread_ts) = &thread_ts {
+            payload["thread_ts"] = json!(thread_ts);
+        }
+
+        let ts = send_slack_message(&client, slack_token, &payload, &worker_id)
+            .await
+            .filter(|response| response["ok"].as_bool().unwrap_or(false))
+            .and_then(|response| response["ts"].as_str().map(str::to_string));
+        let Some(ts) = ts else {
+            error!(target: TARGET_WEB_REQUEST, "Worker {}: Slack did not accept the message for {}", worker_id, channel);
+            posted = false;
+            continue;
+        };
+        if throttled {
+            throttle::record_sent(db, &recipient, &candidate).await;
+        }
 
-        send_slack_message(&client, slack_token, &final_payload, &worker_id).await;
+        if let (Some(cluster_id), None) = (cluster_id, &thread_ts) {
+            if let Err(e) = db.save_slack_thread(cluster_id, channel, &ts).await {
+                error!(target: TARGET_WEB_REQUEST, "Worker {}: Failed to save Slack thread for cluster {}: {:?}", worker_id, cluster_id, e);
+            }
+        }
     }
+    posted
+}
+
+/// Finds the thread for a cluster in a channel. Clusters that were merged into this one
+/// may already have a thread, which the merged cluster then takes over.
+async fn cluster_thread(db: &Database, cluster_id: i64, channel: &str) -> Option<String> {
+    let mut candidates = vec![cluster_id];
+    candidates.extend(
+        cluster::get_clusters_merged_into(db, cluster_id)
+            .await
+            .unwrap_or_default(),
+    );
+    for candidate in candidates {
+        match db.get_slack_thread(candidate, channel).await {
+            Ok(Some(ts)) => {
+                if candidate != cluster_id {
+                    if let Err(e) = db.save_slack_thread(cluster_id, channel, &ts).await {
+                        warn!(target: TARGET_WEB_REQUEST, "Failed to save Slack thread for cluster {}: {:?}", cluster_id, e);
+                    }
+                }
+                return Some(ts);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                warn!(target: TARGET_WEB_REQUEST, "Failed to look up Slack thread for cluster {}: {:?}", candidate, e);
+            }
+        }
+    }
+    None
+}
+
+/// Action IDs of the feedback buttons start with this, followed by the feedback kind.
+const FEEDBACK_ACTION_PREFIX: &str = "feedback_";
+/// Slack requests older than this many seconds are rejected to prevent replays.
+const MAX_REQUEST_AGE_SECS: i64 = 300;
+
+/// Buttons readers use to rate an analysis posted under `topics`.
+fn feedback_block(article_id: i64, topics: &[String]) -> Value {
+    let value = json!({ "article_id": article_id, "topics": topics }).to_string();
+    let buttons: Vec<Value> = FeedbackKind::ALL
+        .into_iter()
+        .map(|kind| {
+            let mut button = json!({
+                "type": "button",
+                "action_id": format!("{}{}", FEEDBACK_ACTION_PREFIX, kind.as_str()),
+                "text": { "type": "plain_text", "text": kind.label() },
+                "value": value,
+            });
+            match kind {
+                FeedbackKind::Great => button["style"] = json!("primary"),
+                FeedbackKind::NotRelevant => button["style"] = json!("danger"),
+                FeedbackKind::WrongTopic => {}
+            }
+            button
+        })
+        .collect();
+    json!({ "type": "actions", "block_id": "feedback", "elements": buttons })
+}
+
+/// A reader's click on one of the feedback buttons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlackFeedback {
+    pub article_id: i64,
+    pub topics: Vec<String>,
+    pub kind: FeedbackKind,
+    /// Slack ID of the reader
+    pub user_id: String,
+    /// Where to reply to the reader
+    pub response_url: Option<String>,
+}
+
+/// Checks the `X-Slack-Signature` of an interaction request: `v0=` and the hex
+/// HMAC-SHA256 of `v0:{timestamp}:{body}` with the app's signing secret.
+pub fn verify_slack_signature(
+    signing_secret: &str,
+    timestamp: &str,
+    body: &str,
+    signature: &str,
+    now: i64,
+) -> bool {
+    let Ok(sent_at) = timestamp.parse::<i64>() else {
+        return false;
+    };
+    if (now - sent_at).abs() > MAX_REQUEST_AGE_SECS {
+        return false;
+    }
+    let Some(tag) = signature.strip_prefix("v0=").and_then(decode_hex) else {
+        return false;
+    };
+    let key = hmac::Key::new(hmac::HMAC_SHA256, signing_secret.as_bytes());
+    hmac::verify(&key, format!("v0:{}:{}", timestamp, body).as_bytes(), &tag).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Reads a feedback vote from the form-encoded body Slack posts when a button is clicked.
+/// Returns `None` for any other interaction.
+pub fn parse_feedback_interaction(body: &str) -> Option<SlackFeedback> {
+    let payload = form_urlencoded::parse(body.as_bytes())
+        .find(|(key, _)| key == "payload")
+        .map(|(_, value)| value.into_owned())?;
+    let payload: Value = serde_json::from_str(&payload).ok()?;
+    if payload["type"] != "block_actions" {
+        return None;
+    }
+
+    let action = payload["actions"].as_array()?.iter().find(|action| {
+        action["action_id"]
+            .as_str()
+            .is_some_and(|id| id.starts_with(FEEDBACK_ACTION_PREFIX))
+    })?;
+    let kind = FeedbackKind::from_name(
+        action["action_id"]
+            .as_str()?
+            .trim_start_matches(FEEDBACK_ACTION_PREFIX),
+    )?;
+    let value: Value = serde_json::from_str(action["value"].as_str()?).ok()?;
+    let topics: Vec<String> = value["topics"]
+        .as_array()?
+        .iter()
+        .filter_map(|topic| topic.as_str().map(str::to_string))
+        .collect();
+
+    Some(SlackFeedback {
+        article_id: value["article_id"].as_i64()?,
+        topics,
+        kind,
+        user_id: payload["user"]["id"].as_str()?.to_string(),
+        response_url: payload["response_url"].as_str().map(str::to_string),
+    })
+}
+
+/// Thanks a reader for their feedback with a message only they can see.
+pub async fn acknowledge_feedback(response_url: &str, kind: FeedbackKind) {
+    let payload = json!({
+        "response_type": "ephemeral",
+        "replace_original": false,
+        "text": format!("Thanks, your feedback ({}) was recorded.", kind.label().to_lowercase()),
+    });
+    if let Err(e) = Client::new()
+        .post(response_url)
+        .json(&payload)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+    {
+        warn!(target: TARGET_WEB_REQUEST, "Failed to acknowledge Slack feedback: {:?}", e);
+    }
+}
+
+/// Sends a standalone notice, such as an all-clear or a digest, to a Slack channel or,
+/// given a user ID, as a direct message. Long notices are split across sections. Returns
+/// whether Slack accepted it.
+pub async fn send_slack_notice(text: &str, slack_token: &str, channel: &str) -> bool {
+    let client = Client::new();
+    let worker_id = format!("{:?}", std::thread::current().id());
+    let blocks: Vec<Value> = chunk_text(&deduplicate_markdown(text), MAX_SECTION_CHARS)
+        .into_iter()
+        .take(MAX_BLOCKS)
+        .map(|chunk| json!({ "type": "section", "text": { "type": "mrkdwn", "text": chunk } }))
+        .collect();
+    let payload = json!({
+        "channel": channel,
+        "blocks": blocks,
+        "unfurl_links": false,
+        "unfurl_media": false,
+    });
+    send_slack_message(&client, slack_token, &payload, &worker_id)
+        .await
+        .is_some_and(|response| response["ok"].as_bool().unwrap_or(false))
 }
 
 // Helper function to send a Slack message with retries
@@ -307,3 +571,126 @@ async fn send_slack_message(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_article_blocks() {
+        let long_analysis = "Paragraph.\n\n".repeat(400);
+        let analysis = json!({
+            "tiny_title": "Rust 2.0 announced",
+            "tiny_summary": "The Rust team announced **Rust 2.0**.",
+            "summary": "A summary.",
+            "critical_analysis": long_analysis,
+            "logical_fallacies": "",
+            "quality": 3,
+            "sources_quality": 2,
+            "argument_quality": 3,
+            "source_type": "official",
+            "model": "test-model",
+            "elapsed_time": 1.5
+        });
+        let blocks = article_blocks("*<https://example.com|Rust>*", &analysis);
+
+        assert_eq!(blocks[0]["type"], "header");
+        assert_eq!(blocks[0]["text"]["text"], "Rust 2.0 announced");
+        assert_eq!(
+            blocks[1]["text"]["text"],
+            "*<https://example.com|Rust>*\nThe Rust team announced *Rust 2.0*."
+        );
+        assert!(blocks[2]["elements"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("High quality"));
+
+        // Long sections are split to fit Slack's limit, empty ones are left out
+        let texts: Vec<&str> = blocks
+            .iter()
+            .filter_map(|block| block["text"]["text"].as_str())
+            .collect();
+        assert!(texts
+            .iter()
+            .all(|text| text.chars().count() <= MAX_SECTION_CHARS));
+        assert!(texts
+            .iter()
+            .any(|text| text.starts_with("*Critical Analysis (Part 2)*")));
+        assert!(!texts
+            .iter()
+            .any(|text| text.starts_with("*Logical Fallacies*")));
+        assert!(blocks.len() <= MAX_BLOCKS);
+        assert_eq!(blocks.last().unwrap()["type"], "context");
+    }
+
+    #[test]
+    fn test_chunk_text() {
+        assert_eq!(chunk_text("short", 10), vec!["short"]);
+        assert_eq!(
+            chunk_text("one\ntwo three", 8),
+            vec!["one", "two thre", "e"]
+        );
+        assert_eq!(chunk_text("ééééé", 2), vec!["éé", "éé", "é"]);
+        assert!(chunk_text("  ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_feedback_interaction() {
+        let block = feedback_block(42, &["Rust".to_string()]);
+        let button = &block["elements"][0];
+        let payload = json!({
+            "type": "block_actions",
+            "user": { "id": "U123" },
+            "response_url": "https://hooks.slack.com/actions/1",
+            "actions": [{ "action_id": button["action_id"], "value": button["value"] }]
+        });
+        let body: String = form_urlencoded::Serializer::new(String::new())
+            .append_pair("payload", &payload.to_string())
+            .finish();
+
+        assert_eq!(
+            parse_feedback_interaction(&body),
+            Some(SlackFeedback {
+                article_id: 42,
+                topics: vec!["Rust".to_string()],
+                kind: FeedbackKind::NotRelevant,
+                user_id: "U123".to_string(),
+                response_url: Some("https://hooks.slack.com/actions/1".to_string()),
+            })
+        );
+        assert_eq!(parse_feedback_interaction("payload=%7B%7D"), None);
+
+        // Example from Slack's documentation on verifying requests
+        let secret = "8f742231b10e8888abcd99yyyzzz85a5";
+        let body = "token=xyzz0WbapA4vBCDEFasx0q6G&team_id=T1DC2JH3J&team_domain=testteamnow&channel_id=G8PSS9T3V&channel_name=foobar&user_id=U2CERLKJA&user_name=roadrunner&command=%2Fwebhook-collect&text=&response_url=https%3A%2F%2Fhooks.slack.com%2Fcommands%2FT1DC2JH3J%2F397700885554%2F96rGlfmibIGlgcZRskXaIFfN&trigger_id=398738663015.47445629121.803a0bc887a14d10d2c447fce8b6703c";
+        let signature = "v0=a2114d57b48eac39b9ad189dd8316235a7b4a8d21a10bd27519666489c69b503";
+        assert!(verify_slack_signature(
+            secret,
+            "1531420618",
+            body,
+            signature,
+            1531420618
+        ));
+        assert!(!verify_slack_signature(
+            secret,
+            "1531420618",
+            body,
+            signature,
+            1531429999
+        ));
+        assert!(!verify_slack_signature(
+            "wrong",
+            "1531420618",
+            body,
+            signature,
+            1531420618
+        ));
+        assert!(!verify_slack_signature(
+            secret,
+            "1531420618",
+            body,
+            "v0=zz",
+            1531420618
+        ));
+    }
+}
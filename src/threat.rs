@@ -0,0 +1,300 @@
+//! Threat classification for life safety alerts: what kind of threat it is, how severe,
+//! when it applies and how sure the model is, and the notification urgency that follows.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Threats without a known end are treated as over this many hours after they start.
+const DEFAULT_THREAT_DURATION_HOURS: i64 = 72;
+
+/// Below this confidence a threat is notified one urgency level lower.
+const LOW_CONFIDENCE: f32 = 0.5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreatCategory {
+    /// Storms, tornadoes, floods, heat waves and other weather events
+    Weather,
+    /// Earthquakes, tsunamis, volcanic eruptions and landslides
+    Geophysical,
+    /// Wildfires and large fires
+    Fire,
+    /// Disease outbreaks and contamination of food or water
+    Health,
+    /// War, terrorism, shootings and civil unrest
+    Conflict,
+    /// Failures of power, water, transport or communications
+    Infrastructure,
+    /// Chemical, biological, radiological or nuclear releases
+    Hazmat,
+    #[default]
+    #[serde(other)]
+    Other,
+}
+
+/// Severity levels, following the Common Alerting Protocol.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreatSeverity {
+    /// Minimal threat to life or property
+    Minor,
+    /// Possible threat to life or property
+    Moderate,
+    /// Significant threat to life or property
+    Severe,
+    /// Extraordinary threat to life or property
+    Extreme,
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+/// What people are advised to do, following the Common Alerting Protocol.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreatResponse {
+    /// Stay informed
+    Monitor,
+    /// Get ready to act
+    Prepare,
+    /// Stay away from the area
+    Avoid,
+    /// Take shelter in place
+    Shelter,
+    /// Leave the area
+    Evacuate,
+    /// The threat is over
+    AllClear,
+    #[default]
+    #[serde(other)]
+    None,
+}
+
+/// How urgently a threat should be brought to people's attention.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Routine,
+    Elevated,
+    Immediate,
+}
+
+impl ThreatCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThreatCategory::Weather => "weather",
+            ThreatCategory::Geophysical => "geophysical",
+            ThreatCategory::Fire => "fire",
+            ThreatCategory::Health => "health",
+            ThreatCategory::Conflict => "conflict",
+            ThreatCategory::Infrastructure => "infrastructure",
+            ThreatCategory::Hazmat => "hazmat",
+            ThreatCategory::Other => "other",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        serde_json::from_value(json!(name.to_lowercase())).unwrap_or_default()
+    }
+}
+
+impl ThreatSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThreatSeverity::Minor => "minor",
+            ThreatSeverity::Moderate => "moderate",
+            ThreatSeverity::Severe => "severe",
+            ThreatSeverity::Extreme => "extreme",
+            ThreatSeverity::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        serde_json::from_value(json!(name.to_lowercase())).unwrap_or_default()
+    }
+
+    /// Orders severities from unknown (0) to extreme (4).
+    pub fn rank(&self) -> u8 {
+        match self {
+            ThreatSeverity::Unknown => 0,
+            ThreatSeverity::Minor => 1,
+            ThreatSeverity::Moderate => 2,
+            ThreatSeverity::Severe => 3,
+            ThreatSeverity::Extreme => 4,
+        }
+    }
+}
+
+impl ThreatResponse {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThreatResponse::Monitor => "monitor",
+            ThreatResponse::Prepare => "prepare",
+            ThreatResponse::Avoid => "avoid",
+            ThreatResponse::Shelter => "shelter",
+            ThreatResponse::Evacuate => "evacuate",
+            ThreatResponse::AllClear => "all_clear",
+            ThreatResponse::None => "none",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        serde_json::from_value(json!(name.to_lowercase())).unwrap_or_default()
+    }
+}
+
+impl Urgency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Urgency::Routine => "routine",
+            Urgency::Elevated => "elevated",
+            Urgency::Immediate => "immediate",
+        }
+    }
+}
+
+/// What the model reported about a threat, alongside where it is.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, JsonSchema)]
+pub struct ThreatDetails {
+    #[serde(default)]
+    pub category: ThreatCategory,
+    #[serde(default)]
+    pub severity: ThreatSeverity,
+    /// What people in the area are advised to do
+    #[serde(default)]
+    pub response: ThreatResponse,
+    /// When the threat starts or started, as an ISO 8601 date or date and time
+    #[serde(default)]
+    pub starts_at: Option<String>,
+    /// When the threat is expected to end, as an ISO 8601 date or date and time
+    #[serde(default)]
+    pub ends_at: Option<String>,
+    /// How confident the model is that this is a real, current threat, from 0 to 1
+    #[serde(default)]
+    pub confidence: Option<f32>,
+}
+
+impl ThreatDetails {
+    /// When alerts about the threat stop being useful: its end if known, otherwise a few
+    /// days after it starts. `None` if neither is known.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        if let Some(ends_at) = self.ends_at.as_deref().and_then(|s| parse_time(s, true)) {
+            return Some(ends_at);
+        }
+        self.starts_at
+            .as_deref()
+            .and_then(|s| parse_time(s, false))
+            .map(|starts_at| starts_at + chrono::Duration::hours(DEFAULT_THREAT_DURATION_HOURS))
+    }
+
+    /// Whether the threat is over. All-clear notices never expire, as they are what
+    /// closes an incident.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.response != ThreatResponse::AllClear
+            && self.expires_at().is_some_and(|expires_at| expires_at < now)
+    }
+
+    /// Extreme threats, and severe ones that are under way or due within a day, are
+    /// immediate. Other severe and moderate threats are elevated. A low confidence
+    /// lowers the urgency by one level.
+    pub fn urgency(&self, now: DateTime<Utc>) -> Urgency {
+        let imminent = self
+            .starts_at
+            .as_deref()
+            .and_then(|s| parse_time(s, false))
+            .is_none_or(|starts_at| starts_at - now < chrono::Duration::hours(24));
+        let urgency = match self.severity {
+            ThreatSeverity::Extreme => Urgency::Immediate,
+            ThreatSeverity::Severe if imminent => Urgency::Immediate,
+            ThreatSeverity::Severe | ThreatSeverity::Moderate => Urgency::Elevated,
+            ThreatSeverity::Minor | ThreatSeverity::Unknown => Urgency::Routine,
+        };
+        if self.confidence.is_some_and(|c| c < LOW_CONFIDENCE) {
+            match urgency {
+                Urgency::Immediate => Urgency::Elevated,
+                _ => Urgency::Routine,
+            }
+        } else {
+            urgency
+        }
+    }
+
+    /// The details and resulting urgency, as included in analysis results.
+    pub fn to_json(&self, now: DateTime<Utc>) -> Value {
+        json!({
+            "category": self.category.as_str(),
+            "severity": self.severity.as_str(),
+            "response": self.response.as_str(),
+            "starts_at": self.starts_at,
+            "ends_at": self.ends_at,
+            "confidence": self.confidence,
+            "urgency": self.urgency(now).as_str(),
+        })
+    }
+}
+
+/// Parses an RFC 3339 time, a naive date and time (taken as UTC) or a date. A bare date
+/// means the start of the day, or its end if `end_of_day` is set.
+fn parse_time(value: &str, end_of_day: bool) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(time.and_utc());
+        }
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    let time = if end_of_day {
+        date.and_hms_opt(23, 59, 59)?
+    } else {
+        date.and_hms_opt(0, 0, 0)?
+    };
+    Some(time.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threat_details_urgency_and_expiry() {
+        let now = DateTime::parse_from_rfc3339("2025-03-10T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let details: ThreatDetails = serde_json::from_str(
+            r#"{"category": "hazmat", "severity": "severe", "response": "shelter", "starts_at": "2025-03-10T08:00:00Z",
+                "ends_at": "2025-03-11", "confidence": 0.9}"#,
+        )
+        .unwrap();
+        assert_eq!(details.category, ThreatCategory::Hazmat);
+        assert_eq!(details.response, ThreatResponse::Shelter);
+        assert_eq!(details.urgency(now), Urgency::Immediate);
+        assert!(!details.is_expired(now));
+        assert!(details.is_expired(now + chrono::Duration::days(2)));
+
+        let watch = ThreatDetails {
+            category: ThreatCategory::from_name("Weather"),
+            severity: ThreatSeverity::Severe,
+            starts_at: Some("2025-03-12".to_string()),
+            confidence: Some(0.4),
+            ..Default::default()
+        };
+        assert_eq!(watch.urgency(now), Urgency::Routine);
+        assert_eq!(
+            watch.expires_at().unwrap().to_rfc3339(),
+            "2025-03-15T00:00:00+00:00"
+        );
+
+        let unknown: ThreatDetails =
+            serde_json::from_str(r#"{"category": "aliens", "severity": "dire"}"#).unwrap();
+        assert_eq!(unknown.category, ThreatCategory::Other);
+        assert_eq!(unknown.severity, ThreatSeverity::Unknown);
+        assert_eq!(unknown.response, ThreatResponse::None);
+        assert_eq!(unknown.expires_at(), None);
+    }
+}
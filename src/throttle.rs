@@ -0,0 +1,422 @@
+//! Rate limits, batching and deduplication of notifications per device and Slack channel.
+//!
+//! Each device and channel may be notified a limited number of times per hour. Articles
+//! beyond the limit are held back and later collapsed into a single "N more stories on X"
+//! notification per topic. A recipient that was already notified about a story cluster
+//! through a sibling article is not notified again, and a device following a cluster gets
+//! at most one story update about it a day. Life safety alerts bypass all of this.
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::collections::HashSet;
+use std::env;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info, warn};
+
+use crate::app::push::{Interruption, PushMessage, Pusher};
+use crate::db::core::Database;
+use crate::db::throttle::HeldNotification;
+use crate::slack::send_slack_notice;
+use crate::TARGET_WEB_REQUEST;
+
+const DEVICE_NOTIFICATIONS_PER_HOUR_ENV: &str = "DEVICE_NOTIFICATIONS_PER_HOUR";
+const CHANNEL_NOTIFICATIONS_PER_HOUR_ENV: &str = "CHANNEL_NOTIFICATIONS_PER_HOUR";
+
+const DEFAULT_DEVICE_NOTIFICATIONS_PER_HOUR: i64 = 4;
+const DEFAULT_CHANNEL_NOTIFICATIONS_PER_HOUR: i64 = 20;
+
+/// Sibling articles in a story cluster are suppressed for this long after the first
+/// notification about it.
+const CLUSTER_DEDUP_HOURS: i64 = 24;
+
+/// How long notifications are held before they go out as a batch.
+const BATCH_DELAY_MINUTES: i64 = 30;
+
+/// How often held notifications are checked.
+const BATCH_CHECK_INTERVAL_SECS: u64 = 60;
+
+/// The topic story updates are limited and batched under.
+pub const STORY_UPDATE_TOPIC: &str = "Followed stories";
+
+/// Titles listed in a batch before the rest are only counted.
+const MAX_BATCH_TITLES: usize = 5;
+
+static DEVICE_LIMIT: Lazy<i64> = Lazy::new(|| {
+    limit_from_env(
+        DEVICE_NOTIFICATIONS_PER_HOUR_ENV,
+        DEFAULT_DEVICE_NOTIFICATIONS_PER_HOUR,
+    )
+});
+static CHANNEL_LIMIT: Lazy<i64> = Lazy::new(|| {
+    limit_from_env(
+        CHANNEL_NOTIFICATIONS_PER_HOUR_ENV,
+        DEFAULT_CHANNEL_NOTIFICATIONS_PER_HOUR,
+    )
+});
+
+fn limit_from_env(name: &str, default: i64) -> i64 {
+    match env::var(name) {
+        Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+            warn!("Invalid {}; defaulting to {}", name, default);
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+/// Who a notification goes to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recipient<'a> {
+    /// A device's push token: an APNs device token, an FCM registration token, or a web
+    /// push endpoint
+    Device(&'a str),
+    /// A Slack channel ID
+    Channel(&'a str),
+}
+
+impl<'a> Recipient<'a> {
+    /// The key a recipient is stored under, e.g. "slack:C0123".
+    pub fn key(&self) -> String {
+        match self {
+            Recipient::Device(token) => format!("device:{}", token),
+            Recipient::Channel(channel) => format!("slack:{}", channel),
+        }
+    }
+
+    pub fn from_key(key: &'a str) -> Option<Self> {
+        if let Some(token) = key.strip_prefix("device:") {
+            Some(Recipient::Device(token))
+        } else {
+            key.strip_prefix("slack:").map(Recipient::Channel)
+        }
+    }
+
+    /// Notifications allowed per hour; 0 means unlimited.
+    fn limit(&self) -> i64 {
+        match self {
+            Recipient::Device(_) => *DEVICE_LIMIT,
+            Recipient::Channel(_) => *CHANNEL_LIMIT,
+        }
+    }
+}
+
+/// An article about to be sent to a recipient. Without a cluster, the article URL is what a
+/// recipient mustn't hear about twice.
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate<'a> {
+    pub topic: &'a str,
+    pub cluster_id: Option<i64>,
+    pub article_url: &'a str,
+    pub title: &'a str,
+}
+
+/// What to do with a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Send,
+    /// The recipient already heard about this story
+    Duplicate,
+    /// The recipient is over its limit; the notification goes out in a batch
+    Hold,
+}
+
+/// Decides on a notification given how many the recipient got in the last hour, its
+/// limit, and whether it already heard about the story.
+pub fn decide(sent_last_hour: i64, limit: i64, story_seen: bool) -> Verdict {
+    if story_seen {
+        Verdict::Duplicate
+    } else if limit > 0 && sent_last_hour >= limit {
+        Verdict::Hold
+    } else {
+        Verdict::Send
+    }
+}
+
+/// Decides whether a recipient gets a notification now. Held notifications are recorded
+/// to wait for the next batch; a notification to send only counts towards the limit once
+/// it is delivered and passed to [`record_sent`]. Fails open, so a database error never
+/// stops a notification.
+pub async fn admit(db: &Database, recipient: &Recipient<'_>, candidate: &Candidate<'_>) -> Verdict {
+    let key = recipient.key();
+    let now = Utc::now().timestamp();
+
+    let story_seen = match candidate.cluster_id {
+        Some(cluster_id) => db
+            .cluster_notified_since(&key, cluster_id, now - CLUSTER_DEDUP_HOURS * 3600)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(target: TARGET_WEB_REQUEST, "Failed to check earlier notifications for {}: {:?}", key, e);
+                false
+            }),
+        None => db
+            .article_notified_since(&key, candidate.article_url, now - CLUSTER_DEDUP_HOURS * 3600)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(target: TARGET_WEB_REQUEST, "Failed to check earlier notifications for {}: {:?}", key, e);
+                false
+            }),
+    };
+    let sent_last_hour = db
+        .count_notifications_since(&key, now - 3600)
+        .await
+        .unwrap_or_else(|e| {
+            warn!(target: TARGET_WEB_REQUEST, "Failed to count notifications for {}: {:?}", key, e);
+            0
+        });
+
+    let verdict = decide(sent_last_hour, recipient.limit(), story_seen);
+    let recorded = match verdict {
+        Verdict::Send => Ok(()),
+        Verdict::Hold => {
+            info!(target: TARGET_WEB_REQUEST, "{} is over its limit, holding {}", key, candidate.article_url);
+            db.hold_notification(
+                &key,
+                candidate.topic,
+                candidate.cluster_id,
+                candidate.article_url,
+                candidate.title,
+            )
+            .await
+        }
+        Verdict::Duplicate => {
+            info!(target: TARGET_WEB_REQUEST, "{} already heard about cluster {:?} or {}, skipping it", key, candidate.cluster_id, candidate.article_url);
+            Ok(())
+        }
+    };
+    if let Err(e) = recorded {
+        error!(target: TARGET_WEB_REQUEST, "Failed to record notification for {}: {:?}", key, e);
+    }
+    verdict
+}
+
+/// The key story updates about a cluster are deduplicated under, in place of an article URL.
+pub fn story_update_key(cluster_id: i64) -> String {
+    format!("story-update:{}", cluster_id)
+}
+
+/// Records a notification the recipient was sent, counting it towards the recipient's
+/// limit and marking its story as seen.
+pub async fn record_sent(db: &Database, recipient: &Recipient<'_>, candidate: &Candidate<'_>) {
+    let key = recipient.key();
+    if let Err(e) = db
+        .log_notification(
+            &key,
+            candidate.topic,
+            candidate.cluster_id,
+            candidate.article_url,
+        )
+        .await
+    {
+        error!(target: TARGET_WEB_REQUEST, "Failed to record notification for {}: {:?}", key, e);
+    }
+}
+
+/// The title and lines of a batch of held notifications about one topic. Articles in the
+/// same story cluster count as one story.
+pub fn batch_summary(topic: &str, held: &[HeldNotification]) -> (String, Vec<String>) {
+    let mut stories = HashSet::new();
+    let mut titles = Vec::new();
+    for notification in held {
+        let story = match notification.cluster_id {
+            Some(cluster_id) => cluster_id.to_string(),
+            None => notification.article_url.clone(),
+        };
+        if stories.insert(story) && titles.len() < MAX_BATCH_TITLES {
+            titles.push(notification.title.clone());
+        }
+    }
+    let count = stories.len();
+    let title = if count == 1 {
+        format!("1 more story on {}", topic)
+    } else {
+        format!("{} more stories on {}", count, topic)
+    };
+    if count > titles.len() {
+        titles.push(format!("and {} more", count - titles.len()));
+    }
+    (title, titles)
+}
+
+/// Sends every batch whose oldest notification has waited long enough. Batches that
+/// can't be sent are kept and tried again on the next run.
+pub async fn send_due_batches(db: &Database, slack_token: &str) {
+    let cutoff = Utc::now().timestamp() - BATCH_DELAY_MINUTES * 60;
+    let held = match db.fetch_due_batches(cutoff).await {
+        Ok(held) => held,
+        Err(e) => {
+            error!(target: TARGET_WEB_REQUEST, "Failed to fetch held notifications: {:?}", e);
+            return;
+        }
+    };
+
+    // Held notifications come ordered by recipient and topic
+    for batch in held.chunk_by(|a, b| a.recipient == b.recipient && a.topic == b.topic) {
+        let (recipient_key, topic) = (&batch[0].recipient, &batch[0].topic);
+        let ids: Vec<i64> = batch.iter().map(|notification| notification.id).collect();
+        let Some(recipient) = Recipient::from_key(recipient_key) else {
+            warn!(target: TARGET_WEB_REQUEST, "Dropping batch for unknown recipient {}", recipient_key);
+            if let Err(e) = db.release_held_notifications(&ids).await {
+                error!(target: TARGET_WEB_REQUEST, "Failed to drop batch for {}: {:?}", recipient_key, e);
+            }
+            continue;
+        };
+        let (title, lines) = batch_summary(topic, batch);
+
+        let sent = match recipient {
+            Recipient::Channel(channel) => {
+                let text = format!("*{}*\n• {}", title, lines.join("\n• "));
+                send_slack_notice(&text, slack_token, channel).await
+            }
+            Recipient::Device(device_token) => {
+                let Some(pusher) = Pusher::shared() else {
+                    warn!(target: TARGET_WEB_REQUEST, "The app is not configured, keeping batch for {}", recipient_key);
+                    continue;
+                };
+                let data = json!({ "topic": topic, "batch": batch.len() });
+                let message = PushMessage {
+                    title,
+                    body: lines.join("\n"),
+                    data: data.as_object().cloned().unwrap_or_default(),
+                    interruption: Interruption::Passive,
+                    high_priority: false,
+                    sound: false,
+                    content_available: false,
+                };
+                pusher.push(db, device_token, &message).await
+            }
+        };
+
+        if sent {
+            info!(target: TARGET_WEB_REQUEST, "Sent batch of {} held notifications to {}", batch.len(), recipient_key);
+            if let Err(e) = db.release_held_notifications(&ids).await {
+                error!(target: TARGET_WEB_REQUEST, "Failed to release batch for {}: {:?}", recipient_key, e);
+            }
+            if let Err(e) = db
+                .log_notification(recipient_key, topic, None, &batch[0].article_url)
+                .await
+            {
+                error!(target: TARGET_WEB_REQUEST, "Failed to record batch for {}: {:?}", recipient_key, e);
+            }
+        } else {
+            error!(target: TARGET_WEB_REQUEST, "Failed to send batch of {} held notifications to {}, keeping it", batch.len(), recipient_key);
+        }
+    }
+}
+
+/// Sends held notifications in batches and forgets old ones, every minute.
+pub async fn batch_loop(slack_token: String) -> anyhow::Result<()> {
+    let db = Database::instance().await;
+    loop {
+        send_due_batches(db, &slack_token).await;
+        let before = Utc::now().timestamp() - CLUSTER_DEDUP_HOURS * 3600;
+        if let Err(e) = db.prune_notification_log(before).await {
+            warn!(target: TARGET_WEB_REQUEST, "Failed to prune the notification log: {:?}", e);
+        }
+        match db.prune_held_notifications(before).await {
+            Ok(0) => {}
+            Ok(dropped) => {
+                warn!(target: TARGET_WEB_REQUEST, "Gave up on {} held notifications that could not be sent", dropped)
+            }
+            Err(e) => {
+                warn!(target: TARGET_WEB_REQUEST, "Failed to prune held notifications: {:?}", e)
+            }
+        }
+        sleep(Duration::from_secs(BATCH_CHECK_INTERVAL_SECS)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decide_and_batch_summary() {
+        assert_eq!(decide(0, 4, false), Verdict::Send);
+        assert_eq!(decide(3, 4, false), Verdict::Send);
+        assert_eq!(decide(4, 4, false), Verdict::Hold);
+        assert_eq!(decide(0, 4, true), Verdict::Duplicate);
+        assert_eq!(decide(100, 0, false), Verdict::Send);
+
+        assert_eq!(
+            Recipient::from_key(&Recipient::Channel("C0123").key()),
+            Some(Recipient::Channel("C0123"))
+        );
+        assert_eq!(
+            Recipient::from_key("device:abc"),
+            Some(Recipient::Device("abc"))
+        );
+        assert_eq!(Recipient::from_key("pager:abc"), None);
+
+        let held = |cluster_id, n: usize| HeldNotification {
+            id: n as i64,
+            recipient: "slack:C0123".to_string(),
+            topic: "Rust".to_string(),
+            cluster_id,
+            article_url: format!("https://example.com/{}", n),
+            title: format!("Story {}", n),
+        };
+        let (title, lines) = batch_summary("Rust", &[held(Some(1), 1), held(Some(1), 2)]);
+        assert_eq!(title, "1 more story on Rust");
+        assert_eq!(lines, vec!["Story 1"]);
+
+        let batch: Vec<HeldNotification> = (1..=7).map(|n| held(None, n)).collect();
+        let (title, lines) = batch_summary("Rust", &batch);
+        assert_eq!(title, "7 more stories on Rust");
+        assert_eq!(lines.len(), MAX_BATCH_TITLES + 1);
+        assert_eq!(lines[MAX_BATCH_TITLES], "and 2 more");
+    }
+
+    #[tokio::test]
+    async fn test_only_delivered_notifications_count() {
+        let db = Database::in_memory().await;
+        let recipient = Recipient::Device("device-token");
+        let candidate = Candidate {
+            topic: "Rust",
+            cluster_id: Some(7),
+            article_url: "https://example.com/a",
+            title: "Rust 2.0",
+        };
+        let since = Utc::now().timestamp() - 3600;
+
+        assert_eq!(admit(&db, &recipient, &candidate).await, Verdict::Send);
+        assert_eq!(
+            db.count_notifications_since(&recipient.key(), since)
+                .await
+                .unwrap(),
+            0
+        );
+        // A failed delivery leaves the story unseen, so the next article gets through
+        assert_eq!(admit(&db, &recipient, &candidate).await, Verdict::Send);
+
+        record_sent(&db, &recipient, &candidate).await;
+        assert_eq!(
+            db.count_notifications_since(&recipient.key(), since)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(admit(&db, &recipient, &candidate).await, Verdict::Duplicate);
+    }
+
+    #[tokio::test]
+    async fn test_story_updates_are_deduplicated_per_cluster() {
+        let db = Database::in_memory().await;
+        let recipient = Recipient::Device("device-token");
+        let (first, other) = (story_update_key(1), story_update_key(2));
+        let update = |key| Candidate {
+            topic: STORY_UPDATE_TOPIC,
+            cluster_id: None,
+            article_url: key,
+            title: "New developments",
+        };
+
+        assert_eq!(admit(&db, &recipient, &update(&first)).await, Verdict::Send);
+        record_sent(&db, &recipient, &update(&first)).await;
+        assert_eq!(
+            admit(&db, &recipient, &update(&first)).await,
+            Verdict::Duplicate
+        );
+        assert_eq!(admit(&db, &recipient, &update(&other)).await, Verdict::Send);
+    }
+}
@@ -0,0 +1,377 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::language::language_allowed;
+
+const TOPICS_JSON_PATH_ENV: &str = "TOPICS_JSON_PATH";
+const TOPICS_ENV: &str = "TOPICS";
+
+/// Topics that are not configured but are valid for device subscriptions.
+pub const ALERT_DIRECT_TOPIC: &str = "Alert: Direct";
+pub const ALERT_NEAR_TOPIC: &str = "Alert: Near";
+pub const TEST_TOPIC: &str = "Test";
+
+/// A configured topic and the settings that control how articles are matched and delivered.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Topic {
+    /// Short name, used as the article category and the subscription key.
+    pub name: String,
+    /// Description handed to the LLM when asking if an article is about this topic.
+    pub description: String,
+    /// If non-empty, the article must mention at least one of these (case-insensitive).
+    #[serde(default)]
+    pub required_keywords: Vec<String>,
+    /// The article is skipped if it mentions any of these (case-insensitive).
+    #[serde(default)]
+    pub excluded_keywords: Vec<String>,
+    /// Minimum combined quality score (-2 to 4) required before notifying.
+    #[serde(default)]
+    pub min_quality: Option<i8>,
+    /// Slack channel for this topic; the default channel is used if unset.
+    #[serde(default)]
+    pub slack_channel: Option<String>,
+    /// Whether matches are pushed to the iOS app.
+    #[serde(default = "default_push_eligible")]
+    pub push_eligible: bool,
+    /// ISO 639-1 codes of article languages to consider; empty means all languages.
+    #[serde(default)]
+    pub languages: Vec<String>,
+}
+
+/// A topic confirmed for an article, with how confident the match is (0.0 to 1.0).
+///
+/// Confidence is the article's embedding similarity to the topic description when the
+/// prefilter could score it, otherwise 0.0, so topics without an embedding never outrank
+/// topics that were scored.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct TopicMatch {
+    pub topic: String,
+    pub confidence: f32,
+}
+
+impl TopicMatch {
+    /// A match the LLM confirmed, with the prefilter's similarity if it had one.
+    pub fn confirmed(topic: &str, similarity: Option<f32>) -> Self {
+        TopicMatch {
+            topic: topic.to_string(),
+            confidence: similarity.unwrap_or(0.0).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Sorts matches most confident first, keeping the order of equally confident ones. The
+/// first is the article's primary topic.
+pub fn sort_by_confidence(matches: &mut [TopicMatch]) {
+    matches.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Parses the `matched_topics` JSON stored with a queued article, falling back to the
+/// single primary topic for entries queued before multi-topic matching.
+pub fn parse_topic_matches(matched_topics: Option<&str>, primary_topic: &str) -> Vec<TopicMatch> {
+    let matches: Vec<TopicMatch> = matched_topics
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default();
+    if matches.is_empty() {
+        vec![TopicMatch {
+            topic: primary_topic.to_string(),
+            confidence: 1.0,
+        }]
+    } else {
+        matches
+    }
+}
+
+/// Returns the topics an analysis was delivered under: the `topics` array if present,
+/// otherwise the single `topic` field.
+pub fn topics_in_response(response_json: &serde_json::Value) -> Vec<String> {
+    let topics: Vec<String> = response_json["topics"]
+        .as_array()
+        .map(|topics| {
+            topics
+                .iter()
+                .filter_map(|topic| topic.as_str().map(|t| t.trim().to_string()))
+                .filter(|topic| !topic.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    if !topics.is_empty() {
+        return topics;
+    }
+    response_json["topic"]
+        .as_str()
+        .map(|topic| vec![topic.trim().to_string()])
+        .unwrap_or_default()
+}
+
+fn default_push_eligible() -> bool {
+    true
+}
+
+impl Topic {
+    /// Creates a topic with default settings.
+    pub fn new(name: &str, description: &str) -> Self {
+        Topic {
+            name: name.to_string(),
+            description: description.to_string(),
+            required_keywords: Vec::new(),
+            excluded_keywords: Vec::new(),
+            min_quality: None,
+            slack_channel: None,
+            push_eligible: default_push_eligible(),
+            languages: Vec::new(),
+        }
+    }
+
+    /// Cheap pre-LLM check of keywords and language.
+    ///
+    /// Returns the reason the article was rejected, or `None` if the LLM should be asked.
+    pub fn prefilter_rejection(
+        &self,
+        article_text: &str,
+        language: Option<&str>,
+    ) -> Option<String> {
+        if !language_allowed(&self.languages, language) {
+            return Some(format!(
+                "language {} not in {:?}",
+                language.unwrap_or("unknown"),
+                self.languages
+            ));
+        }
+
+        let lowercase_text = article_text.to_lowercase();
+        if let Some(keyword) = self
+            .excluded_keywords
+            .iter()
+            .find(|k| lowercase_text.contains(&k.to_lowercase()))
+        {
+            return Some(format!("excluded keyword '{}'", keyword));
+        }
+        if !self.required_keywords.is_empty()
+            && !self
+                .required_keywords
+                .iter()
+                .any(|k| lowercase_text.contains(&k.to_lowercase()))
+        {
+            return Some("no required keyword".to_string());
+        }
+
+        None
+    }
+
+    /// Whether an analysis with this quality score should be sent out.
+    pub fn meets_quality(&self, quality: i8) -> bool {
+        self.min_quality.is_none_or(|min| quality >= min)
+    }
+}
+
+/// All configured topics, loaded once from `TOPICS_JSON_PATH` or the legacy `TOPICS` variable.
+///
+/// A missing or malformed topics file stops Argus at startup, rather than leaving it running
+/// with no topics and dropping every article as off-topic.
+static TOPICS: Lazy<Vec<Topic>> = Lazy::new(|| match load_topics() {
+    Ok(topics) => {
+        info!("Loaded {} topics", topics.len());
+        topics
+    }
+    Err(err) => panic!("Failed to load topics: {}", err),
+});
+
+/// Returns all configured topics.
+pub fn all() -> &'static [Topic] {
+    &TOPICS
+}
+
+/// Looks up a configured topic by name.
+pub fn get(name: &str) -> Option<&'static Topic> {
+    TOPICS.iter().find(|topic| topic.name == name)
+}
+
+/// Whether a device may subscribe to `name`: any configured topic, or one of the alert topics.
+pub fn is_valid_subscription_topic(name: &str) -> bool {
+    name == ALERT_DIRECT_TOPIC
+        || name == ALERT_NEAR_TOPIC
+        || name == TEST_TOPIC
+        || get(name).is_some()
+}
+
+/// Loads topics from the JSON file named by `TOPICS_JSON_PATH` if set, otherwise parses the
+/// legacy `TOPICS` environment variable.
+pub fn load_topics() -> Result<Vec<Topic>, String> {
+    match env::var(TOPICS_JSON_PATH_ENV) {
+        Ok(json_path) => load_topics_file(&json_path),
+        Err(_) => Ok(parse_legacy_topics(
+            &env::var(TOPICS_ENV).unwrap_or_default(),
+        )),
+    }
+}
+
+/// Loads topics from a JSON file of topic definitions.
+pub fn load_topics_file(json_path: &str) -> Result<Vec<Topic>, String> {
+    if !Path::new(json_path).exists() {
+        return Err(format!(
+            "The specified topics file does not exist: {}",
+            json_path
+        ));
+    }
+    let json_data = fs::read_to_string(json_path)
+        .map_err(|err| format!("Failed to read the topics file: {}", err))?;
+    parse_topics_json(&json_data)
+}
+
+/// Parses a JSON array of topic definitions. Topics without a name or description are
+/// rejected, along with the rest of the file.
+pub fn parse_topics_json(json_data: &str) -> Result<Vec<Topic>, String> {
+    let topics: Vec<Topic> = serde_json::from_str(json_data)
+        .map_err(|err| format!("Failed to parse topics file: {}", err))?;
+    if let Some(index) = topics
+        .iter()
+        .position(|topic| topic.name.trim().is_empty() || topic.description.trim().is_empty())
+    {
+        return Err(format!(
+            "Topic {} in the topics file needs a name and a description",
+            index + 1
+        ));
+    }
+    Ok(topics)
+}
+
+/// Parses the legacy `name:description[:channel];...` format.
+pub fn parse_legacy_topics(topics: &str) -> Vec<Topic> {
+    topics
+        .split(';')
+        .filter_map(|entry| {
+            let mut parts = entry.trim().splitn(3, ':');
+            let name = parts.next()?.trim();
+            let description = parts.next()?.trim();
+            if name.is_empty() || description.is_empty() {
+                return None;
+            }
+            let mut topic = Topic::new(name, description);
+            topic.slack_channel = parts
+                .next()
+                .map(|channel| channel.trim().to_string())
+                .filter(|channel| !channel.is_empty());
+            Some(topic)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_legacy_topics() {
+        let topics = parse_legacy_topics(
+            "\n  Rust:the Rust programming language;\n  EVs:Electric Cars:C0123;\n  Broken;",
+        );
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0].name, "Rust");
+        assert_eq!(topics[0].slack_channel, None);
+        assert_eq!(topics[1].description, "Electric Cars");
+        assert_eq!(topics[1].slack_channel.as_deref(), Some("C0123"));
+        assert!(topics[1].push_eligible);
+    }
+
+    #[test]
+    fn test_parse_topic_matches_falls_back_to_primary() {
+        let matches = parse_topic_matches(None, "Rust");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].topic, "Rust");
+
+        let matches = parse_topic_matches(
+            Some(r#"[{"topic":"EVs","confidence":0.82},{"topic":"Batteries","confidence":0.77}]"#),
+            "EVs",
+        );
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].topic, "Batteries");
+    }
+
+    #[test]
+    fn test_unembedded_topics_rank_last() {
+        let mut matches = vec![
+            TopicMatch::confirmed("Unembedded", None),
+            TopicMatch::confirmed("Batteries", Some(0.41)),
+            TopicMatch::confirmed("Also unembedded", None),
+            TopicMatch::confirmed("EVs", Some(0.82)),
+        ];
+        sort_by_confidence(&mut matches);
+        let order: Vec<&str> = matches.iter().map(|m| m.topic.as_str()).collect();
+        assert_eq!(order, ["EVs", "Batteries", "Unembedded", "Also unembedded"]);
+        assert_eq!(matches[2].confidence, 0.0);
+
+        let mut matches = vec![
+            TopicMatch::confirmed("First", None),
+            TopicMatch::confirmed("Second", None),
+        ];
+        sort_by_confidence(&mut matches);
+        assert_eq!(matches[0].topic, "First");
+    }
+
+    #[test]
+    fn test_parse_topics_json_and_prefilter() {
+        let topics = parse_topics_json(
+            r#"[{
+                "name": "Semiconductors",
+                "description": "Semiconductor manufacturing and the chip industry",
+                "required_keywords": ["chip", "semiconductor"],
+                "excluded_keywords": ["potato chip"],
+                "min_quality": 1,
+                "push_eligible": false,
+                "languages": ["en", "de"]
+            }]"#,
+        )
+        .unwrap();
+        let topic = &topics[0];
+        assert!(!topic.push_eligible);
+        assert!(topic.meets_quality(2));
+        assert!(!topic.meets_quality(0));
+        assert!(topic
+            .prefilter_rejection("TSMC opens a new chip fab", Some("en"))
+            .is_none());
+        assert!(topic
+            .prefilter_rejection("TSMC opens a new chip fab", Some("fr"))
+            .is_some());
+        assert!(topic
+            .prefilter_rejection("Potato chip prices soar", None)
+            .is_some());
+        assert!(topic
+            .prefilter_rejection("Election results are in", None)
+            .is_some());
+    }
+
+    #[test]
+    fn test_load_topics_file_rejects_malformed_files() {
+        let path = env::temp_dir().join(format!("argus-topics-{}.json", std::process::id()));
+        let path_str = path.to_str().unwrap();
+
+        fs::write(
+            &path,
+            r#"[{"name": "Rust", "description": "The Rust language"}]"#,
+        )
+        .unwrap();
+        assert_eq!(load_topics_file(path_str).unwrap()[0].name, "Rust");
+
+        fs::write(
+            &path,
+            r#"[{"name": "Rust", "description": "The Rust language""#,
+        )
+        .unwrap();
+        assert!(load_topics_file(path_str).is_err());
+        fs::write(&path, r#"{"name": "Rust"}"#).unwrap();
+        assert!(load_topics_file(path_str).is_err());
+        fs::write(&path, r#"[{"name": "Rust", "description": " "}]"#).unwrap();
+        assert!(load_topics_file(path_str).is_err());
+
+        fs::remove_file(&path).unwrap();
+        assert!(load_topics_file(path_str).is_err());
+    }
+}
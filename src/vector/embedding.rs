@@ -1,9 +1,11 @@
 use anyhow::Result;
 use candle_core::{DType, Tensor};
 use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Mutex;
 use tokio::time::Instant;
 use tracing::{error, info};
 
+use crate::metrics;
 use crate::vector::{
     config::{init_e5_model, init_e5_tokenizer, E5Config},
     TARGET_VECTOR,
@@ -12,6 +14,9 @@ use crate::vector::{
 // Static initialized flag
 static INITIALIZED: AtomicBool = AtomicBool::new(false);
 
+// Serializes first-time model loading when several workers start embedding at once
+static INIT_LOCK: Mutex<()> = Mutex::const_new(());
+
 /// Generate an embedding for a given article text
 async fn get_article_embedding(prefixed_text: &str, config: &E5Config) -> Result<Vec<f32>> {
     let start_time = Instant::now();
@@ -124,35 +129,73 @@ async fn get_article_embedding(prefixed_text: &str, config: &E5Config) -> Result
     Ok(vector)
 }
 
+/// Loads the E5 model and tokenizer on first use, downloading them if needed
+async fn ensure_initialized(config: &E5Config) -> Result<()> {
+    if INITIALIZED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    let _guard = INIT_LOCK.lock().await;
+    if INITIALIZED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    let init_start = Instant::now();
+    config.ensure_models_exist().await?;
+    let model_init_start = Instant::now();
+    init_e5_model(config)?;
+    let tokenizer_init_start = Instant::now();
+    init_e5_tokenizer(config)?;
+    INITIALIZED.store(true, Ordering::Relaxed);
+    let init_end = Instant::now();
+
+    info!(target: TARGET_VECTOR,
+        "Initialization timing: Model download/check: {:?}; Model initialization: {:?}; Tokenizer initialization: {:?}; Total init time: {:?}",
+        model_init_start.duration_since(init_start),
+        tokenizer_init_start.duration_since(model_init_start),
+        init_end.duration_since(tokenizer_init_start),
+        init_end.duration_since(init_start)
+    );
+
+    Ok(())
+}
+
+/// Generate an E5 embedding for short text such as a topic description or a search query.
+///
+/// E5 is trained with asymmetric prefixes: use `"query: "` for the short side of a
+/// comparison (topics, questions) and `"passage: "` for the text being matched against.
+pub async fn get_prefixed_embedding(prefix: &str, text: &str) -> Result<Option<Vec<f32>>> {
+    let config = E5Config::default();
+    ensure_initialized(&config).await?;
+
+    let prefixed_text = format!("{}{}", prefix, text);
+    match metrics::time_embedding(get_article_embedding(&prefixed_text, &config)).await {
+        Ok(embedding) if embedding.len() == config.dimensions => Ok(Some(embedding)),
+        Ok(embedding) => {
+            error!(target: TARGET_VECTOR, "Unexpected embedding dimensions: got {}, expected {}",
+                embedding.len(), config.dimensions);
+            Ok(None)
+        }
+        Err(e) => {
+            error!(target: TARGET_VECTOR, "Failed to generate prefixed embedding: {:?}", e);
+            Ok(None)
+        }
+    }
+}
+
 /// Public function to get vector embedding for article text
 pub async fn get_article_vectors(text: &str) -> Result<Option<Vec<f32>>> {
     let config = E5Config::default();
     let total_start = Instant::now();
 
-    if !INITIALIZED.load(Ordering::Relaxed) {
-        let init_start = Instant::now();
-        config.ensure_models_exist().await?;
-        let model_init_start = Instant::now();
-        init_e5_model(&config)?;
-        let tokenizer_init_start = Instant::now();
-        init_e5_tokenizer(&config)?;
-        INITIALIZED.store(true, Ordering::Relaxed);
-
-        info!(target: TARGET_VECTOR,
-            "Initialization timing: Model download/check: {:?}; Model initialization: {:?}; Tokenizer initialization: {:?}; Total init time: {:?}",
-            model_init_start.duration_since(init_start),
-            tokenizer_init_start.duration_since(model_init_start),
-            total_start.duration_since(tokenizer_init_start),
-            total_start.duration_since(init_start)
-        );
-    }
+    ensure_initialized(&config).await?;
 
     // Use query-focused embedding to direct the model to focus on event identification
     let prefixed_text = format!(
         "query: What is the main event described in this article? passage: {}",
         text
     );
-    match get_article_embedding(&prefixed_text, &config).await {
+    match metrics::time_embedding(get_article_embedding(&prefixed_text, &config)).await {
         Ok(embedding) => {
             let validation_start = Instant::now();
 
@@ -1,12 +1,20 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use qdrant_client::qdrant::point_id::PointIdOptions;
-use qdrant_client::qdrant::{SearchParams, SearchPoints, WithPayloadSelector, WithVectorsSelector};
+use qdrant_client::qdrant::value::Kind;
+use qdrant_client::qdrant::{
+    Condition, DatetimeRange, Filter, Range, SearchParams, SearchPoints, Timestamp,
+    WithPayloadSelector, WithVectorsSelector,
+};
 use qdrant_client::Qdrant;
 use sqlx;
+use std::collections::HashMap;
 use tracing::{error, info, warn};
 
 use crate::entity;
+use crate::metrics;
 use crate::vector::{
+    embedding::get_prefixed_embedding,
     similarity::{calculate_direct_similarity, calculate_similarity_date_threshold},
     storage::get_article_vector_from_qdrant,
     types::{ArticleMatch, EnhancedArticleMatch, NearMissMatch},
@@ -77,7 +85,7 @@ pub async fn get_similar_articles(embedding: &Vec<f32>, limit: u64) -> Result<Ve
         ..Default::default()
     };
 
-    match client.search_points(search_points).await {
+    match metrics::time_qdrant("search", client.search_points(search_points)).await {
         Ok(response) => {
             let mut matches: Vec<ArticleMatch> = response
                 .result
@@ -885,3 +893,145 @@ pub async fn get_similar_articles_with_entities(
 
     Ok(final_matches)
 }
+
+/// Filters for a semantic search over article vectors. Every filter that is set must match.
+#[derive(Debug, Clone, Default)]
+pub struct SemanticFilter {
+    /// Published at or after
+    pub since: Option<DateTime<Utc>>,
+    /// Published before
+    pub until: Option<DateTime<Utc>>,
+    /// Any of these categories
+    pub categories: Vec<String>,
+    /// Minimum quality score
+    pub min_quality: Option<i8>,
+}
+
+impl SemanticFilter {
+    fn to_qdrant(&self) -> Option<Filter> {
+        let timestamp = |date: DateTime<Utc>| Timestamp {
+            seconds: date.timestamp(),
+            nanos: 0,
+        };
+        let mut conditions = Vec::new();
+        if self.since.is_some() || self.until.is_some() {
+            conditions.push(Condition::datetime_range(
+                "published_date",
+                DatetimeRange {
+                    gte: self.since.map(timestamp),
+                    lt: self.until.map(timestamp),
+                    ..Default::default()
+                },
+            ));
+        }
+        if !self.categories.is_empty() {
+            conditions.push(Condition::matches("category", self.categories.clone()));
+        }
+        if let Some(min_quality) = self.min_quality {
+            conditions.push(Condition::range(
+                "quality_score",
+                Range {
+                    gte: Some(min_quality as f64),
+                    ..Default::default()
+                },
+            ));
+        }
+        (!conditions.is_empty()).then(|| Filter::must(conditions))
+    }
+}
+
+/// Search for the articles closest in meaning to a free-text query. The query is embedded
+/// with the E5 "query: " prefix, so it is compared like a question against passages.
+/// Returns matches with at least `min_score`, most similar first.
+pub async fn search_articles_by_query(
+    query: &str,
+    filter: &SemanticFilter,
+    limit: u64,
+    min_score: Option<f32>,
+) -> Result<Vec<ArticleMatch>> {
+    let Some(embedding) = get_prefixed_embedding("query: ", query).await? else {
+        return Err(anyhow::anyhow!("Failed to embed search query"));
+    };
+
+    let client = Qdrant::from_url(
+        &std::env::var(QDRANT_URL_ENV).expect("QDRANT_URL environment variable required"),
+    )
+    .timeout(std::time::Duration::from_secs(60))
+    .build()?;
+
+    let search_points = SearchPoints {
+        collection_name: "articles".to_string(),
+        vector: embedding,
+        limit,
+        filter: filter.to_qdrant(),
+        with_payload: Some(WithPayloadSelector::from(true)),
+        with_vectors: Some(WithVectorsSelector::from(false)),
+        params: Some(SearchParams {
+            hnsw_ef: Some(128),
+            ..Default::default()
+        }),
+        score_threshold: min_score,
+        ..Default::default()
+    };
+
+    let response = metrics::time_qdrant("search", client.search_points(search_points))
+        .await
+        .map_err(|e| {
+            error!(target: TARGET_VECTOR, "Failed to search articles by query: {:?}", e);
+            anyhow::anyhow!("Failed to search articles by query: {:?}", e)
+        })?;
+
+    let payload_string =
+        |payload: &HashMap<String, qdrant_client::qdrant::Value>, key: &str| match payload
+            .get(key)
+            .and_then(|v| v.kind.as_ref())
+        {
+            Some(Kind::StringValue(s)) => s.clone(),
+            _ => String::new(),
+        };
+    let matches: Vec<ArticleMatch> = response
+        .result
+        .into_iter()
+        .filter_map(|scored_point| {
+            let id = match scored_point.id?.point_id_options? {
+                PointIdOptions::Num(num) => num as i64,
+                _ => return None,
+            };
+            let payload = scored_point.payload;
+            let quality_score = match payload.get("quality_score").and_then(|v| v.kind.as_ref()) {
+                Some(Kind::IntegerValue(i)) => *i as i8,
+                _ => 0,
+            };
+            Some(ArticleMatch {
+                id,
+                published_date: payload_string(&payload, "published_date"),
+                category: payload_string(&payload, "category"),
+                quality_score,
+                score: scored_point.score,
+                vector_score: Some(scored_point.score),
+                vector_active_dimensions: None,
+                vector_magnitude: None,
+                entity_overlap_count: None,
+                primary_overlap_count: None,
+                person_overlap: None,
+                org_overlap: None,
+                location_overlap: None,
+                event_overlap: None,
+                temporal_proximity: None,
+                similarity_formula: Some("100% vector similarity to the query".to_string()),
+            })
+        })
+        .collect();
+    info!(target: TARGET_VECTOR, "Found {} articles similar to the query", matches.len());
+    Ok(matches)
+}
+
+/// Search for the articles closest in meaning to a free-text query, across all dates.
+/// Returns article IDs with their similarity, most similar first.
+pub async fn search_articles_by_text(query: &str, limit: u64) -> Result<Vec<(i64, f32)>> {
+    let matches = search_articles_by_query(query, &SemanticFilter::default(), limit, None).await?;
+    Ok(matches
+        .into_iter()
+        .map(|article| (article.id, article.score))
+        .collect())
+}
@@ -9,6 +9,7 @@ use serde_json::json;
 use std::collections::HashMap;
 use tracing::{error, info};
 
+use crate::metrics;
 use crate::vector::{QDRANT_URL_ENV, TARGET_VECTOR};
 
 /// Store an article's embedding vector in Qdrant
@@ -89,7 +90,7 @@ pub async fn store_embedding(
         shard_key_selector: None,
     };
 
-    match client.upsert_points(upsert_points).await {
+    match metrics::time_qdrant("upsert", client.upsert_points(upsert_points)).await {
         Ok(_) => {
             info!(
                 target: TARGET_VECTOR,
@@ -118,8 +119,9 @@ pub async fn get_article_vector_from_qdrant(article_id: i64) -> Result<Vec<f32>>
     info!(target: TARGET_VECTOR, "Retrieving vector for article {}", article_id);
 
     // Get the article's vector from Qdrant
-    let response = client
-        .get_points(qdrant_client::qdrant::GetPoints {
+    let response = metrics::time_qdrant(
+        "get",
+        client.get_points(qdrant_client::qdrant::GetPoints {
             collection_name: "articles".to_string(),
             ids: vec![PointId {
                 point_id_options: Some(PointIdOptions::Num(article_id as u64)),
@@ -127,8 +129,9 @@ pub async fn get_article_vector_from_qdrant(article_id: i64) -> Result<Vec<f32>>
             with_payload: Some(WithPayloadSelector::from(false)),
             with_vectors: Some(WithVectorsSelector::from(true)),
             ..Default::default()
-        })
-        .await?;
+        }),
+    )
+    .await?;
 
     info!(target: TARGET_VECTOR, "Vector retrieval response received for article {}, points: {}", 
           article_id, response.result.len());
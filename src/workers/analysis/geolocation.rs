@@ -0,0 +1,88 @@
+use serde_json::{json, Value};
+use tracing::{debug, info};
+
+use crate::geo::{gazetteer, haversine_km, Impact, RadiusBands};
+use crate::llm::{generate_llm_response, ThreatGeolocationResponse};
+use crate::prompt;
+use crate::{JsonSchemaType, LLMParams, WorkerDetail, TARGET_LLM_REQUEST};
+
+/// A threatened area placed on the map.
+#[derive(Debug, Clone)]
+pub struct LocatedThreat {
+    pub place: String,
+    pub center: (f64, f64),
+    pub bands: RadiusBands,
+}
+
+impl LocatedThreat {
+    /// How this threat affects someone at `coordinates`, if at all.
+    pub fn impact_at(&self, coordinates: (f64, f64)) -> Option<Impact> {
+        self.bands.classify(haversine_km(self.center, coordinates))
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "place": self.place,
+            "latitude": self.center.0,
+            "longitude": self.center.1,
+            "radius_km": self.bands.radius_km,
+            "near_radius_km": self.bands.near_radius_km,
+        })
+    }
+}
+
+/// Asks the LLM where the threat is and how far it reaches, then places each area using
+/// the gazetteer, falling back to the LLM's own coordinates. Areas that can't be placed
+/// are dropped.
+pub async fn geolocate_threat(
+    article_text: &str,
+    llm_params: &LLMParams,
+    worker_detail: &WorkerDetail,
+) -> Vec<LocatedThreat> {
+    let geolocation_prompt = prompt::threat_geolocation_prompt(article_text);
+    let mut json_llm_params = llm_params.clone();
+    json_llm_params.json_format = Some(JsonSchemaType::ThreatGeolocation);
+
+    let Some(response) = generate_llm_response(
+        &geolocation_prompt,
+        &json_llm_params,
+        worker_detail,
+        "geolocation",
+    )
+    .await
+    else {
+        return Vec::new();
+    };
+
+    let areas = match serde_json::from_str::<ThreatGeolocationResponse>(response.trim()) {
+        Ok(parsed) => parsed.threat_areas,
+        Err(e) => {
+            info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: failed to parse threat geolocation: {}.", worker_detail.name, worker_detail.id, worker_detail.model, e);
+            return Vec::new();
+        }
+    };
+
+    areas
+        .into_iter()
+        .filter_map(|area| {
+            let center = gazetteer()
+                .lookup(&area.place, area.country_code.as_deref())
+                .map(|entry| (entry.latitude, entry.longitude))
+                .or(match (area.latitude, area.longitude) {
+                    (Some(lat), Some(lon)) => Some((lat, lon)),
+                    _ => None,
+                });
+            match center {
+                Some(center) => Some(LocatedThreat {
+                    place: area.place,
+                    center,
+                    bands: RadiusBands::for_radius(area.radius_km),
+                }),
+                None => {
+                    debug!(target: TARGET_LLM_REQUEST, "[{} {} {}]: could not place threat area {}.", worker_detail.name, worker_detail.id, worker_detail.model, area.place);
+                    None
+                }
+            }
+        })
+        .collect()
+}
@@ -1,4 +1,5 @@
 pub mod entity_handling;
+pub mod geolocation;
 pub mod processing;
 pub mod quality;
 pub mod similarity;
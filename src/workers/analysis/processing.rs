@@ -1,16 +1,29 @@
+use chrono::Utc;
 use serde_json::json;
 use std::collections::{BTreeMap, HashSet};
 use tokio::time::{sleep, Duration, Instant};
 use tracing::{debug, error, info, warn};
 
-use crate::app::util::send_to_app;
+use crate::alerts::{self, PersonAlert};
 use crate::db::core::Database;
+use crate::db::incident::Incident;
+use crate::events::{self, EventKind};
+use crate::geo::Impact;
+use crate::incident;
 use crate::llm::generate_llm_response;
+use crate::metrics;
+use crate::notifier::{self, Notification};
+use crate::places::{person_entry, Places};
 use crate::prompt;
-use crate::slack::send_to_slack;
-use crate::workers::common::calculate_quality_score;
+use crate::threat::{ThreatDetails, ThreatSeverity, Urgency};
+use crate::topics;
+use crate::workers::common::{
+    calculate_quality_score, find_near_duplicate, record_near_duplicate, store_fingerprint,
+};
+use crate::workers::status;
 use crate::{LLMParams, WorkerDetail, TARGET_LLM_REQUEST};
 
+use super::geolocation::geolocate_threat;
 use super::quality::process_analysis;
 use super::similarity::process_article_similarity;
 
@@ -22,11 +35,11 @@ pub async fn process_analysis_item(
     db: &Database,
     slack_token: &str,
     slack_channel: &str,
-    places_detailed: &BTreeMap<
-        String,
-        BTreeMap<String, BTreeMap<String, BTreeMap<String, Vec<String>>>>,
-    >,
+    places: &Places,
 ) -> bool {
+    incident::clear_expired_incidents(db, slack_token, slack_channel).await;
+    alerts::deliver_due_alerts(db, slack_token, false).await;
+
     // First, try to process an item from the life safety queue
     if let Ok(Some((
         article_url,
@@ -36,16 +49,20 @@ pub async fn process_analysis_item(
         article_hash,
         title_domain_hash,
         threat_regions,
+        threat_details,
         pub_date,
+        language,
+        original_text,
     ))) = db.fetch_and_delete_from_life_safety_queue().await
     {
+        status::start_article(worker_detail, &article_url);
         process_life_safety_item(
             worker_detail,
             llm_params,
             db,
             slack_token,
             slack_channel,
-            places_detailed,
+            places,
             article_url,
             article_title,
             article_text,
@@ -53,7 +70,10 @@ pub async fn process_analysis_item(
             article_hash,
             title_domain_hash,
             threat_regions,
+            threat_details,
             pub_date,
+            language,
+            original_text,
         )
         .await;
 
@@ -69,9 +89,13 @@ pub async fn process_analysis_item(
         article_hash,
         title_domain_hash,
         topic,
+        matched_topics,
         pub_date,
+        language,
+        original_text,
     ))) = db.fetch_and_delete_from_matched_topics_queue().await
     {
+        status::start_article(worker_detail, &article_url);
         let success = process_matched_topic_item(
             worker_detail,
             llm_params,
@@ -85,7 +109,10 @@ pub async fn process_analysis_item(
             article_hash,
             title_domain_hash,
             topic,
+            matched_topics,
             pub_date,
+            language,
+            original_text,
         )
         .await;
 
@@ -108,10 +135,7 @@ async fn process_life_safety_item(
     db: &Database,
     slack_token: &str,
     slack_channel: &str,
-    places_detailed: &BTreeMap<
-        String,
-        BTreeMap<String, BTreeMap<String, BTreeMap<String, Vec<String>>>>,
-    >,
+    places: &Places,
     article_url: String,
     article_title: String,
     article_text: String,
@@ -119,7 +143,10 @@ async fn process_life_safety_item(
     article_hash: String,
     title_domain_hash: String,
     threat_regions: String,
+    threat_details: ThreatDetails,
     pub_date: Option<String>,
+    language: Option<String>,
+    original_text: Option<String>,
 ) -> bool {
     let start_time = Instant::now();
     info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: pulled from life safety queue {}.", worker_detail.name, worker_detail.id, worker_detail.model, article_url);
@@ -138,6 +165,32 @@ async fn process_life_safety_item(
         );
         return false;
     }
+
+    // Copies of a life safety story aren't suppressed as near-duplicates: an updated wire
+    // copy may carry new casualty counts or evacuation areas, and incident tracking
+    // decides whether it is worth alerting
+    let fingerprint_text = original_text.as_deref().unwrap_or(&article_text);
+
+    // The threat may have ended while the article waited in the queue
+    if threat_details.is_expired(Utc::now()) {
+        info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: threat ended at {:?}, not alerting: {}.", worker_detail.name, worker_detail.id, worker_detail.model, threat_details.expires_at(), article_url);
+        let _ = db
+            .add_article(
+                &article_url,
+                false,
+                None,
+                None,
+                None,
+                Some(&article_hash),
+                Some(&title_domain_hash),
+                None,
+                pub_date.as_deref(),
+                None,
+                language.as_deref(),
+            )
+            .await;
+        return false;
+    }
     info!("flat threat_regions: {:?}", threat_regions);
 
     // Parse the JSON threat_regions
@@ -147,6 +200,44 @@ async fn process_life_safety_item(
 
     let mut directly_affected_people: BTreeMap<String, HashSet<String>> = BTreeMap::new();
     let mut indirectly_affected_people: BTreeMap<String, HashSet<String>> = BTreeMap::new();
+    // Each affected person's id and how they are affected, for alerting them individually
+    let mut alert_recipients: BTreeMap<i64, Impact> = BTreeMap::new();
+
+    // Match people by distance from the threat: within its radius they are directly
+    // affected, within the surrounding band indirectly.
+    let threats = geolocate_threat(&article_text, llm_params, worker_detail).await;
+    info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: located {} threat areas for {}.", worker_detail.name, worker_detail.id, worker_detail.model, threats.len(), article_url);
+    if !threats.is_empty() {
+        for (person, coordinates) in places.people.iter().zip(&places.coordinates) {
+            let Some(coordinates) = coordinates else {
+                continue;
+            };
+            let Some(impact) = threats
+                .iter()
+                .filter_map(|threat| threat.impact_at(*coordinates))
+                .min()
+            else {
+                continue;
+            };
+            alert_recipients.insert(person.id, impact);
+            let affected_people = match impact {
+                Impact::Direct => &mut directly_affected_people,
+                Impact::Near => &mut indirectly_affected_people,
+            };
+            affected_people
+                .entry(person.location.city.clone())
+                .or_default()
+                .insert(person.first_name.clone());
+        }
+    }
+
+    // People without coordinates, or everyone if the threat couldn't be placed, are
+    // still checked region by region.
+    let legacy_places = if threats.is_empty() {
+        &places.detailed
+    } else {
+        &places.detailed_unlocated
+    };
 
     // Iterate through the threat regions
     if let Some(impacted_regions) = threat_regions["impacted_regions"].as_array() {
@@ -159,7 +250,7 @@ async fn process_life_safety_item(
                 article_url, continent, country, region_name
             );
 
-            if let Some(countries) = places_detailed.get(continent) {
+            if let Some(countries) = legacy_places.get(continent) {
                 if let Some(regions) = countries.get(country) {
                     if let Some(cities) = regions.get(region_name) {
                         // Validate if the region truly has a threat
@@ -170,10 +261,14 @@ async fn process_life_safety_item(
                             continent,
                         );
                         info!("region_prompt: {}", region_prompt);
-                        let region_response =
-                            generate_llm_response(&region_prompt, &llm_params, worker_detail)
-                                .await
-                                .unwrap_or_default();
+                        let region_response = generate_llm_response(
+                            &region_prompt,
+                            &llm_params,
+                            worker_detail,
+                            "region",
+                        )
+                        .await
+                        .unwrap_or_default();
 
                         // Parse the response for yes/no
                         if region_response.trim().to_lowercase().starts_with("yes") {
@@ -185,11 +280,29 @@ async fn process_life_safety_item(
                                     country,
                                     continent,
                                 );
-                                let city_response =
-                                    generate_llm_response(&city_prompt, llm_params, worker_detail)
-                                        .await
-                                        .unwrap_or_default();
-                                if city_response.to_lowercase().contains("yes") {
+                                let city_response = generate_llm_response(
+                                    &city_prompt,
+                                    llm_params,
+                                    worker_detail,
+                                    "city",
+                                )
+                                .await
+                                .unwrap_or_default();
+                                let impact = if city_response.to_lowercase().contains("yes") {
+                                    Impact::Direct
+                                } else {
+                                    Impact::Near
+                                };
+                                for person in people {
+                                    if let Some(person) =
+                                        places.people.iter().find(|p| person_entry(p) == *person)
+                                    {
+                                        let entry =
+                                            alert_recipients.entry(person.id).or_insert(impact);
+                                        *entry = (*entry).min(impact);
+                                    }
+                                }
+                                if impact == Impact::Direct {
                                     for person in people {
                                         let parts: Vec<&str> = person.split(", ").collect();
                                         if parts.len() >= 3 {
@@ -239,12 +352,17 @@ async fn process_life_safety_item(
                 "Generated how_does_it_affect prompt: {:?}",
                 how_does_it_affect_prompt
             );
-            generate_llm_response(&how_does_it_affect_prompt, llm_params, worker_detail)
-                .await
-                .unwrap_or_else(|| {
-                    warn!("Failed to generate how_does_it_affect");
-                    String::new()
-                })
+            generate_llm_response(
+                &how_does_it_affect_prompt,
+                llm_params,
+                worker_detail,
+                "how_does_it_affect",
+            )
+            .await
+            .unwrap_or_else(|| {
+                warn!("Failed to generate how_does_it_affect");
+                String::new()
+            })
         } else {
             String::new()
         };
@@ -255,12 +373,17 @@ async fn process_life_safety_item(
                 "Generated why_not_affect prompt: {:?}",
                 why_not_affect_prompt
             );
-            generate_llm_response(&why_not_affect_prompt, llm_params, worker_detail)
-                .await
-                .unwrap_or_else(|| {
-                    warn!("Failed to generate why_not_affect");
-                    String::new()
-                })
+            generate_llm_response(
+                &why_not_affect_prompt,
+                llm_params,
+                worker_detail,
+                "why_not_affect",
+            )
+            .await
+            .unwrap_or_else(|| {
+                warn!("Failed to generate why_not_affect");
+                String::new()
+            })
         } else {
             String::new()
         };
@@ -334,11 +457,15 @@ async fn process_life_safety_item(
             "title": article_title,
             "url": article_url,
             "article_body": article_text,
+            "original_article_body": original_text,
+            "language": language,
             "pub_date": pub_date,
             "tiny_summary": tiny_summary,
             "tiny_title": tiny_title,
             "summary": summary,
             "affected": affected,
+            "threat_areas": threats.iter().map(|threat| threat.to_json()).collect::<Vec<_>>(),
+            "threat": threat_details.to_json(Utc::now()),
             "critical_analysis": critical_analysis,
             "logical_fallacies": logical_fallacies,
             "relation_to_topic": relation_to_topic,
@@ -367,7 +494,8 @@ async fn process_life_safety_item(
                 Some(&title_domain_hash),
                 None, // Placeholder for R2 URL, will update later
                 pub_date.as_deref(),
-                None, // event_date
+                None,                // event_date
+                language.as_deref(), // language
             )
             .await
         {
@@ -380,6 +508,8 @@ async fn process_life_safety_item(
                 return false; // Skip processing if saving fails
             }
         };
+        store_fingerprint(db, article_id, fingerprint_text).await;
+        metrics::record_article_analyzed(topic);
 
         // Process vector embeddings and entities
         if let Err(e) = process_article_similarity(
@@ -407,35 +537,128 @@ async fn process_life_safety_item(
         // Add the article ID to the JSON now that we have it
         response_json["id"] = json!(article_id);
 
-        // Send notification to app
-        if let Some(r2_url) = send_to_app(&response_json).await {
-            // Update the article with R2 details
-            if let Err(e) = db
-                .update_article_with_r2_details(&article_url, &r2_url)
-                .await
-            {
-                error!(
-                    target: TARGET_LLM_REQUEST,
-                    "Failed to update R2 details in database: {:?}", e
-                );
+        // Analysis takes a while, so check again that the threat is still current
+        let now = Utc::now();
+        if threat_details.is_expired(now) {
+            info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: threat ended at {:?} during analysis, not alerting: {}.", worker_detail.name, worker_detail.id, worker_detail.model, threat_details.expires_at(), article_url);
+            return true;
+        }
+        let urgency = threat_details.urgency(now);
+        response_json["threat"] = threat_details.to_json(now);
+
+        // Follow-up reports on a known incident are only notified if something changed
+        let observed = Incident {
+            id: 0,
+            title: article_title.clone(),
+            topic: topic.to_string(),
+            category: threat_details.category,
+            severity: threat_details.severity,
+            response: threat_details.response,
+            area: threats
+                .iter()
+                .max_by(|a, b| a.bands.radius_km.total_cmp(&b.bands.radius_km))
+                .map(|threat| (threat.center.0, threat.center.1, threat.bands.radius_km)),
+            cluster_id: None,
+            expires_at: threat_details
+                .expires_at()
+                .unwrap_or_else(incident::default_expiry),
+        };
+        let incident_update = match incident::track_incident(db, article_id, observed).await {
+            Ok(Some(update)) => Some(update),
+            Ok(None) => {
+                info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: all clear for no active incident, not alerting: {}.", worker_detail.name, worker_detail.id, worker_detail.model, article_url);
+                return true;
+            }
+            Err(e) => {
+                error!(target: TARGET_LLM_REQUEST, "Failed to track incident: {:?}", e);
+                None
+            }
+        };
+        if let Some(update) = &incident_update {
+            response_json["incident"] = update.to_json();
+            if update.is_all_clear() {
+                incident::send_all_clear(
+                    db,
+                    &update.incident,
+                    &tiny_summary,
+                    slack_token,
+                    slack_channel,
+                )
+                .await;
+                return true;
+            }
+            if !update.should_notify() {
+                info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: no material change to incident {}, not alerting: {}.", worker_detail.name, worker_detail.id, worker_detail.model, update.incident.id, article_url);
+                return true;
+            }
+            if !update.is_new {
+                response_json["tiny_title"] = json!(format!("Update: {}", tiny_title));
             }
-        } else {
-            warn!("failed to send analysis: {} to app...", article_url);
         }
 
-        // Notify Slack
-        send_to_slack(
-            &format!("*<{}|{}>*", article_url, article_title),
-            &response_json.to_string(),
-            slack_token,
-            slack_channel,
-        )
-        .await;
+        // Alert each affected person on their own channels
+        let recipients: Vec<_> = places
+            .people
+            .iter()
+            .filter_map(|person| {
+                alert_recipients
+                    .get(&person.id)
+                    .map(|impact| (person, Some(*impact)))
+            })
+            .collect();
+        if !recipients.is_empty() {
+            let subject = response_json["tiny_title"]
+                .as_str()
+                .unwrap_or(&article_title);
+            let alert = PersonAlert {
+                article_id: Some(article_id),
+                incident_id: incident_update.as_ref().map(|update| update.incident.id),
+                urgency,
+                subject,
+                summary: &tiny_summary,
+                url: Some(&article_url),
+                direct_details: &how_does_it_affect,
+            };
+            let queued = alerts::queue_person_alerts(db, &alert, &recipients).await;
+            info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: queued {} alerts for {} people: {}.", worker_detail.name, worker_detail.id, worker_detail.model, queued, recipients.len(), article_url);
+            alerts::deliver_due_alerts(db, slack_token, true).await;
+        }
+
+        // Flag urgent threats and what changed since the last alert
+        let urgency_prefix = match urgency {
+            Urgency::Immediate => ":rotating_light: *Immediate:* ",
+            Urgency::Elevated => ":warning: ",
+            Urgency::Routine => "",
+        };
+        let update_prefix = match &incident_update {
+            Some(update) if !update.is_new => {
+                format!("*Update ({}):* ", update.describe_changes())
+            }
+            _ => String::new(),
+        };
+        let cluster_id = db.get_article_cluster_id(article_id).await.unwrap_or(None);
+        let mut event = events::article_payload(&response_json);
+        event["urgency"] = json!(urgency);
+        event["cluster_id"] = json!(cluster_id);
+        events::publish(db, EventKind::LifeSafetyAlert, &[topic.to_string()], &event).await;
+
+        let notification = Notification {
+            topics: vec![topic.to_string()],
+            severity: threat_details.severity,
+            article_url: &article_url,
+            cluster_id,
+            headline: format!(
+                "{}{}*<{}|{}>*",
+                urgency_prefix, update_prefix, article_url, article_title
+            ),
+            analysis: &response_json,
+        };
+        let receipts = notifier::dispatch(db, &notification, slack_token, slack_channel).await;
 
         debug!(
             target: TARGET_LLM_REQUEST,
-            "[{} {} {}]: sent analysis to slack: {}.",
-            worker_detail.name, worker_detail.id, worker_detail.model, article_url
+            "[{} {} {}]: sent analysis to {} backends: {}.",
+            worker_detail.name, worker_detail.id, worker_detail.model, receipts.len(), article_url
         );
 
         return true;
@@ -458,9 +681,14 @@ async fn process_matched_topic_item(
     article_hash: String,
     title_domain_hash: String,
     topic: String,
+    matched_topics: Option<String>,
     pub_date: Option<String>,
+    language: Option<String>,
+    original_text: Option<String>,
 ) -> bool {
     let mut llm_params_clone = llm_params.clone();
+    // The article is analyzed once, relative to its primary topic, and shared by all matches
+    let topic_matches = topics::parse_topic_matches(matched_topics.as_deref(), &topic);
 
     let start_time = std::time::Instant::now();
 
@@ -476,6 +704,22 @@ async fn process_matched_topic_item(
         return false;
     }
 
+    // Another copy of the same story may have been analyzed since this one was queued
+    let fingerprint_text = original_text.as_deref().unwrap_or(&article_text);
+    if let Some(duplicate) = find_near_duplicate(db, fingerprint_text).await {
+        record_near_duplicate(
+            db,
+            &article_url,
+            duplicate,
+            &article_hash,
+            &title_domain_hash,
+            pub_date.as_deref(),
+            language.as_deref(),
+        )
+        .await;
+        return false;
+    }
+
     let (
         summary,
         tiny_summary,
@@ -519,9 +763,12 @@ async fn process_matched_topic_item(
 
         let mut response_json = json!({
             "topic": topic,
+            "topics": topic_matches.iter().map(|m| m.topic.as_str()).collect::<Vec<_>>(),
             "title": article_title,
             "url": article_url,
             "article_body": article_text,
+            "original_article_body": original_text,
+            "language": language,
             "pub_date": pub_date,
             "tiny_summary": tiny_summary,
             "tiny_title": tiny_title,
@@ -554,7 +801,8 @@ async fn process_matched_topic_item(
                 Some(&title_domain_hash),
                 None, // Placeholder for R2 URL, will update later
                 pub_date.as_deref(),
-                None, // event_date
+                None,                // event_date
+                language.as_deref(), // language
             )
             .await
         {
@@ -567,6 +815,12 @@ async fn process_matched_topic_item(
                 return false; // Skip processing if saving fails
             }
         };
+        store_fingerprint(db, article_id, fingerprint_text).await;
+        metrics::record_article_analyzed(&topic);
+
+        if let Err(e) = db.add_article_topics(article_id, &topic_matches).await {
+            error!(target: TARGET_LLM_REQUEST, "Failed to record article topics: {:?}", e);
+        }
 
         // Process vector embeddings and entities
         if let Err(e) = process_article_similarity(
@@ -594,35 +848,45 @@ async fn process_matched_topic_item(
         // Add the article ID to the JSON now that we have it
         response_json["id"] = json!(article_id);
 
-        // Send notification to app
-        if let Some(r2_url) = send_to_app(&response_json).await {
-            // Update the article with R2 details
-            if let Err(e) = db
-                .update_article_with_r2_details(&article_url, &r2_url)
-                .await
-            {
-                error!(
-                    target: TARGET_LLM_REQUEST,
-                    "Failed to update R2 details in database: {:?}", e
-                );
-            }
-        } else {
-            warn!("failed to send analysis: {} to app...", article_url);
-        }
-
-        // Send notification to slack
-        send_to_slack(
-            &format!("*<{}|{}>*", article_url, article_title),
-            &response_json.to_string(),
-            slack_token,
-            slack_channel,
+        // Every analysis is streamed, including those below a topic's minimum quality
+        let cluster_id = db.get_article_cluster_id(article_id).await.unwrap_or(None);
+        let mut event = events::article_payload(&response_json);
+        event["cluster_id"] = json!(cluster_id);
+        events::publish(
+            db,
+            EventKind::Analysis,
+            &topics::topics_in_response(&response_json),
+            &event,
         )
         .await;
 
+        // Topics can require a minimum quality before anyone is notified
+        let notify_topics: Vec<&str> = topic_matches
+            .iter()
+            .map(|m| m.topic.as_str())
+            .filter(|name| topics::get(name).is_none_or(|t| t.meets_quality(quality)))
+            .collect();
+        if notify_topics.is_empty() {
+            info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: quality {} below minimum for topics {:?}, not notifying: {}.", worker_detail.name, worker_detail.id, worker_detail.model, quality, response_json["topics"], article_url);
+            return true;
+        }
+        response_json["topics"] = json!(notify_topics);
+
+        // Send the analysis to every backend routed for its topics
+        let notification = Notification {
+            topics: topics::topics_in_response(&response_json),
+            severity: ThreatSeverity::Unknown,
+            article_url: &article_url,
+            cluster_id,
+            headline: format!("*<{}|{}>*", article_url, article_title),
+            analysis: &response_json,
+        };
+        let receipts = notifier::dispatch(db, &notification, slack_token, slack_channel).await;
+
         debug!(
             target: TARGET_LLM_REQUEST,
-            "[{} {} {}]: sent analysis to slack: {}.",
-            worker_detail.name, worker_detail.id, worker_detail.model, article_url
+            "[{} {} {}]: sent analysis to {} backends: {}.",
+            worker_detail.name, worker_detail.id, worker_detail.model, receipts.len(), article_url
         );
         return true; // An item was processed
     }
@@ -52,33 +52,35 @@ pub async fn process_analysis(
 
     // Start with summary to establish base understanding
     let summary_prompt = prompt::summary_prompt(article_text, pub_date);
-    let summary = match generate_llm_response(&summary_prompt, llm_params, worker_detail).await {
-        Some(s) if !s.trim().is_empty() => s,
-        _ => {
-            warn!("Failed to generate valid summary");
-            return (
-                String::new(),
-                String::new(),
-                String::new(),
-                String::new(),
-                String::new(),
-                String::new(),
-                None,
-                2,
-                2,
-                String::from("none"),
-                String::new(),
-                String::new(),
-                String::new(),
-            );
-        }
-    };
+    let summary =
+        match generate_llm_response(&summary_prompt, llm_params, worker_detail, "summary").await {
+            Some(s) if !s.trim().is_empty() => s,
+            _ => {
+                warn!("Failed to generate valid summary");
+                return (
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    None,
+                    2,
+                    2,
+                    String::from("none"),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                );
+            }
+        };
 
     // Only proceed with other analyses if we have a valid summary
     let tiny_summary = generate_llm_response(
         &prompt::tiny_summary_prompt(&summary),
         llm_params,
         worker_detail,
+        "tiny_summary",
     )
     .await
     .unwrap_or_default();
@@ -87,6 +89,7 @@ pub async fn process_analysis(
         &prompt::tiny_title_prompt(&summary),
         llm_params,
         worker_detail,
+        "tiny_title",
     )
     .await
     .unwrap_or_default();
@@ -96,6 +99,7 @@ pub async fn process_analysis(
         &prompt::critical_analysis_prompt(article_text, pub_date),
         llm_params,
         worker_detail,
+        "critical_analysis",
     )
     .await
     .unwrap_or_default();
@@ -104,6 +108,7 @@ pub async fn process_analysis(
         &prompt::logical_fallacies_prompt(article_text, pub_date),
         llm_params,
         worker_detail,
+        "logical_fallacies",
     )
     .await
     .unwrap_or_default();
@@ -113,6 +118,7 @@ pub async fn process_analysis(
         &prompt::source_analysis_prompt(article_html, article_url, pub_date),
         llm_params,
         worker_detail,
+        "source_analysis",
     )
     .await
     .unwrap_or_default();
@@ -123,6 +129,7 @@ pub async fn process_analysis(
             &prompt::sources_quality_prompt(&critical_analysis),
             llm_params,
             worker_detail,
+            "sources_quality",
         )
         .await
         .and_then(|resp| resp.trim().parse::<u8>().ok())
@@ -136,6 +143,7 @@ pub async fn process_analysis(
             &prompt::argument_quality_prompt(&logical_fallacies),
             llm_params,
             worker_detail,
+            "argument_quality",
         )
         .await
         .and_then(|resp| resp.trim().parse::<u8>().ok())
@@ -150,6 +158,7 @@ pub async fn process_analysis(
             &prompt::source_type_prompt(&source_analysis, article_url),
             llm_params,
             worker_detail,
+            "source_type",
         )
         .await
         .unwrap_or_else(|| String::from("none"))
@@ -165,6 +174,7 @@ pub async fn process_analysis(
             &prompt::relation_to_topic_prompt(article_text, topic, pub_date),
             llm_params,
             worker_detail,
+            "relation_to_topic",
         )
         .await
     } else {
@@ -177,6 +187,7 @@ pub async fn process_analysis(
             &prompt::additional_insights_prompt(article_text, pub_date),
             llm_params,
             worker_detail,
+            "additional_insights",
         )
         .await
         .unwrap_or_default()
@@ -190,6 +201,7 @@ pub async fn process_analysis(
             &prompt::action_recommendations_prompt(article_text, pub_date),
             llm_params,
             worker_detail,
+            "action_recommendations",
         )
         .await
         .unwrap_or_default()
@@ -203,6 +215,7 @@ pub async fn process_analysis(
             &prompt::talking_points_prompt(article_text, pub_date),
             llm_params,
             worker_detail,
+            "talking_points",
         )
         .await
         .unwrap_or_default()
@@ -4,8 +4,10 @@ use tracing::{debug, error, info};
 
 use crate::db::core::Database;
 use crate::llm::generate_llm_response;
-use crate::util::{parse_places_data_detailed, parse_places_data_hierarchical};
+use crate::places;
+use crate::topics::Topic;
 use crate::workers::common::{build_connection_info, FeedItem, ProcessItemParams};
+use crate::workers::status::{self, WorkerMode};
 use crate::{
     FallbackConfig, LLMClient, LLMParams, ThinkingModelConfig, WorkerDetail, TARGET_LLM_REQUEST,
 };
@@ -19,7 +21,7 @@ enum Mode {
 /// Main analysis loop function with fallback mechanism
 pub async fn analysis_loop(
     worker_id: i16,
-    topics: &[String],
+    topics: &[Topic],
     llm_client: &LLMClient,
     model: &str,
     slack_token: &str,
@@ -54,25 +56,15 @@ pub async fn analysis_loop(
         connection_info,
     };
 
-    // If analysis_worker will switch to a decision_worker, we need this.
-    let places = match parse_places_data_hierarchical() {
-        Ok(hierarchy) => hierarchy,
-        Err(err) => panic!("Error: {}", err),
-    };
-    info!("loaded places: {:#?}", places);
-
-    // And we need this to analyze life safety threats.
-    let places_detailed = match parse_places_data_detailed() {
-        Ok(hierarchy) => hierarchy,
-        Err(err) => panic!("Error: {}", err),
-    };
-    info!("loaded places_detailed: {:#?}", places_detailed);
-
     info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: starting analysis_loop using {:?}.", worker_detail.name, worker_detail.id, worker_detail.model, llm_client);
+    status::register(&worker_detail, WorkerMode::Analysis);
 
     loop {
         match mode {
             Mode::Analysis => {
+                // Places are re-read for each item so registry changes apply without a restart
+                let places = places::current(db).await;
+
                 // Attempt to process an analysis item
                 let processed = super::processing::process_analysis_item(
                     &worker_detail,
@@ -80,9 +72,10 @@ pub async fn analysis_loop(
                     &db,
                     slack_token,
                     default_slack_channel,
-                    &places_detailed,
+                    &places,
                 )
                 .await;
+                status::finish_article(&worker_detail);
 
                 if processed {
                     last_activity = Instant::now();
@@ -97,6 +90,7 @@ pub async fn analysis_loop(
 
                         // Update active model to fallback model
                         worker_detail.model = fallback_config.model.to_string();
+                        status::set_mode(&worker_detail, WorkerMode::FallbackDecision);
 
                         // Update LLM params to use fallback model (no thinking config in fallback)
                         llm_params = LLMParams {
@@ -121,6 +115,7 @@ pub async fn analysis_loop(
                             mode = Mode::Analysis;
                             fallback_start_time = None;
                             worker_detail.model = model.to_string();
+                            status::set_mode(&worker_detail, WorkerMode::Analysis);
                             llm_params = LLMParams {
                                 llm_client: llm_client.clone(),
                                 model: model.to_string(),
@@ -162,6 +157,7 @@ pub async fn analysis_loop(
                             };
 
                             worker_detail.model = model.to_string();
+                            status::set_mode(&worker_detail, WorkerMode::Analysis);
 
                             // Wait for the original model to be operational
                             let _ =
@@ -170,7 +166,7 @@ pub async fn analysis_loop(
                         }
                     }
 
-                    let places_clone = places.clone();
+                    let places_clone = places::current(db).await.hierarchical.clone();
 
                     // Process a single Decision task
                     match db.fetch_and_delete_url_from_rss_queue("random").await {
@@ -196,12 +192,14 @@ pub async fn analysis_loop(
                                 };
 
                                 // Process the item using the decision worker's process_item function
+                                status::start_article(&worker_detail, &item.url);
                                 crate::workers::decision::processing::process_item(
                                     item,
                                     &mut params,
                                     &worker_detail,
                                 )
                                 .await;
+                                status::finish_article(&worker_detail);
                             }
                         }
                         Ok(None) => {
@@ -220,6 +218,7 @@ pub async fn analysis_loop(
                     // No fallback configured; remain in Analysis mode
                     info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: no Decision fallback configured, remaining in analysis mode.", worker_detail.name, worker_detail.id, worker_detail.model);
                     mode = Mode::Analysis;
+                    status::set_mode(&worker_detail, WorkerMode::Analysis);
                 }
             }
         }
@@ -234,6 +233,7 @@ pub async fn analysis_loop(
 
                     // Update active model to original model
                     worker_detail.model = model.to_string();
+                    status::set_mode(&worker_detail, WorkerMode::Analysis);
 
                     // Restore original LLM params with thinking config
                     llm_params = LLMParams {
@@ -273,7 +273,7 @@ async fn wait_for_model_ready(
 
     loop {
         attempts += 1;
-        match generate_llm_response(test_prompt, llm_params, worker_detail).await {
+        match generate_llm_response(test_prompt, llm_params, worker_detail, "readiness").await {
             Some(response) => {
                 info!(
                     target: TARGET_LLM_REQUEST,
@@ -1,10 +1,13 @@
 use crate::db::core::Database;
-use crate::{LLMClient, LLMParams};
+use crate::fingerprint;
+use crate::topics::Topic;
+use crate::{LLMClient, LLMParams, TARGET_DB};
 use std::collections::BTreeMap;
+use tracing::{error, info};
 
 /// Parameters required for processing an item, including topics, database, and Slack channel information.
 pub struct ProcessItemParams<'a> {
-    pub topics: &'a [String],
+    pub topics: &'a [Topic],
     pub llm_client: &'a LLMClient,
     pub model: &'a str,
     pub temperature: f32,
@@ -91,3 +94,142 @@ pub fn calculate_quality_score(sources_quality: u8, argument_quality: u8) -> i8
     // Combine scores
     sources_score + argument_score
 }
+
+/// Returns the canonical article that `article_text` is a near-duplicate of, and the
+/// estimated similarity, if there is one.
+///
+/// Copies of an article about an active incident are not treated as duplicates: wire
+/// updates keep most of the text while the casualty count or evacuation area changes,
+/// and incident tracking decides whether they are news.
+pub async fn find_near_duplicate(db: &Database, article_text: &str) -> Option<(i64, f64)> {
+    let signature = fingerprint::minhash(article_text)?;
+    let (canonical_article_id, similarity) = match db
+        .find_near_duplicate(&signature, fingerprint::threshold())
+        .await
+    {
+        Ok(duplicate) => duplicate?,
+        Err(e) => {
+            error!(target: TARGET_DB, "Failed to look up near-duplicates: {:?}", e);
+            return None;
+        }
+    };
+    match db.reports_active_incident(canonical_article_id).await {
+        Ok(false) => Some((canonical_article_id, similarity)),
+        Ok(true) => {
+            info!(target: TARGET_DB, "Near-duplicate ({:.2}) of article {} reports on an active incident, not skipping it.", similarity, canonical_article_id);
+            None
+        }
+        Err(e) => {
+            error!(target: TARGET_DB, "Failed to check incidents of article {}: {:?}", canonical_article_id, e);
+            Some((canonical_article_id, similarity))
+        }
+    }
+}
+
+/// Stores the fingerprint of a saved article so that later copies are recognized.
+pub async fn store_fingerprint(db: &Database, article_id: i64, article_text: &str) {
+    if let Some(signature) = fingerprint::minhash(article_text) {
+        if let Err(e) = db.set_article_fingerprint(article_id, &signature).await {
+            error!(target: TARGET_DB, "Failed to store fingerprint for article {}: {:?}", article_id, e);
+        }
+    }
+}
+
+/// Saves a near-duplicate as a non-relevant article linked to its canonical article, so
+/// that it is never analyzed or sent again.
+pub async fn record_near_duplicate(
+    db: &Database,
+    article_url: &str,
+    (canonical_article_id, similarity): (i64, f64),
+    article_hash: &str,
+    title_domain_hash: &str,
+    pub_date: Option<&str>,
+    language: Option<&str>,
+) {
+    info!(target: TARGET_DB, "Article {} is a near-duplicate ({:.2}) of article {}, skipping.", article_url, similarity, canonical_article_id);
+
+    match db
+        .add_article(
+            article_url,
+            false,
+            None,
+            None,
+            None,
+            Some(article_hash),
+            Some(title_domain_hash),
+            None,
+            pub_date,
+            None, // event_date
+            language,
+        )
+        .await
+    {
+        Ok(article_id) => {
+            if let Err(e) = db.link_to_canonical(article_id, canonical_article_id).await {
+                error!(target: TARGET_DB, "Failed to link article {} to canonical article {}: {:?}", article_id, canonical_article_id, e);
+            }
+        }
+        Err(e) => {
+            error!(target: TARGET_DB, "Failed to save near-duplicate article {}: {:?}", article_url, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::incident::Incident;
+    use crate::incident::default_expiry;
+    use crate::threat::{ThreatCategory, ThreatResponse, ThreatSeverity};
+
+    #[tokio::test]
+    async fn test_copies_of_active_incidents_are_not_duplicates() {
+        let db = Database::in_memory().await;
+        let text = "Firefighters are battling a large fire at a chemical plant near the river. \
+            Residents within two kilometers have been told to stay indoors and keep their \
+            windows closed while crews work to contain the blaze and monitor the air.";
+        let article_id = db
+            .add_article(
+                "https://example.com/fire",
+                true,
+                Some("Alert: Near"),
+                None,
+                None,
+                Some("hash"),
+                Some("title-domain-hash"),
+                None,
+                None,
+                None,
+                Some("en"),
+            )
+            .await
+            .unwrap();
+        store_fingerprint(&db, article_id, text).await;
+        assert_eq!(
+            find_near_duplicate(&db, text).await.map(|(id, _)| id),
+            Some(article_id)
+        );
+
+        let incident_id = db
+            .create_incident(&Incident {
+                id: 0,
+                title: "Chemical plant fire".to_string(),
+                topic: "Alert: Near".to_string(),
+                category: ThreatCategory::Hazmat,
+                severity: ThreatSeverity::Severe,
+                response: ThreatResponse::Shelter,
+                area: Some((45.76, 4.84, 2.0)),
+                cluster_id: None,
+                expires_at: default_expiry(),
+            })
+            .await
+            .unwrap();
+        db.link_article_to_incident(incident_id, article_id, "", true)
+            .await
+            .unwrap();
+        assert_eq!(find_near_duplicate(&db, text).await, None);
+
+        db.clear_incident(incident_id).await.unwrap();
+        assert!(find_near_duplicate(&db, text).await.is_some());
+    }
+}
@@ -83,6 +83,7 @@ pub async fn handle_access_denied(
                 None,
                 pub_date,
                 None, // event_date
+                None, // language
             )
             .await
         {
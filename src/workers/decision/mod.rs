@@ -1,6 +1,8 @@
 pub mod extraction;
+pub mod prefilter;
 pub mod processing;
 pub mod threat;
+pub mod translation;
 pub mod worker_loop;
 
 // Re-export the worker_loop module as the main interface
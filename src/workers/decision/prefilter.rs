@@ -0,0 +1,334 @@
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::OnceCell;
+use tracing::{debug, info, warn};
+
+use crate::topics::Topic;
+use crate::vector::{calculate_direct_similarity, get_prefixed_embedding};
+use crate::{WorkerDetail, TARGET_LLM_REQUEST};
+
+const TOPIC_PREFILTER_TOP_K_ENV: &str = "TOPIC_PREFILTER_TOP_K";
+const TOPIC_PREFILTER_MIN_SIMILARITY_ENV: &str = "TOPIC_PREFILTER_MIN_SIMILARITY";
+const TOPIC_PREFILTER_AUDIT_RATE_ENV: &str = "TOPIC_PREFILTER_AUDIT_RATE";
+
+/// How much of the article body is embedded, in characters. The model truncates at
+/// 512 tokens anyway, and the lede is where the subject of a news article lives.
+const PREFILTER_TEXT_CHARS: usize = 1500;
+
+/// How often (in articles) the per-topic prefilter statistics are logged.
+const STATS_LOG_INTERVAL: u64 = 100;
+
+/// A configured topic together with the embedding of its description.
+struct TopicEmbedding {
+    name: String,
+    embedding: Option<Vec<f32>>,
+}
+
+/// Similarity of an article to one topic, and whether the topic goes on to the LLM.
+#[derive(Debug, Clone)]
+pub struct TopicScore {
+    pub name: String,
+    pub similarity: Option<f32>,
+    pub is_candidate: bool,
+}
+
+/// Running counters for one topic, used to log thresholds and estimated recall.
+#[derive(Default)]
+struct TopicStats {
+    scored: u64,
+    candidates: u64,
+    skipped: u64,
+    llm_yes: u64,
+    audit_hits: u64,
+    audit_misses: u64,
+    similarity_sum: f64,
+}
+
+static TOPIC_STATS: Lazy<DashMap<String, TopicStats>> = Lazy::new(DashMap::new);
+static ARTICLES_SCORED: AtomicU64 = AtomicU64::new(0);
+
+/// Embedding-based topic prefilter that runs before the per-topic LLM checks.
+///
+/// Each topic description is embedded once with the E5 model. Articles are embedded
+/// from their title and lede and compared against every topic; only the top-k topics
+/// above the minimum similarity are sent to the LLM. A sampled fraction of articles
+/// (`TOPIC_PREFILTER_AUDIT_RATE`) also checks the skipped topics so that recall can be
+/// estimated per topic.
+pub struct TopicPrefilter {
+    topics: Vec<TopicEmbedding>,
+    top_k: usize,
+    min_similarity: f32,
+    audit_rate: f64,
+}
+
+impl TopicPrefilter {
+    /// Returns the shared prefilter, embedding the topic descriptions on first use.
+    pub async fn instance(topics: &[Topic]) -> &'static TopicPrefilter {
+        static INSTANCE: OnceCell<TopicPrefilter> = OnceCell::const_new();
+
+        INSTANCE
+            .get_or_init(|| async { TopicPrefilter::new(topics).await })
+            .await
+    }
+
+    async fn new(topics: &[Topic]) -> Self {
+        let top_k = std::env::var(TOPIC_PREFILTER_TOP_K_ENV)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+        let min_similarity = std::env::var(TOPIC_PREFILTER_MIN_SIMILARITY_ENV)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+        let audit_rate = std::env::var(TOPIC_PREFILTER_AUDIT_RATE_ENV)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.02);
+
+        let mut embedded = Vec::with_capacity(topics.len());
+        for topic in topics {
+            let embedding = match get_prefixed_embedding("query: ", &topic.description).await {
+                Ok(Some(embedding)) => Some(embedding),
+                Ok(None) | Err(_) => {
+                    warn!(target: TARGET_LLM_REQUEST, "Failed to embed topic '{}', it will always be sent to the LLM.", topic.name);
+                    None
+                }
+            };
+            embedded.push(TopicEmbedding {
+                name: topic.name.clone(),
+                embedding,
+            });
+        }
+
+        info!(target: TARGET_LLM_REQUEST,
+            "Topic prefilter ready: {} topics embedded, top_k={}, min_similarity={:.3}, audit_rate={:.3}",
+            embedded.iter().filter(|t| t.embedding.is_some()).count(),
+            top_k,
+            min_similarity,
+            audit_rate
+        );
+
+        TopicPrefilter {
+            topics: embedded,
+            top_k,
+            min_similarity,
+            audit_rate,
+        }
+    }
+
+    /// Whether this article should also be checked against the skipped topics.
+    pub fn should_audit(&self) -> bool {
+        self.audit_rate > 0.0 && rand::random::<f64>() < self.audit_rate
+    }
+
+    /// Scores the article against every topic and marks the top-k candidates.
+    ///
+    /// The result is ordered by descending similarity so the most likely topics are asked
+    /// first. If the article cannot be embedded, every topic is returned as a candidate.
+    pub async fn rank(
+        &self,
+        article_title: &str,
+        article_text: &str,
+        worker_detail: &WorkerDetail,
+    ) -> Vec<TopicScore> {
+        let passage = prefilter_text(article_title, article_text);
+        let article_embedding = match get_prefixed_embedding("passage: ", &passage).await {
+            Ok(Some(embedding)) => embedding,
+            Ok(None) | Err(_) => {
+                warn!(target: TARGET_LLM_REQUEST, "[{} {} {}]: failed to embed article for topic prefilter, checking all topics.", worker_detail.name, worker_detail.id, worker_detail.model);
+                return self
+                    .topics
+                    .iter()
+                    .map(|topic| TopicScore {
+                        name: topic.name.clone(),
+                        similarity: None,
+                        is_candidate: true,
+                    })
+                    .collect();
+            }
+        };
+
+        let mut scores: Vec<(String, Option<f32>)> = self
+            .topics
+            .iter()
+            .map(|topic| {
+                let similarity = topic.embedding.as_ref().and_then(|embedding| {
+                    calculate_direct_similarity(&article_embedding, embedding).ok()
+                });
+                (topic.name.clone(), similarity)
+            })
+            .collect();
+
+        let candidates = select_candidates(&mut scores, self.top_k, self.min_similarity);
+
+        let ranked: Vec<TopicScore> = scores
+            .into_iter()
+            .map(|(name, similarity)| TopicScore {
+                is_candidate: candidates.contains(&name),
+                name,
+                similarity,
+            })
+            .collect();
+
+        for score in &ranked {
+            debug!(target: TARGET_LLM_REQUEST, "[{} {} {}]: prefilter topic '{}' similarity={} threshold={:.3} candidate={}.",
+                worker_detail.name, worker_detail.id, worker_detail.model, score.name,
+                score.similarity.map_or("n/a".to_string(), |s| format!("{:.3}", s)),
+                self.min_similarity, score.is_candidate);
+
+            let mut stats = TOPIC_STATS.entry(score.name.clone()).or_default();
+            if let Some(similarity) = score.similarity {
+                stats.scored += 1;
+                stats.similarity_sum += similarity as f64;
+            }
+            if score.is_candidate {
+                stats.candidates += 1;
+            } else {
+                stats.skipped += 1;
+            }
+        }
+
+        if ARTICLES_SCORED.fetch_add(1, Ordering::Relaxed) % STATS_LOG_INTERVAL
+            == STATS_LOG_INTERVAL - 1
+        {
+            self.log_stats();
+        }
+
+        ranked
+    }
+
+    /// Records the LLM's answer for a topic so that recall can be estimated.
+    ///
+    /// Misses are only observable on audited articles: a skipped topic that the LLM
+    /// confirms is a prefilter miss, a candidate that the LLM confirms is a hit.
+    pub fn record_outcome(
+        &self,
+        topic_name: &str,
+        is_candidate: bool,
+        audited: bool,
+        llm_yes: bool,
+    ) {
+        let mut stats = TOPIC_STATS.entry(topic_name.to_string()).or_default();
+        if llm_yes && is_candidate {
+            stats.llm_yes += 1;
+        }
+        if audited && llm_yes {
+            if is_candidate {
+                stats.audit_hits += 1;
+            } else {
+                stats.audit_misses += 1;
+                warn!(target: TARGET_LLM_REQUEST, "Topic prefilter miss: '{}' was skipped but confirmed by the LLM during audit.", topic_name);
+            }
+        }
+    }
+
+    /// Logs per-topic candidate rates, mean similarity and estimated recall.
+    pub fn log_stats(&self) {
+        for topic in &self.topics {
+            if let Some(stats) = TOPIC_STATS.get(&topic.name) {
+                let mean_similarity = if stats.scored > 0 {
+                    stats.similarity_sum / stats.scored as f64
+                } else {
+                    0.0
+                };
+                let audited = stats.audit_hits + stats.audit_misses;
+                let recall = if audited > 0 {
+                    format!("{:.2}", stats.audit_hits as f64 / audited as f64)
+                } else {
+                    "n/a".to_string()
+                };
+                info!(target: TARGET_LLM_REQUEST,
+                    "Topic prefilter stats '{}': candidates={} skipped={} llm_yes={} mean_similarity={:.3} threshold={:.3} top_k={} recall={} (audit hits={} misses={})",
+                    topic.name, stats.candidates, stats.skipped, stats.llm_yes, mean_similarity,
+                    self.min_similarity, self.top_k, recall, stats.audit_hits, stats.audit_misses);
+            }
+        }
+    }
+}
+
+/// Builds the text embedded for an article: the title plus the first paragraphs.
+fn prefilter_text(article_title: &str, article_text: &str) -> String {
+    let body = article_text
+        .split_once("Body:")
+        .map(|(_, body)| body)
+        .unwrap_or(article_text)
+        .trim();
+    let lede: String = body.chars().take(PREFILTER_TEXT_CHARS).collect();
+    format!("{}\n{}", article_title.trim(), lede)
+}
+
+/// Sorts `scores` by descending similarity and returns the names of the candidate topics.
+///
+/// Candidates are the `top_k` highest-scoring topics at or above `min_similarity`. Topics
+/// without a score (their description could not be embedded) are always candidates.
+fn select_candidates(
+    scores: &mut [(String, Option<f32>)],
+    top_k: usize,
+    min_similarity: f32,
+) -> HashSet<String> {
+    scores.sort_by(|a, b| {
+        b.1.unwrap_or(f32::INFINITY)
+            .partial_cmp(&a.1.unwrap_or(f32::INFINITY))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut candidates = HashSet::new();
+    let mut taken = 0;
+    for (name, similarity) in scores.iter() {
+        match similarity {
+            None => {
+                candidates.insert(name.clone());
+            }
+            Some(similarity) if taken < top_k && *similarity >= min_similarity => {
+                candidates.insert(name.clone());
+                taken += 1;
+            }
+            Some(_) => {}
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_candidates_top_k_and_threshold() {
+        let mut scores = vec![
+            ("Space".to_string(), Some(0.78)),
+            ("Rust".to_string(), Some(0.81)),
+            ("EVs".to_string(), Some(0.74)),
+            ("Apple".to_string(), Some(0.69)),
+        ];
+        let candidates = select_candidates(&mut scores, 2, 0.70);
+        assert_eq!(scores[0].0, "Rust");
+        assert!(candidates.contains("Rust"));
+        assert!(candidates.contains("Space"));
+        assert!(!candidates.contains("EVs"));
+
+        let candidates = select_candidates(&mut scores, 10, 0.75);
+        assert_eq!(candidates.len(), 2);
+    }
+
+    #[test]
+    fn test_select_candidates_keeps_unscored_topics() {
+        let mut scores = vec![
+            ("Space".to_string(), Some(0.78)),
+            ("Tuscany".to_string(), None),
+        ];
+        let candidates = select_candidates(&mut scores, 0, 0.0);
+        assert_eq!(candidates.len(), 1);
+        assert!(candidates.contains("Tuscany"));
+    }
+
+    #[test]
+    fn test_prefilter_text_uses_title_and_lede() {
+        let text = format!("Title: Ignored\nBody: {}", "a".repeat(5000));
+        let passage = prefilter_text("Headline", &text);
+        assert!(passage.starts_with("Headline\n"));
+        assert_eq!(passage.len(), "Headline\n".len() + PREFILTER_TEXT_CHARS);
+    }
+}
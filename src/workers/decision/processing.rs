@@ -4,14 +4,22 @@ use tracing::{debug, error, info, warn};
 use url::Url;
 
 // No need to import Database, we use it through params
+use crate::language::detect_language;
 use crate::llm::generate_llm_response;
+use crate::metrics;
 use crate::prompt;
+use crate::topics::{self, TopicMatch};
 use crate::util::weighted_sleep;
-use crate::workers::common::{extract_llm_params, FeedItem, ProcessItemParams};
+use crate::workers::common::{
+    extract_llm_params, find_near_duplicate, record_near_duplicate, store_fingerprint, FeedItem,
+    ProcessItemParams,
+};
 use crate::{WorkerDetail, TARGET_DB, TARGET_LLM_REQUEST};
 
 use super::extraction::{extract_article_text, handle_access_denied};
+use super::prefilter::TopicPrefilter;
 use super::threat::{article_is_relevant, check_if_threat_at_all, determine_threat_location};
+use super::translation::translate_article;
 
 /// Processes a single feed item, determining if it's a threat or matches any topics.
 pub async fn process_item(
@@ -81,26 +89,55 @@ pub async fn process_item(
                 return;
             }
 
+            let language = detect_language(&article_text);
+
+            // Syndicated copies of an article that was already processed are linked to it
+            // instead of going through the LLM again
+            if let Some(duplicate) = find_near_duplicate(params.db, &article_text).await {
+                record_near_duplicate(
+                    params.db,
+                    &article_url,
+                    duplicate,
+                    &article_hash,
+                    &title_domain_hash,
+                    item.pub_date.as_deref(),
+                    language.as_deref(),
+                )
+                .await;
+                return;
+            }
+
+            // Non-English articles are optionally translated; the decision prompts and the
+            // analysis use the translation while the original is kept alongside it
+            let translation =
+                translate_article(&article_text, language.as_deref(), params, worker_detail).await;
+            let original_text = translation.as_ref().map(|_| article_text.as_str());
+            let decision_text = translation.as_deref().unwrap_or(&article_text);
+
             let places = params.places.clone();
 
             // First check if it's a threat - this takes priority
-            if check_if_threat_at_all(&article_text, params, &worker_detail).await {
+            if check_if_threat_at_all(decision_text, params, &worker_detail).await {
                 let threat =
-                    determine_threat_location(&article_text, places, params, &worker_detail).await;
+                    determine_threat_location(decision_text, places, params, &worker_detail).await;
 
-                if !threat.is_empty() {
+                if let Some((threat, details)) = threat {
+                    metrics::record_article_decided("life_safety");
                     // Add to life safety queue if it's a threat
                     params
                         .db
                         .add_to_life_safety_queue(
                             &threat,
+                            &details,
                             &article_url,
                             &article_title,
-                            &article_text,
+                            decision_text,
                             &article_html,
                             &article_hash,
                             &title_domain_hash,
                             item.pub_date.as_deref(),
+                            language.as_deref(),
+                            original_text,
                         )
                         .await
                         .unwrap_or_else(|e| {
@@ -112,13 +149,15 @@ pub async fn process_item(
                 } else {
                     // If not a valid threat, process normally for topics
                     process_topics(
-                        &article_text,
+                        decision_text,
                         &article_url,
                         &article_title,
                         &article_hash,
                         &title_domain_hash,
                         &article_html,
                         item.pub_date.as_deref(),
+                        language.as_deref(),
+                        original_text,
                         params,
                         worker_detail,
                     )
@@ -127,13 +166,15 @@ pub async fn process_item(
             } else {
                 // Not a threat, process for topics
                 process_topics(
-                    &article_text,
+                    decision_text,
                     &article_url,
                     &article_title,
                     &article_hash,
                     &title_domain_hash,
                     &article_html,
                     item.pub_date.as_deref(),
+                    language.as_deref(),
+                    original_text,
                     params,
                     worker_detail,
                 )
@@ -165,14 +206,21 @@ async fn process_topics(
     title_domain_hash: &str,
     article_html: &str,
     pub_date: Option<&str>,
+    language: Option<&str>,
+    original_text: Option<&str>,
     params: &mut ProcessItemParams<'_>,
     worker_detail: &WorkerDetail,
 ) {
     // Early check to filter promotional content
     let promo_check_prompt = prompt::filter_promotional_content(article_text);
     let llm_params = extract_llm_params(params);
-    if let Some(promo_response) =
-        generate_llm_response(&promo_check_prompt, &llm_params, worker_detail).await
+    if let Some(promo_response) = generate_llm_response(
+        &promo_check_prompt,
+        &llm_params,
+        worker_detail,
+        "promo_check",
+    )
+    .await
     {
         if promo_response.trim().to_lowercase().starts_with("yes") {
             // This is a promotional article, skip further processing
@@ -180,7 +228,7 @@ async fn process_topics(
                    worker_detail.name, worker_detail.id, worker_detail.model);
 
             // Add to database as non-relevant
-            let _ = params
+            if let Ok(article_id) = params
                 .db
                 .add_article(
                     article_url,
@@ -192,25 +240,43 @@ async fn process_topics(
                     Some(title_domain_hash),
                     None,
                     pub_date,
-                    None, // event_date
+                    None,     // event_date
+                    language, // language
                 )
-                .await;
+                .await
+            {
+                store_fingerprint(params.db, article_id, original_text.unwrap_or(article_text))
+                    .await;
+            }
 
             return;
         }
     }
 
-    let mut article_relevant = false;
+    // Rank topics by embedding similarity so only the likely ones reach the LLM
+    let prefilter = TopicPrefilter::instance(params.topics).await;
+    let ranked = prefilter
+        .rank(article_title, article_text, worker_detail)
+        .await;
+    let audited = prefilter.should_audit();
+
+    // Every topic is checked so that an article can be stored and delivered under all the
+    // topics it matches; the summary used for confirmation is only generated once.
+    let mut matches: Vec<TopicMatch> = Vec::new();
+    let mut summary: Option<String> = None;
 
-    for topic in params.topics {
-        let parts: Vec<_> = topic.trim().split(':').collect();
-        if parts.len() < 2 {
+    for score in ranked {
+        if !score.is_candidate && !audited {
             continue;
         }
-        let topic_name = parts[0].trim();
-        let topic_prompt = parts[1].trim();
+        let Some(topic) = params.topics.iter().find(|topic| topic.name == score.name) else {
+            continue;
+        };
+        let topic_name = topic.name.as_str();
+        let topic_prompt = topic.description.as_str();
 
-        if topic_name.is_empty() || topic_prompt.is_empty() {
+        if let Some(reason) = topic.prefilter_rejection(article_text, language) {
+            debug!(target: TARGET_LLM_REQUEST, "[{} {} {}]: not asking about '{}': {}.", worker_detail.name, worker_detail.id, worker_detail.model, topic_name, reason);
             continue;
         }
 
@@ -219,53 +285,23 @@ async fn process_topics(
         let yes_no_prompt = prompt::is_this_about(article_text, topic_prompt);
         let mut llm_params = extract_llm_params(params);
         if let Some(yes_no_response) =
-            generate_llm_response(&yes_no_prompt, &llm_params, worker_detail).await
+            generate_llm_response(&yes_no_prompt, &llm_params, worker_detail, "is_this_about").await
         {
-            if yes_no_response.trim().to_lowercase().starts_with("yes") {
-                // Article is relevant to the topic
-                article_relevant = true;
-
-                // Perform a secondary check before posting to Slack
-                if params.db.has_hash(article_hash).await.unwrap_or(false) {
-                    info!(
-                        target: TARGET_LLM_REQUEST,
-                        "Article with hash {} was already processed (second check), skipping topic '{}'.",
-                        article_hash,
-                        topic_name
-                    );
-                    continue; // Skip to the next topic
-                }
-
+            let said_yes = yes_no_response.trim().to_lowercase().starts_with("yes");
+            prefilter.record_outcome(topic_name, score.is_candidate, audited, said_yes);
+            if said_yes {
                 if article_is_relevant(
                     article_text,
                     topic_prompt,
                     pub_date,
+                    &mut summary,
                     &mut llm_params,
                     worker_detail,
                 )
                 .await
                 {
-                    // Add to matched topics queue
-                    if let Err(e) = params
-                        .db
-                        .add_to_matched_topics_queue(
-                            article_text,
-                            article_html,
-                            article_url,
-                            article_title,
-                            article_hash,
-                            title_domain_hash,
-                            topic_name,
-                            pub_date,
-                        )
-                        .await
-                    {
-                        error!(target: TARGET_LLM_REQUEST, "[{} {} {}]: failed to add to Matched Topics queue: {}: [{:?}].", worker_detail.name, worker_detail.id, worker_detail.model, topic_name, e);
-                    } else {
-                        debug!(target: TARGET_LLM_REQUEST, "[{} {} {}]: added to Matched Topics queue: {}.", worker_detail.name, worker_detail.id, worker_detail.model, topic_name);
-                    }
-
-                    return; // No need to continue checking other topics
+                    debug!(target: TARGET_LLM_REQUEST, "[{} {} {}]: confirmed topic '{}'.", worker_detail.name, worker_detail.id, worker_detail.model, topic_name);
+                    matches.push(TopicMatch::confirmed(topic_name, score.similarity));
                 } else {
                     debug!(target: TARGET_LLM_REQUEST, "[{} {} {}]: not about '{}' or is promotional.", worker_detail.name, worker_detail.id, worker_detail.model, topic_name);
                     weighted_sleep().await;
@@ -277,8 +313,51 @@ async fn process_topics(
         }
     }
 
+    let article_relevant = !matches.is_empty();
+    if article_relevant {
+        // Perform a secondary check before queueing, another worker may have finished it
+        if params.db.has_hash(article_hash).await.unwrap_or(false) {
+            info!(
+                target: TARGET_LLM_REQUEST,
+                "Article with hash {} was already processed (second check), skipping.",
+                article_hash
+            );
+            return;
+        }
+
+        // The most confident match is the primary topic, stored as the article category
+        topics::sort_by_confidence(&mut matches);
+        let primary_topic = matches[0].topic.as_str();
+        metrics::record_article_decided(primary_topic);
+        let matched_topics = serde_json::to_string(&matches).unwrap_or_default();
+
+        if let Err(e) = params
+            .db
+            .add_to_matched_topics_queue(
+                article_text,
+                article_html,
+                article_url,
+                article_title,
+                article_hash,
+                title_domain_hash,
+                primary_topic,
+                &matched_topics,
+                pub_date,
+                language,
+                original_text,
+            )
+            .await
+        {
+            error!(target: TARGET_LLM_REQUEST, "[{} {} {}]: failed to add to Matched Topics queue: {}: [{:?}].", worker_detail.name, worker_detail.id, worker_detail.model, matched_topics, e);
+        } else {
+            debug!(target: TARGET_LLM_REQUEST, "[{} {} {}]: added to Matched Topics queue: {}.", worker_detail.name, worker_detail.id, worker_detail.model, matched_topics);
+        }
+        return;
+    }
+
     // If no relevant topic was found, add the URL to the database as a non-relevant article
     if !article_relevant {
+        metrics::record_article_decided("none");
         match params
             .db
             .add_article(
@@ -291,11 +370,14 @@ async fn process_topics(
                 Some(title_domain_hash),
                 None,
                 pub_date,
-                None, // event_date
+                None,     // event_date
+                language, // language
             )
             .await
         {
-            Ok(_) => {
+            Ok(article_id) => {
+                store_fingerprint(params.db, article_id, original_text.unwrap_or(article_text))
+                    .await;
                 debug!(target: TARGET_DB, "[{} {} {}]: added non-relevant article to database.", worker_detail.name, worker_detail.id, worker_detail.model);
             }
             Err(e) => {
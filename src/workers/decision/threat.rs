@@ -1,7 +1,9 @@
 use crate::llm::generate_llm_response;
 use crate::prompt;
+use crate::threat::ThreatDetails;
 use crate::workers::common::extract_llm_params;
 use crate::{LLMParams, WorkerDetail, TARGET_LLM_REQUEST};
+use chrono::Utc;
 use std::collections::BTreeMap;
 use tracing::{debug, info};
 
@@ -17,15 +19,21 @@ pub async fn check_if_threat_at_all(
     let threat_prompt = prompt::threat_prompt(article_text);
     debug!(target: TARGET_LLM_REQUEST, "[{} {} {}]: asking LLM if about something affecting life or safety.", worker_detail.name, worker_detail.id, worker_detail.model);
 
-    if let Some(response) = generate_llm_response(&threat_prompt, &llm_params, worker_detail).await
+    if let Some(response) =
+        generate_llm_response(&threat_prompt, &llm_params, worker_detail, "threat").await
     {
         if response.trim().to_lowercase().starts_with("yes") {
             // Confirmation check
             let confirm_prompt = prompt::confirm_threat_prompt(article_text);
             debug!(target: TARGET_LLM_REQUEST, "[{} {} {}]: confirming if genuine threat to life or safety.", worker_detail.name, worker_detail.id, worker_detail.model);
 
-            if let Some(confirm_response) =
-                generate_llm_response(&confirm_prompt, &llm_params, worker_detail).await
+            if let Some(confirm_response) = generate_llm_response(
+                &confirm_prompt,
+                &llm_params,
+                worker_detail,
+                "confirm_threat",
+            )
+            .await
             {
                 return confirm_response.trim().to_lowercase().starts_with("yes");
             }
@@ -35,13 +43,14 @@ pub async fn check_if_threat_at_all(
 }
 
 /// Processes the places mentioned in the article text and determines affected locations.
-/// Returns JSON string of impacted regions if threat detected, empty string otherwise.
+/// Returns the JSON string of impacted regions and the threat's details if a current threat
+/// is detected near any known place, `None` otherwise.
 pub async fn determine_threat_location(
     article_text: &str,
     places: BTreeMap<String, BTreeMap<String, Vec<String>>>,
     params: &crate::workers::common::ProcessItemParams<'_>,
     worker_detail: &WorkerDetail,
-) -> String {
+) -> Option<(String, ThreatDetails)> {
     // Generate the prompt based on the article text and places hierarchy
     let threat_locations_prompt = prompt::threat_locations(article_text, &places);
     debug!(
@@ -57,8 +66,13 @@ pub async fn determine_threat_location(
     let mut json_llm_params = llm_params.clone();
     json_llm_params.json_format = Some(crate::JsonSchemaType::ThreatLocation);
 
-    if let Some(response) =
-        generate_llm_response(&threat_locations_prompt, &json_llm_params, worker_detail).await
+    if let Some(response) = generate_llm_response(
+        &threat_locations_prompt,
+        &json_llm_params,
+        worker_detail,
+        "threat_locations",
+    )
+    .await
     {
         info!("initial response: {}", response);
         let trimmed_response = response.trim();
@@ -80,7 +94,11 @@ pub async fn determine_threat_location(
                             })
                     })
                 }) {
-                    return trimmed_response.to_string();
+                    if json_response.details.is_expired(Utc::now()) {
+                        info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: threat ended at {:?}, not alerting.", worker_detail.name, worker_detail.id, worker_detail.model, json_response.details.expires_at());
+                        return None;
+                    }
+                    return Some((trimmed_response.to_string(), json_response.details));
                 }
             }
             Err(e) => {
@@ -89,15 +107,19 @@ pub async fn determine_threat_location(
         }
     }
 
-    // If no region is impacted, return an empty string
-    "".to_string()
+    // No region is impacted
+    None
 }
 
-/// Checks if the article is relevant to the given topic
+/// Checks if the article is relevant to the given topic.
+///
+/// The article summary is generated on the first call and kept in `summary` so that
+/// confirming further topics for the same article only costs one LLM request each.
 pub async fn article_is_relevant(
     article_text: &str,
     topic_prompt: &str,
     pub_date: Option<&str>,
+    summary: &mut Option<String>,
     llm_params: &mut LLMParams,
     worker_detail: &WorkerDetail,
 ) -> bool {
@@ -112,15 +134,20 @@ pub async fn article_is_relevant(
     }
 
     // Generate summary
-    let summary_prompt = prompt::summary_prompt(article_text, pub_date);
-    let summary_response = generate_llm_response(&summary_prompt, llm_params, worker_detail)
-        .await
-        .unwrap_or_default();
+    if summary.is_none() {
+        let summary_prompt = prompt::summary_prompt(article_text, pub_date);
+        *summary = Some(
+            generate_llm_response(&summary_prompt, llm_params, worker_detail, "summary")
+                .await
+                .unwrap_or_default(),
+        );
+    }
+    let summary_response = summary.as_deref().unwrap_or_default();
 
     // Confirm the article relevance
-    let confirm_prompt = prompt::confirm_prompt(&summary_response, topic_prompt);
+    let confirm_prompt = prompt::confirm_prompt(summary_response, topic_prompt);
     if let Some(confirm_response) =
-        generate_llm_response(&confirm_prompt, llm_params, worker_detail).await
+        generate_llm_response(&confirm_prompt, llm_params, worker_detail, "confirm").await
     {
         if confirm_response.trim().to_lowercase().starts_with("yes") {
             return true;
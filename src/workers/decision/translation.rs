@@ -0,0 +1,43 @@
+use tracing::{debug, warn};
+
+use crate::language::{language_name, needs_translation};
+use crate::llm::generate_llm_response;
+use crate::prompt;
+use crate::workers::common::{extract_llm_params, ProcessItemParams};
+use crate::{WorkerDetail, TARGET_LLM_REQUEST};
+
+/// Translates a non-English article into English for the decision prompts.
+///
+/// Returns `None` when translation is disabled (`TRANSLATE_ARTICLES`), the article is
+/// already English or its language is unknown, or the LLM gives no usable answer. In
+/// every case the caller falls back to the original text.
+pub async fn translate_article(
+    article_text: &str,
+    language: Option<&str>,
+    params: &ProcessItemParams<'_>,
+    worker_detail: &WorkerDetail,
+) -> Option<String> {
+    if !needs_translation(language) {
+        return None;
+    }
+    let source_language = language_name(language?);
+
+    debug!(target: TARGET_LLM_REQUEST, "[{} {} {}]: translating {} article to English.", worker_detail.name, worker_detail.id, worker_detail.model, source_language);
+
+    let llm_params = extract_llm_params(params);
+    let translation_prompt = prompt::translation_prompt(article_text, &source_language);
+    match generate_llm_response(
+        &translation_prompt,
+        &llm_params,
+        worker_detail,
+        "translation",
+    )
+    .await
+    {
+        Some(translation) if !translation.trim().is_empty() => Some(translation.trim().to_string()),
+        _ => {
+            warn!(target: TARGET_LLM_REQUEST, "[{} {} {}]: failed to translate {} article, using original text.", worker_detail.name, worker_detail.id, worker_detail.model, source_language);
+            None
+        }
+    }
+}
@@ -5,8 +5,10 @@ use tokio::time::{sleep, Duration};
 use tracing::{debug, error, info};
 
 use crate::db::core::Database;
-use crate::util::parse_places_data_hierarchical;
+use crate::places;
+use crate::topics::Topic;
 use crate::workers::common::{build_connection_info, ProcessItemParams};
+use crate::workers::status::{self, WorkerMode};
 use crate::{LLMClient, WorkerDetail, TARGET_LLM_REQUEST};
 
 use super::processing::process_item;
@@ -14,7 +16,7 @@ use super::processing::process_item;
 /// Main decision worker loop that continuously processes items from the RSS queue
 pub async fn decision_loop(
     worker_id: i16,
-    topics: &[String],
+    topics: &[Topic],
     llm_client: &LLMClient,
     model: &str,
     temperature: f32,
@@ -36,14 +38,11 @@ pub async fn decision_loop(
     };
 
     info!(target: TARGET_LLM_REQUEST, "[{} {} {}]: starting decision_loop using {:?}.", worker_detail.name, worker_detail.id, worker_detail.model, llm_client);
-
-    // Each decision_worker loads places before entering the main loop.
-    let places = match parse_places_data_hierarchical() {
-        Ok(hierarchy) => hierarchy,
-        Err(err) => panic!("Error: {}", err),
-    };
+    status::register(&worker_detail, WorkerMode::Decision);
 
     loop {
+        status::finish_article(&worker_detail);
+
         // Determine which article to select next: 30% of the time select the newest
         // (latest news), 25% oldest (stale queue), 45% random.
         let roll = rng.random_range(0..=99); // Updated to avoid deprecation warning
@@ -92,6 +91,7 @@ pub async fn decision_loop(
                             None,
                             pub_date.as_deref(),
                             None, // event_date
+                            None, // language
                         )
                         .await;
                     continue;
@@ -105,7 +105,8 @@ pub async fn decision_loop(
                     pub_date,
                 };
 
-                let places_clone = places.clone();
+                // Places are re-read for each item so registry changes apply without a restart
+                let places_clone = places::current(db).await.hierarchical.clone();
 
                 let mut params = ProcessItemParams {
                     topics,
@@ -118,6 +119,7 @@ pub async fn decision_loop(
                     places: places_clone,
                 };
 
+                status::start_article(&worker_detail, &item.url);
                 process_item(item, &mut params, &worker_detail).await;
             }
             Ok(None) => {
@@ -1,6 +1,7 @@
 pub mod analysis;
 pub mod common;
 pub mod decision;
+pub mod status;
 
 // Common re-exports
 pub use common::{extract_llm_params, FeedItem, ProcessItemParams};
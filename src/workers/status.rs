@@ -0,0 +1,180 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::Mutex;
+
+use crate::WorkerDetail;
+
+const WORKER_STUCK_SECONDS_ENV: &str = "WORKER_STUCK_SECONDS";
+/// A worker that hasn't reported progress for this long is considered stuck.
+const DEFAULT_WORKER_STUCK_SECONDS: i64 = 15 * 60;
+
+/// What a worker is currently doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerMode {
+    /// A decision worker filtering articles from the RSS queue
+    Decision,
+    /// An analysis worker analyzing matched and life safety articles
+    Analysis,
+    /// An idle analysis worker helping out with decisions on its fallback model
+    FallbackDecision,
+}
+
+/// The last reported state of a worker.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerStatus {
+    pub name: String,
+    pub id: i16,
+    pub mode: WorkerMode,
+    pub model: String,
+    /// The article being processed, if any
+    pub current_article: Option<String>,
+    pub article_started_at: Option<DateTime<Utc>>,
+    /// When the worker last reported progress
+    pub last_activity: DateTime<Utc>,
+}
+
+impl WorkerStatus {
+    /// Whether the worker hasn't reported progress for longer than `stuck_after` seconds.
+    pub fn is_stuck(&self, now: DateTime<Utc>, stuck_after: i64) -> bool {
+        (now - self.last_activity).num_seconds() > stuck_after
+    }
+}
+
+/// Workers by name and ID; analysis and decision worker IDs overlap.
+static REGISTRY: Lazy<Mutex<BTreeMap<(String, i16), WorkerStatus>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+fn update(worker_detail: &WorkerDetail, apply: impl FnOnce(&mut WorkerStatus)) {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(status) = registry.get_mut(&(worker_detail.name.clone(), worker_detail.id)) {
+        status.model = worker_detail.model.clone();
+        status.last_activity = Utc::now();
+        apply(status);
+    }
+}
+
+/// Adds a worker to the registry, or resets it if it was restarted.
+pub fn register(worker_detail: &WorkerDetail, mode: WorkerMode) {
+    let status = WorkerStatus {
+        name: worker_detail.name.clone(),
+        id: worker_detail.id,
+        mode,
+        model: worker_detail.model.clone(),
+        current_article: None,
+        article_started_at: None,
+        last_activity: Utc::now(),
+    };
+    REGISTRY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert((worker_detail.name.clone(), worker_detail.id), status);
+}
+
+/// Records a switch between analysis and fallback decision mode, and the model used.
+pub fn set_mode(worker_detail: &WorkerDetail, mode: WorkerMode) {
+    update(worker_detail, |status| status.mode = mode);
+}
+
+/// Records that the worker started processing an article.
+pub fn start_article(worker_detail: &WorkerDetail, url: &str) {
+    update(worker_detail, |status| {
+        status.current_article = Some(url.to_string());
+        status.article_started_at = Some(Utc::now());
+    });
+}
+
+/// Records that the worker is done with its article, or is alive while idle.
+pub fn finish_article(worker_detail: &WorkerDetail) {
+    update(worker_detail, |status| {
+        status.current_article = None;
+        status.article_started_at = None;
+    });
+}
+
+/// Returns the status of every registered worker.
+pub fn snapshot() -> Vec<WorkerStatus> {
+    REGISTRY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect()
+}
+
+/// Seconds without progress after which a worker is reported stuck, from
+/// `WORKER_STUCK_SECONDS`.
+pub fn stuck_threshold() -> i64 {
+    env::var(WORKER_STUCK_SECONDS_ENV)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|seconds| *seconds > 0)
+        .unwrap_or(DEFAULT_WORKER_STUCK_SECONDS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worker(name: &str, model: &str) -> WorkerDetail {
+        WorkerDetail {
+            name: name.to_string(),
+            id: 0,
+            model: model.to_string(),
+            connection_info: String::new(),
+        }
+    }
+
+    fn status_of(worker_detail: &WorkerDetail) -> WorkerStatus {
+        snapshot()
+            .into_iter()
+            .find(|status| status.name == worker_detail.name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_registry_updates() {
+        let mut detail = worker("status test worker", "analysis-model");
+        register(&detail, WorkerMode::Analysis);
+
+        start_article(&detail, "https://example.com/story");
+        let status = status_of(&detail);
+        assert_eq!(status.mode, WorkerMode::Analysis);
+        assert_eq!(
+            status.current_article.as_deref(),
+            Some("https://example.com/story")
+        );
+        assert!(status.article_started_at.is_some());
+
+        // Switching to the fallback picks up the fallback model
+        detail.model = "fallback-model".to_string();
+        set_mode(&detail, WorkerMode::FallbackDecision);
+        finish_article(&detail);
+        let status = status_of(&detail);
+        assert_eq!(status.mode, WorkerMode::FallbackDecision);
+        assert_eq!(status.model, "fallback-model");
+        assert!(status.current_article.is_none());
+
+        // Unregistered workers are ignored
+        start_article(
+            &worker("unregistered worker", "model"),
+            "https://example.com",
+        );
+        assert!(!snapshot()
+            .iter()
+            .any(|status| status.name == "unregistered worker"));
+    }
+
+    #[test]
+    fn test_is_stuck() {
+        let detail = worker("stuck test worker", "model");
+        register(&detail, WorkerMode::Decision);
+        let status = status_of(&detail);
+        let now = status.last_activity;
+        assert!(!status.is_stuck(now + chrono::Duration::seconds(60), 900));
+        assert!(status.is_stuck(now + chrono::Duration::seconds(901), 900));
+    }
+}